@@ -0,0 +1,140 @@
+//! Measures how many streamed completion chunks per second are parsed from a local server.
+//!
+//! `transport` only reads the body and `string_lines` parses it the way streams were parsed
+//! before, with a `String` for every line and event, as baselines for `completion_chunks`.
+//!
+//! Run with `cargo bench --bench stream_throughput`
+use std::{fmt::Write, net::SocketAddr, sync::Arc};
+
+use async_openai::{
+    types::{CreateCompletionRequestArgs, CreateCompletionResponse},
+    Client,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::StreamExt;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+const CHUNKS: usize = 10_000;
+
+fn http_response(chunks: usize) -> Vec<u8> {
+    let mut response = String::from(
+        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n",
+    );
+    for i in 0..chunks {
+        write!(
+            response,
+            "data: {{\"id\":\"cmpl-1\",\"object\":\"text_completion\",\"created\":1,\"model\":\"text-davinci-003\",\"choices\":[{{\"text\":\" token{i}\",\"index\":0,\"logprobs\":null,\"finish_reason\":null}}]}}\n\n"
+        )
+        .unwrap();
+    }
+    response.push_str("data: [DONE]\n\n");
+    response.into_bytes()
+}
+
+/// Answer every connection with `response`
+async fn serve(response: Vec<u8>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let response = Arc::new(response);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let response = response.clone();
+            tokio::spawn(async move {
+                let mut request = vec![0; 64 * 1024];
+                let _ = socket.read(&mut request).await.unwrap();
+                socket.write_all(&response).await.unwrap();
+            });
+        }
+    });
+
+    addr
+}
+
+/// Parse the events of `response` with a `String` for every line, data field and event
+async fn string_lines(response: reqwest::Response) -> usize {
+    let mut body = response.bytes_stream();
+    let (mut line, mut data) = (Vec::new(), Vec::<String>::new());
+    let mut received = 0;
+    while let Some(chunk) = body.next().await {
+        for &byte in chunk.unwrap().iter() {
+            if byte != b'\n' {
+                line.push(byte);
+                continue;
+            }
+            let text = String::from_utf8_lossy(&line).into_owned();
+            if text.is_empty() && !data.is_empty() {
+                let event = data.join("\n");
+                data.clear();
+                if event != "[DONE]" {
+                    serde_json::from_str::<CreateCompletionResponse>(&event).unwrap();
+                    received += 1;
+                }
+            } else if let Some(value) = text.strip_prefix("data: ") {
+                data.push(value.to_string());
+            }
+            line.clear();
+        }
+    }
+    received
+}
+
+fn stream_throughput(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let addr = runtime.block_on(serve(http_response(CHUNKS)));
+
+    let client = Client::new()
+        .with_api_key("sk-bench")
+        .with_api_base(format!("http://{addr}/v1"));
+
+    let request = CreateCompletionRequestArgs::default()
+        .model("text-davinci-003")
+        .prompt("Count")
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("stream_throughput");
+    group.throughput(Throughput::Elements(CHUNKS as u64));
+    group.sample_size(10);
+    let url = format!("http://{addr}/v1/completions");
+    let http_client = reqwest::Client::new();
+    group.bench_function("transport", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let response = http_client.post(&url).send().await.unwrap();
+            let mut body = response.bytes_stream();
+            while let Some(chunk) = body.next().await {
+                chunk.unwrap();
+            }
+        })
+    });
+    group.bench_function("string_lines", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let response = http_client.post(&url).send().await.unwrap();
+            assert_eq!(string_lines(response).await, CHUNKS);
+        })
+    });
+    group.bench_function("completion_chunks", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let mut stream = client
+                .completions()
+                .create_stream(request.clone())
+                .await
+                .unwrap();
+
+            let mut received = 0;
+            while let Some(response) = stream.next().await {
+                response.unwrap();
+                received += 1;
+            }
+            assert_eq!(received, CHUNKS);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, stream_throughput);
+criterion_main!(benches);
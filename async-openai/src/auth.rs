@@ -0,0 +1,579 @@
+//! Credentials which are obtained at request time instead of being set on the client.
+use std::{fmt, sync::Arc};
+
+use crate::{error::OpenAIError, runtime::BoxFuture};
+
+/// Source of the bearer token sent with each request, consulted before every request and
+/// retry so that short-lived tokens can be refreshed. Set with [Client::with_auth_provider](crate::Client::with_auth_provider).
+///
+/// ```
+/// use async_openai::{error::OpenAIError, AuthProvider, Client};
+///
+/// #[derive(Debug)]
+/// struct Vault;
+///
+/// impl AuthProvider for Vault {
+///     fn token(&self) -> futures::future::BoxFuture<'_, Result<String, OpenAIError>> {
+///         Box::pin(async { Ok("sk-from-vault".to_string()) })
+///     }
+/// }
+///
+/// let client = Client::new().with_auth_provider(Vault);
+/// ```
+pub trait AuthProvider: fmt::Debug + Send + Sync {
+    /// The token to send in the `Authorization: Bearer` header. Implementations should cache
+    /// tokens, as this is called for every request.
+    fn token(&self) -> BoxFuture<'_, Result<String, OpenAIError>>;
+
+    /// Called with the status of each response to a request made with `token`, for streams once
+    /// their response headers are received, e.g. to stop handing out a key which is rate limited.
+    /// Does nothing by default.
+    fn on_response(&self, _token: &str, _status: reqwest::StatusCode) {}
+}
+
+/// Hook adding headers to fully built requests, see [Client::with_request_signer](crate::Client::with_request_signer).
+#[derive(Clone)]
+#[cfg_attr(not(feature = "_api"), allow(dead_code))]
+pub(crate) struct RequestSigner(Arc<SignFn>);
+
+type SignFn = dyn Fn(&mut reqwest::Request) -> Result<(), OpenAIError> + Send + Sync;
+
+impl RequestSigner {
+    pub(crate) fn new<F>(sign: F) -> Self
+    where
+        F: Fn(&mut reqwest::Request) -> Result<(), OpenAIError> + Send + Sync + 'static,
+    {
+        Self(Arc::new(sign))
+    }
+
+    #[cfg(feature = "_api")]
+    pub(crate) fn sign(&self, request: &mut reqwest::Request) -> Result<(), OpenAIError> {
+        (self.0)(request)
+    }
+}
+
+impl fmt::Debug for RequestSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestSigner")
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use pool::{KeyPool, KeyRotation};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod pool {
+    use std::{
+        fmt,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant},
+    };
+
+    use super::AuthProvider;
+    use crate::{error::OpenAIError, runtime::BoxFuture};
+
+    /// How long keys are benched after a 429 response, by default
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    /// Order in which a [KeyPool] hands out its keys.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum KeyRotation {
+        /// Each key in turn
+        #[default]
+        RoundRobin,
+        /// The key which was rate limited longest ago, or never
+        LeastRecentlyThrottled,
+    }
+
+    /// Rotates requests across several API keys, benching a key for a cooldown period when a
+    /// request made with it is rate limited or out of quota (`429 Too Many Requests`). With
+    /// retries, the retry of a rate limited request is made with another key.
+    ///
+    /// When all keys are benched, the one whose cooldown ends first is used.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use async_openai::{Client, KeyPool, KeyRotation};
+    ///
+    /// let pool = KeyPool::new(["sk-1", "sk-2", "sk-3"])
+    ///     .with_rotation(KeyRotation::LeastRecentlyThrottled)
+    ///     .with_cooldown(Duration::from_secs(30));
+    /// let client = Client::new().with_auth_provider(pool);
+    /// ```
+    pub struct KeyPool {
+        keys: Vec<String>,
+        rotation: KeyRotation,
+        cooldown: Duration,
+        next: AtomicUsize,
+        /// When each key was last throttled, and until when it is benched
+        throttled: Mutex<Vec<Option<(Instant, Instant)>>>,
+    }
+
+    impl fmt::Debug for KeyPool {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("KeyPool")
+                .field("keys", &self.keys.len())
+                .field("rotation", &self.rotation)
+                .field("cooldown", &self.cooldown)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl KeyPool {
+        pub fn new<I, S>(keys: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+            let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+            Self {
+                throttled: Mutex::new(vec![None; keys.len()]),
+                keys,
+                rotation: KeyRotation::default(),
+                cooldown: COOLDOWN,
+                next: AtomicUsize::new(0),
+            }
+        }
+
+        pub fn with_rotation(mut self, rotation: KeyRotation) -> Self {
+            self.rotation = rotation;
+            self
+        }
+
+        /// How long a key is benched after a 429 response, one minute by default
+        pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+            self.cooldown = cooldown;
+            self
+        }
+
+        /// Index of the key of the next request
+        fn pick(&self) -> Option<usize> {
+            let len = self.keys.len();
+            if len == 0 {
+                return None;
+            }
+            let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+            let now = Instant::now();
+            let throttled = self.throttled.lock().unwrap();
+
+            // Keys in round robin order from `start`
+            let order = (0..len).map(|i| (start + i) % len);
+            let mut available = order
+                .clone()
+                .filter(|&i| throttled[i].map_or(true, |(_, until)| until <= now));
+            let picked = match self.rotation {
+                KeyRotation::RoundRobin => available.next(),
+                KeyRotation::LeastRecentlyThrottled => {
+                    available.min_by_key(|&i| throttled[i].map(|(at, _)| at))
+                }
+            };
+            picked.or_else(|| order.min_by_key(|&i| throttled[i].map(|(_, until)| until)))
+        }
+    }
+
+    impl AuthProvider for KeyPool {
+        fn token(&self) -> BoxFuture<'_, Result<String, OpenAIError>> {
+            let key = self
+                .pick()
+                .map(|i| self.keys[i].clone())
+                .ok_or_else(|| OpenAIError::InvalidArgument("key pool has no keys".into()));
+            Box::pin(async move { key })
+        }
+
+        fn on_response(&self, token: &str, status: reqwest::StatusCode) {
+            if status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return;
+            }
+            if let Some(i) = self.keys.iter().position(|key| key == token) {
+                let now = Instant::now();
+                self.throttled.lock().unwrap()[i] = Some((now, now + self.cooldown));
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "azure-ad", not(target_arch = "wasm32")))]
+pub use azure::AzureAdCredential;
+
+#[cfg(all(feature = "azure-ad", not(target_arch = "wasm32")))]
+mod azure {
+    use std::{
+        fmt,
+        time::{Duration, Instant},
+    };
+
+    use futures::lock::Mutex;
+    use serde::Deserialize;
+
+    use super::AuthProvider;
+    use crate::{
+        config::HttpConfig,
+        error::{ApiError, OpenAIError},
+        runtime::BoxFuture,
+        util,
+    };
+
+    /// Default Azure AD authority
+    const AUTHORITY_HOST: &str = "https://login.microsoftonline.com";
+    /// Scope of Azure OpenAI Service
+    const COGNITIVE_SERVICES_SCOPE: &str = "https://cognitiveservices.azure.com/.default";
+    /// Tokens are refreshed this long before they expire
+    const EXPIRY_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+    /// Azure Active Directory tokens for Azure OpenAI Service, obtained with the client
+    /// credentials of a service principal and refreshed shortly before they expire.
+    ///
+    /// ```no_run
+    /// use async_openai::{AzureAdCredential, Client, RequestOptions};
+    ///
+    /// // Request tokens through the same connection pool as the API calls
+    /// let http_client = reqwest::Client::new();
+    /// let credential = AzureAdCredential::new("tenant-id", "client-id", "client-secret")
+    ///     .with_http_client(http_client.clone());
+    ///
+    /// let client = Client::new()
+    ///     .with_http_client(http_client)
+    ///     .with_api_base("https://my-resource.openai.azure.com/openai/deployments/my-deployment")
+    ///     .with_auth_provider(credential);
+    ///
+    /// let options = RequestOptions::new().with_extra_query("api-version", "2023-05-15");
+    /// let completions = client.completions().with_options(options);
+    /// ```
+    pub struct AzureAdCredential {
+        tenant_id: String,
+        client_id: String,
+        client_secret: Secret,
+        scope: String,
+        authority_host: String,
+        http_client: reqwest::Client,
+        /// Held while a token is being requested, so concurrent requests wait for it
+        token: Mutex<Option<(Secret, Instant)>>,
+    }
+
+    /// A client secret or token, redacted from `Debug` output.
+    #[derive(Clone, Deserialize)]
+    #[serde(transparent)]
+    struct Secret(String);
+
+    impl fmt::Debug for Secret {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("<redacted>")
+        }
+    }
+
+    impl fmt::Debug for AzureAdCredential {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("AzureAdCredential")
+                .field("tenant_id", &self.tenant_id)
+                .field("client_id", &self.client_id)
+                .field("client_secret", &self.client_secret)
+                .field("scope", &self.scope)
+                .field("authority_host", &self.authority_host)
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TokenResponse {
+        access_token: Secret,
+        expires_in: u64,
+    }
+
+    impl AzureAdCredential {
+        pub fn new<S: Into<String>>(tenant_id: S, client_id: S, client_secret: S) -> Self {
+            Self {
+                tenant_id: tenant_id.into(),
+                client_id: client_id.into(),
+                client_secret: Secret(client_secret.into()),
+                scope: COGNITIVE_SERVICES_SCOPE.to_string(),
+                authority_host: AUTHORITY_HOST.to_string(),
+                http_client: reqwest::Client::new(),
+                token: Mutex::new(None),
+            }
+        }
+
+        /// Scope of the requested tokens, by default that of Azure OpenAI Service
+        pub fn with_scope<S: Into<String>>(mut self, scope: S) -> Self {
+            self.scope = scope.into();
+            self
+        }
+
+        /// Authority of sovereign clouds, e.g. `https://login.microsoftonline.us`
+        pub fn with_authority_host<S: Into<String>>(mut self, authority_host: S) -> Self {
+            self.authority_host = authority_host.into();
+            self
+        }
+
+        /// Request tokens with `http_client`, e.g. that of the [Client](crate::Client) so they
+        /// share proxies, timeouts and the connection pool.
+        pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+            self.http_client = http_client;
+            self
+        }
+
+        /// Request tokens with a HTTP client built from the given connection settings.
+        ///
+        /// # Panics
+        ///
+        /// Like [reqwest::Client::new], panics if the TLS backend cannot be initialized.
+        pub fn with_http_config(self, config: &HttpConfig) -> Self {
+            let http_client = config
+                .client_builder()
+                .build()
+                .expect("failed to build HTTP client");
+            self.with_http_client(http_client)
+        }
+
+        async fn request_token(&self) -> Result<TokenResponse, OpenAIError> {
+            let response = self
+                .http_client
+                .post(format!(
+                    "{}/{}/oauth2/v2.0/token",
+                    self.authority_host, self.tenant_id
+                ))
+                .form(&[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", &self.client_id),
+                    ("client_secret", &self.client_secret.0),
+                    ("scope", &self.scope),
+                ])
+                .send()
+                .await?;
+
+            let status = response.status();
+            let bytes = response.bytes().await?;
+            if !status.is_success() {
+                // Azure AD errors are not OpenAI error objects, keep the whole body as the message
+                let error = util::deserialize::<serde_json::Value>(&bytes).ok();
+                return Err(OpenAIError::ApiError(ApiError {
+                    message: error
+                        .as_ref()
+                        .map_or_else(|| status.to_string(), |error| error.to_string()),
+                    r#type: "azure_ad_error".to_string(),
+                    param: None,
+                    code: error.and_then(|error| error.get("error").cloned()),
+                }));
+            }
+            util::deserialize(&bytes)
+        }
+    }
+
+    impl AuthProvider for AzureAdCredential {
+        fn token(&self) -> BoxFuture<'_, Result<String, OpenAIError>> {
+            Box::pin(async move {
+                let mut token = self.token.lock().await;
+                if let Some((access_token, expires_at)) = token.as_ref() {
+                    if Instant::now() + EXPIRY_MARGIN < *expires_at {
+                        return Ok(access_token.0.clone());
+                    }
+                }
+
+                let response = self.request_token().await?;
+                let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+                *token = Some((response.access_token.clone(), expires_at));
+                Ok(response.access_token.0)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "azure-ad", feature = "models", feature = "moderations"))]
+    use std::sync::{Arc, Mutex};
+
+    #[cfg(any(feature = "azure-ad", feature = "models", feature = "moderations"))]
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Responds to each request with `body`, recording the requests received.
+    #[cfg(any(feature = "azure-ad", feature = "models", feature = "moderations"))]
+    async fn serve(body: &'static str, requests: Arc<Mutex<Vec<String>>>) -> String {
+        serve_status("200 OK", body, requests).await
+    }
+
+    #[cfg(any(feature = "azure-ad", feature = "models", feature = "moderations"))]
+    async fn serve_status(
+        status: &'static str,
+        body: &'static str,
+        requests: Arc<Mutex<Vec<String>>>,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[cfg(feature = "models")]
+    #[tokio::test]
+    async fn test_auth_provider() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::AuthProvider;
+        use crate::{error::OpenAIError, runtime::BoxFuture, Client};
+
+        #[derive(Debug, Default)]
+        struct Counter(AtomicUsize);
+
+        impl AuthProvider for Counter {
+            fn token(&self) -> BoxFuture<'_, Result<String, OpenAIError>> {
+                let n = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+                Box::pin(async move { Ok(format!("token-{n}")) })
+            }
+        }
+
+        let requests = Arc::new(Mutex::new(vec![]));
+        let api_base = serve(r#"{"object":"list","data":[]}"#, requests.clone()).await;
+        let client = Client::new()
+            .with_api_key("sk-unused")
+            .with_api_base(api_base)
+            .with_auth_provider(Counter::default());
+
+        client.models().list().await.unwrap();
+        client.models().list().await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("authorization: bearer token-1\r\n"));
+        assert!(requests[1].contains("authorization: bearer token-2\r\n"));
+    }
+
+    #[cfg(feature = "moderations")]
+    #[tokio::test]
+    async fn test_request_signer() {
+        use crate::{types::CreateModerationRequestArgs, Client};
+
+        let requests = Arc::new(Mutex::new(vec![]));
+        let body = r#"{"id":"modr-1","model":"text-moderation-004","results":[]}"#;
+        let api_base = serve(body, requests.clone()).await;
+        let client = Client::new()
+            .with_api_base(api_base)
+            .with_request_signer(|request| {
+                let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+                let signature = format!(
+                    "{} {} {}",
+                    request.method(),
+                    request.url().path(),
+                    body.len()
+                );
+                request
+                    .headers_mut()
+                    .insert("x-signature", signature.parse().unwrap());
+                Ok(())
+            });
+
+        let request = CreateModerationRequestArgs::default()
+            .input("hello")
+            .build()
+            .unwrap();
+        let len = serde_json::to_vec(&request).unwrap().len();
+        client.moderations().create(request).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains(&format!("x-signature: post /moderations {len}\r\n")));
+    }
+
+    #[tokio::test]
+    async fn test_key_pool_rotation() {
+        use std::time::Duration;
+
+        use reqwest::StatusCode;
+
+        use super::{AuthProvider, KeyPool, KeyRotation};
+
+        let pool = KeyPool::new(["a", "b", "c"]);
+        let mut keys = vec![];
+        for _ in 0..4 {
+            keys.push(pool.token().await.unwrap());
+        }
+        assert_eq!(keys, ["a", "b", "c", "a"]);
+
+        // Benched keys are skipped, and used again after their cooldown
+        let pool = KeyPool::new(["a", "b"]).with_cooldown(Duration::from_millis(50));
+        pool.on_response("a", StatusCode::TOO_MANY_REQUESTS);
+        pool.on_response("b", StatusCode::OK);
+        assert_eq!(pool.token().await.unwrap(), "b");
+        assert_eq!(pool.token().await.unwrap(), "b");
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(pool.token().await.unwrap(), "a");
+
+        let pool = KeyPool::new(["a", "b", "c"])
+            .with_rotation(KeyRotation::LeastRecentlyThrottled)
+            .with_cooldown(Duration::ZERO);
+        pool.on_response("b", StatusCode::TOO_MANY_REQUESTS);
+        pool.on_response("a", StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(pool.token().await.unwrap(), "c");
+        assert_eq!(pool.token().await.unwrap(), "c");
+        pool.on_response("c", StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(pool.token().await.unwrap(), "b");
+    }
+
+    #[cfg(feature = "models")]
+    #[tokio::test]
+    async fn test_key_pool_benches_keys_out_of_quota() {
+        use crate::{Client, KeyPool};
+
+        let requests = Arc::new(Mutex::new(vec![]));
+        let body = r#"{"error":{"message":"You exceeded your current quota","type":"insufficient_quota","param":null,"code":"insufficient_quota"}}"#;
+        let api_base = serve_status("429 Too Many Requests", body, requests.clone()).await;
+        let client = Client::new()
+            .with_api_base(api_base)
+            .with_auth_provider(KeyPool::new(["sk-1", "sk-2"]));
+
+        for _ in 0..3 {
+            assert!(client.models().list().await.is_err());
+        }
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("authorization: bearer sk-1\r\n"));
+        assert!(requests[1].contains("authorization: bearer sk-2\r\n"));
+        // Both keys are benched, sk-1 is the first to come back
+        assert!(requests[2].contains("authorization: bearer sk-1\r\n"));
+    }
+
+    #[cfg(feature = "azure-ad")]
+    #[tokio::test]
+    async fn test_azure_ad_token_is_cached() {
+        use super::{AuthProvider, AzureAdCredential};
+
+        let requests = Arc::new(Mutex::new(vec![]));
+        let authority_host = serve(
+            r#"{"token_type":"Bearer","expires_in":3599,"access_token":"aad-token"}"#,
+            requests.clone(),
+        )
+        .await;
+        let credential = AzureAdCredential::new("tenant", "client", "secret")
+            .with_authority_host(authority_host)
+            .with_http_client(reqwest::Client::new());
+
+        assert_eq!(credential.token().await.unwrap(), "aad-token");
+        assert!(!format!("{credential:?}").contains("secret\""));
+        assert!(!format!("{credential:?}").contains("aad-token"));
+        assert_eq!(credential.token().await.unwrap(), "aad-token");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("post /tenant/oauth2/v2.0/token "));
+        assert!(requests[0].contains("grant_type=client_credentials"));
+    }
+}
@@ -0,0 +1,440 @@
+//! A blocking [Client] for scripts and command line tools which do not otherwise use async.
+//!
+//! Each method makes the same API call as its async counterpart on a runtime owned by the
+//! client, blocking the current thread until it completes. Like [reqwest::blocking], it must
+//! not be used from within an async runtime.
+//!
+//! ```no_run
+//! use async_openai::{blocking::Client, types::CreateCompletionRequestArgs};
+//!
+//! let client = Client::new();
+//!
+//! let request = CreateCompletionRequestArgs::default()
+//!     .model("text-davinci-003")
+//!     .prompt("Tell me a joke about the universe")
+//!     .max_tokens(40_u16)
+//!     .build()
+//!     .unwrap();
+//!
+//! for response in client.completions().create_stream(request).unwrap() {
+//!     print!("{}", response.unwrap().choices[0].text);
+//! }
+//! ```
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::{
+    error::OpenAIError, runtime::BoxStream, AuthProvider, CompatibilityMode, Gateway, HttpConfig,
+};
+#[cfg(feature = "_api")]
+use crate::{types::*, RequestOptions};
+
+/// Blocking counterpart of [crate::Client]. Clones share the runtime and connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+impl Default for Client {
+    /// Create client with default [API_BASE](crate::API_BASE) url and default API key from OPENAI_API_KEY env var
+    fn default() -> Self {
+        crate::Client::default().into()
+    }
+}
+
+impl From<crate::Client> for Client {
+    /// Make the API calls of an async client, with all its settings, blocking.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime cannot be created.
+    fn from(inner: crate::Client) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to create runtime for blocking client");
+
+        Self {
+            inner,
+            runtime: Arc::new(runtime),
+        }
+    }
+}
+
+impl Client {
+    /// Create client with default [API_BASE](crate::API_BASE) url and default API key from OPENAI_API_KEY env var
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// See [crate::Client::with_http_config]
+    pub fn with_http_config(self, config: HttpConfig) -> Self {
+        self.map(|inner| inner.with_http_config(config))
+    }
+
+    /// To use a different API key different from default OPENAI_API_KEY env var
+    pub fn with_api_key<S: Into<String>>(self, api_key: S) -> Self {
+        self.map(|inner| inner.with_api_key(api_key))
+    }
+
+    /// See [crate::Client::with_auth_provider]
+    pub fn with_auth_provider<P: AuthProvider + 'static>(self, provider: P) -> Self {
+        self.map(|inner| inner.with_auth_provider(provider))
+    }
+
+    /// See [crate::Client::with_request_signer]
+    pub fn with_request_signer<F>(self, sign: F) -> Self
+    where
+        F: Fn(&mut reqwest::Request) -> Result<(), OpenAIError> + Send + Sync + 'static,
+    {
+        self.map(|inner| inner.with_request_signer(sign))
+    }
+
+    /// See [crate::Client::with_gateway]
+    pub fn with_gateway(self, gateway: Gateway) -> Self {
+        self.map(|inner| inner.with_gateway(gateway))
+    }
+
+    /// To use a different organization id other than default
+    pub fn with_org_id<S: Into<String>>(self, org_id: S) -> Self {
+        self.map(|inner| inner.with_org_id(org_id))
+    }
+
+    /// See [crate::Client::with_project_id]
+    pub fn with_project_id<S: Into<String>>(self, project_id: S) -> Self {
+        self.map(|inner| inner.with_project_id(project_id))
+    }
+
+    /// See [crate::Client::with_beta]
+    pub fn with_beta<F: Into<String>, V: Into<String>>(self, feature: F, version: V) -> Self {
+        self.map(|inner| inner.with_beta(feature, version))
+    }
+
+    /// See [crate::Client::with_user_agent]
+    pub fn with_user_agent<S: AsRef<str>>(self, product: S) -> Self {
+        self.map(|inner| inner.with_user_agent(product))
+    }
+
+    /// See [crate::Client::with_compatibility_mode]
+    pub fn with_compatibility_mode(self, mode: CompatibilityMode) -> Self {
+        self.map(|inner| inner.with_compatibility_mode(mode))
+    }
+
+    /// To use a API base url different from default [API_BASE](crate::API_BASE)
+    pub fn with_api_base<S: Into<String>>(self, api_base: S) -> Self {
+        self.map(|inner| inner.with_api_base(api_base))
+    }
+
+    /// See [crate::Client::with_backoff]
+    #[cfg(feature = "retries")]
+    pub fn with_backoff(self, backoff: backoff::ExponentialBackoff) -> Self {
+        self.map(|inner| inner.with_backoff(backoff))
+    }
+
+    /// See [crate::Client::with_embedding_cache]
+    #[cfg(feature = "embeddings")]
+    pub fn with_embedding_cache<C: crate::EmbeddingCache + 'static>(self, cache: C) -> Self {
+        self.map(|inner| inner.with_embedding_cache(cache))
+    }
+
+    fn map(self, f: impl FnOnce(crate::Client) -> crate::Client) -> Self {
+        Self {
+            inner: f(self.inner),
+            runtime: self.runtime,
+        }
+    }
+
+    /// The async client making the API calls.
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    #[cfg(feature = "_api")]
+    fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    #[cfg(feature = "files")]
+    fn paginate<T>(&self, stream: BoxStream<'static, Result<T, OpenAIError>>) -> StreamIter<T> {
+        StreamIter {
+            stream,
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    #[cfg(any(
+        feature = "completions",
+        feature = "files",
+        feature = "fine-tunes",
+        feature = "images"
+    ))]
+    fn iter<T>(
+        &self,
+        stream: Result<BoxStream<'static, Result<T, OpenAIError>>, OpenAIError>,
+    ) -> Result<StreamIter<T>, OpenAIError> {
+        Ok(StreamIter {
+            stream: stream?,
+            runtime: self.runtime.clone(),
+        })
+    }
+}
+
+/// Blocking iterator over the items of a streamed response.
+pub struct StreamIter<T> {
+    stream: BoxStream<'static, Result<T, OpenAIError>>,
+    runtime: Arc<tokio::runtime::Runtime>,
+}
+
+impl<T> Iterator for StreamIter<T> {
+    type Item = Result<T, OpenAIError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// Defines a blocking API group calling the methods of the async one.
+macro_rules! blocking_group {
+    (
+        $feature:literal, $group:ident, $accessor:ident {
+            $( fn $method:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty; )*
+        }
+    ) => {
+        #[cfg(feature = $feature)]
+        #[doc = concat!("Blocking counterpart of [crate::", stringify!($group), "].")]
+        pub struct $group<'c> {
+            client: &'c Client,
+            options: RequestOptions,
+        }
+
+        #[cfg(feature = $feature)]
+        impl<'c> $group<'c> {
+            #[doc = concat!("See [crate::", stringify!($group), "::with_options]")]
+            pub fn with_options(mut self, options: RequestOptions) -> Self {
+                self.options = options;
+                self
+            }
+
+            $(
+                #[doc = concat!("See [crate::", stringify!($group), "::", stringify!($method), "]")]
+                pub fn $method(&self $(, $arg: $ty)*) -> Result<$ret, OpenAIError> {
+                    self.client.block_on(
+                        self.async_group().$method($($arg),*)
+                    )
+                }
+            )*
+
+            fn async_group(&self) -> crate::$group<'_> {
+                self.client.inner.$accessor().with_options(self.options.clone())
+            }
+        }
+
+        impl Client {
+            #[cfg(feature = $feature)]
+            #[doc = concat!("To call [", stringify!($group), "] group related APIs using this client.")]
+            pub fn $accessor(&self) -> $group<'_> {
+                $group {
+                    client: self,
+                    options: RequestOptions::default(),
+                }
+            }
+        }
+    };
+}
+
+blocking_group!("completions", Completions, completions {
+    fn create(&self, request: CreateCompletionRequest) -> CreateCompletionResponse;
+});
+
+blocking_group!("edits", Edits, edits {
+    fn create(&self, request: CreateEditRequest) -> CreateEditResponse;
+});
+
+blocking_group!("embeddings", Embeddings, embeddings {
+    fn create(&self, request: CreateEmbeddingRequest) -> CreateEmbeddingResponse;
+    fn create_batched(
+        &self,
+        request: CreateEmbeddingRequest,
+        options: EmbeddingBatchOptions
+    ) -> CreateEmbeddingBatchResponse;
+});
+
+blocking_group!("files", Files, files {
+    fn create(&self, request: CreateFileRequest) -> OpenAIFile;
+    fn list(&self, params: &ListParams) -> ListFilesResponse;
+    fn retrieve(&self, file_id: &str) -> OpenAIFile;
+    fn delete(&self, file_id: &str) -> DeleteFileResponse;
+    fn retrieve_content(&self, file_id: &str) -> String;
+});
+
+blocking_group!("fine-tunes", FineTunes, fine_tunes {
+    fn create(&self, request: CreateFineTuneRequest) -> FineTune;
+    fn list(&self) -> ListFineTuneResponse;
+    fn retrieve(&self, fine_tune_id: &str) -> FineTune;
+    fn cancel(&self, fine_tune_id: &str) -> FineTune;
+    fn list_events(&self, fine_tune_id: &str) -> ListFineTuneEventsResponse;
+});
+
+blocking_group!("images", Images, images {
+    fn create(&self, request: CreateImageRequest) -> ImageResponse;
+    fn create_edit(&self, request: CreateImageEditRequest) -> ImageResponse;
+    fn create_variation(&self, request: CreateImageVariationRequest) -> ImageResponse;
+});
+
+blocking_group!("models", Models, models {
+    fn list(&self) -> ListModelResponse;
+    fn retrieve(&self, id: &str) -> Model;
+    fn delete(&self, model: &str) -> DeleteModelResponse;
+});
+
+blocking_group!("moderations", Moderations, moderations {
+    fn create(&self, request: CreateModerationRequest) -> CreateModerationResponse;
+});
+
+blocking_group!("realtime", Realtime, realtime {
+    fn create_session(&self, request: realtime::SessionConfig) -> realtime::EphemeralSession;
+    fn create_transcription_session(
+        &self,
+        request: realtime::TranscriptionSessionConfig
+    ) -> realtime::TranscriptionSession;
+    fn create_call(&self, model: &str, offer_sdp: &str) -> String;
+    fn connect_webrtc(&self, request: realtime::SessionConfig, offer_sdp: &str) -> String;
+});
+
+#[cfg(feature = "completions")]
+impl<'c> Completions<'c> {
+    /// See [crate::Completions::create_stream]. Each call to `next` blocks until the next
+    /// partial response is received.
+    pub fn create_stream(
+        &self,
+        request: CreateCompletionRequest,
+    ) -> Result<StreamIter<CreateCompletionResponse>, OpenAIError> {
+        let stream = self
+            .client
+            .block_on(self.async_group().create_stream(request));
+        self.client.iter(stream)
+    }
+}
+
+#[cfg(feature = "images")]
+impl<'c> Images<'c> {
+    /// See [crate::Images::create_stream]. Each call to `next` blocks until the next partial
+    /// image is received.
+    pub fn create_stream(
+        &self,
+        request: CreateImageRequest,
+    ) -> Result<StreamIter<ImageGenerationEvent>, OpenAIError> {
+        let stream = self
+            .client
+            .block_on(self.async_group().create_stream(request));
+        self.client.iter(stream)
+    }
+}
+
+#[cfg(feature = "files")]
+impl<'c> Files<'c> {
+    /// See [crate::Files::list_stream]
+    pub fn list_stream(&self, params: &ListParams) -> Result<StreamIter<OpenAIFile>, OpenAIError> {
+        let stream = self.client.block_on(self.async_group().list_stream(params));
+        self.client.iter(stream)
+    }
+
+    /// See [crate::Files::list_all]
+    pub fn list_all(&self, params: &ListParams) -> StreamIter<OpenAIFile> {
+        self.client.paginate(self.async_group().list_all(params))
+    }
+
+    /// See [crate::Files::collect_all]
+    pub fn collect_all(
+        &self,
+        params: &ListParams,
+        max_items: usize,
+    ) -> Result<Vec<OpenAIFile>, OpenAIError> {
+        self.client
+            .block_on(self.async_group().collect_all(params, max_items))
+    }
+}
+
+#[cfg(feature = "fine-tunes")]
+impl<'c> FineTunes<'c> {
+    /// See [crate::FineTunes::list_events_stream]
+    pub fn list_events_stream(
+        &self,
+        fine_tune_id: &str,
+    ) -> Result<StreamIter<ListFineTuneEventsResponse>, OpenAIError> {
+        let stream = self
+            .client
+            .block_on(self.async_group().list_events_stream(fine_tune_id));
+        self.client.iter(stream)
+    }
+
+    /// See [crate::FineTunes::results]
+    #[cfg(feature = "files")]
+    pub fn results(&self, fine_tune_id: &str) -> Result<Vec<FineTuneMetrics>, OpenAIError> {
+        self.client
+            .block_on(self.async_group().results(fine_tune_id))
+    }
+
+    /// See [crate::FineTunes::create_and_poll]
+    pub fn create_and_poll(
+        &self,
+        request: CreateFineTuneRequest,
+        poll_interval: std::time::Duration,
+        on_event: impl FnMut(&FineTuneEvent),
+    ) -> Result<FineTune, OpenAIError> {
+        self.client.block_on(
+            self.async_group()
+                .create_and_poll(request, poll_interval, on_event),
+        )
+    }
+
+    /// See [crate::FineTunes::poll_until_done]
+    pub fn poll_until_done(
+        &self,
+        fine_tune_id: &str,
+        poll_interval: std::time::Duration,
+        on_event: impl FnMut(&FineTuneEvent),
+    ) -> Result<FineTune, OpenAIError> {
+        self.client.block_on(self.async_group().poll_until_done(
+            fine_tune_id,
+            poll_interval,
+            on_event,
+        ))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "models")]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use super::Client;
+
+    #[test]
+    fn test_blocking_models_list() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).unwrap();
+            let body = r#"{"object":"list","data":[{"id":"davinci","object":"model","created":1649358449,"owned_by":"openai"}]}"#;
+            write!(
+                socket,
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        });
+
+        let client = Client::new().with_api_base(format!("http://{addr}"));
+        let models = client.models().list().unwrap();
+
+        assert_eq!(models.data[0].id, "davinci");
+    }
+}
@@ -0,0 +1,115 @@
+use futures::StreamExt;
+
+use crate::{error::OpenAIError, runtime::BoxFuture, Client};
+
+/// A request which can be sent in bulk with [Client::run_all].
+pub trait BulkRequest: Clone + Send + Sync {
+    type Response: Send;
+
+    /// Make the API call for this request
+    fn send<'c>(&self, client: &'c Client) -> BoxFuture<'c, Result<Self::Response, OpenAIError>>;
+}
+
+macro_rules! bulk_request {
+    ($feature:literal, $request:ty, $response:ty, $group:ident) => {
+        #[cfg(feature = $feature)]
+        impl BulkRequest for $request {
+            type Response = $response;
+
+            fn send<'c>(
+                &self,
+                client: &'c Client,
+            ) -> BoxFuture<'c, Result<Self::Response, OpenAIError>> {
+                let request = self.clone();
+                Box::pin(async move { client.$group().create(request).await })
+            }
+        }
+    };
+}
+
+bulk_request!(
+    "completions",
+    crate::types::CreateCompletionRequest,
+    crate::types::CreateCompletionResponse,
+    completions
+);
+bulk_request!(
+    "edits",
+    crate::types::CreateEditRequest,
+    crate::types::CreateEditResponse,
+    edits
+);
+bulk_request!(
+    "embeddings",
+    crate::types::CreateEmbeddingRequest,
+    crate::types::CreateEmbeddingResponse,
+    embeddings
+);
+bulk_request!(
+    "images",
+    crate::types::CreateImageRequest,
+    crate::types::ImageResponse,
+    images
+);
+bulk_request!(
+    "moderations",
+    crate::types::CreateModerationRequest,
+    crate::types::CreateModerationResponse,
+    moderations
+);
+
+impl Client {
+    /// Make the API call for each request with at most `max_in_flight` calls at once.
+    ///
+    /// Each call is retried on rate limit like any other call made with this client.
+    /// Results are paired with their requests and returned in the order of `requests`.
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use async_openai::{types::CreateCompletionRequestArgs, Client};
+    ///
+    /// let client = Client::new();
+    ///
+    /// let requests = ["Tell me a joke", "Tell me a riddle"]
+    ///     .into_iter()
+    ///     .map(|prompt| {
+    ///         CreateCompletionRequestArgs::default()
+    ///             .model("text-davinci-003")
+    ///             .prompt(prompt)
+    ///             .build()
+    ///             .unwrap()
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// for (request, result) in client.run_all(requests, 4).await {
+    ///     println!("{:?}: {:?}", request.prompt, result.map(|response| response.choices));
+    /// }
+    /// # });
+    /// ```
+    pub async fn run_all<R>(
+        &self,
+        requests: impl IntoIterator<Item = R>,
+        max_in_flight: usize,
+    ) -> Vec<(R, Result<R::Response, OpenAIError>)>
+    where
+        R: BulkRequest,
+    {
+        let mut results: Vec<(usize, R, Result<R::Response, OpenAIError>)> =
+            futures::stream::iter(requests.into_iter().enumerate().map(
+                |(index, request)| async move {
+                    let result = request.send(self).await;
+                    (index, request, result)
+                },
+            ))
+            .buffer_unordered(max_in_flight.max(1))
+            .collect()
+            .await;
+
+        results.sort_by_key(|(index, _, _)| *index);
+
+        results
+            .into_iter()
+            .map(|(_, request, result)| (request, result))
+            .collect()
+    }
+}
@@ -0,0 +1,158 @@
+//! Cancellation of API calls and streams.
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::Stream;
+
+use crate::error::OpenAIError;
+
+/// Cancel an API call or stream when a future completes, e.g. when the user who made a
+/// request disconnects.
+///
+/// The HTTP request is aborted, or the event source closed, as soon as `cancel` completes:
+/// a call then fails with [OpenAIError::Cancelled] and a stream yields it once before ending.
+///
+/// ```no_run
+/// use async_openai::{types::CreateCompletionRequestArgs, CancelExt, Client};
+/// use tokio_util::sync::CancellationToken;
+///
+/// # tokio_test::block_on(async {
+/// let client = Client::new();
+/// let token = CancellationToken::new();
+///
+/// let request = CreateCompletionRequestArgs::default()
+///     .model("text-davinci-003")
+///     .prompt("Tell me a joke about the universe")
+///     .build()
+///     .unwrap();
+///
+/// let response = client
+///     .completions()
+///     .create(request)
+///     .cancel_on(token.clone().cancelled_owned())
+///     .await;
+/// # });
+/// ```
+pub trait CancelExt: Sized {
+    /// Cancel when `cancel` completes.
+    fn cancel_on<C: Future<Output = ()>>(self, cancel: C) -> CancelOn<Self, C> {
+        CancelOn {
+            inner: Some(Box::pin(self)),
+            cancel: Box::pin(cancel),
+        }
+    }
+
+    /// Cancel when `token` is cancelled.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    fn cancel_on_token(
+        self,
+        token: &tokio_util::sync::CancellationToken,
+    ) -> CancelOn<Self, tokio_util::sync::WaitForCancellationFutureOwned> {
+        self.cancel_on(token.clone().cancelled_owned())
+    }
+}
+
+impl<T> CancelExt for T {}
+
+/// Future or stream returned by [CancelExt::cancel_on].
+pub struct CancelOn<T, C> {
+    /// Dropped on cancellation, which aborts the call
+    inner: Option<Pin<Box<T>>>,
+    cancel: Pin<Box<C>>,
+}
+
+impl<T, C: Future<Output = ()>> CancelOn<T, C> {
+    fn poll_cancel(&mut self, cx: &mut Context<'_>) -> bool {
+        if self.cancel.as_mut().poll(cx).is_ready() {
+            self.inner = None;
+            return true;
+        }
+        false
+    }
+}
+
+impl<F, O, C> Future for CancelOn<F, C>
+where
+    F: Future<Output = Result<O, OpenAIError>>,
+    C: Future<Output = ()>,
+{
+    type Output = Result<O, OpenAIError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = match self.inner.as_mut() {
+            Some(inner) => inner,
+            None => return Poll::Ready(Err(OpenAIError::Cancelled)),
+        };
+        if let Poll::Ready(output) = inner.as_mut().poll(cx) {
+            return Poll::Ready(output);
+        }
+        if self.poll_cancel(cx) {
+            return Poll::Ready(Err(OpenAIError::Cancelled));
+        }
+        Poll::Pending
+    }
+}
+
+impl<S, O, C> Stream for CancelOn<S, C>
+where
+    S: Stream<Item = Result<O, OpenAIError>>,
+    C: Future<Output = ()>,
+{
+    type Item = Result<O, OpenAIError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inner = match self.inner.as_mut() {
+            Some(inner) => inner,
+            None => return Poll::Ready(None),
+        };
+        if let Poll::Ready(item) = inner.as_mut().poll_next(cx) {
+            return Poll::Ready(item);
+        }
+        if self.poll_cancel(cx) {
+            return Poll::Ready(Some(Err(OpenAIError::Cancelled)));
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::oneshot;
+    #[cfg(feature = "completions")]
+    use futures::StreamExt;
+
+    use super::CancelExt;
+    use crate::error::OpenAIError;
+    #[cfg(feature = "completions")]
+    use crate::{test_utils::SimulatedStream, types::CreateCompletionResponse};
+
+    #[tokio::test]
+    async fn test_cancel_call() {
+        let (cancel, cancelled) = oneshot::channel::<()>();
+        let call = futures::future::pending::<Result<(), OpenAIError>>()
+            .cancel_on(async move { cancelled.await.unwrap() });
+
+        cancel.send(()).unwrap();
+
+        assert!(matches!(call.await, Err(OpenAIError::Cancelled)));
+    }
+
+    #[cfg(feature = "completions")]
+    #[tokio::test]
+    async fn test_cancel_stream() {
+        let response: CreateCompletionResponse =
+            serde_json::from_str(crate::test_utils::examples::CREATE_COMPLETION_RESPONSE).unwrap();
+        let stream = SimulatedStream::new()
+            .with_delay(std::time::Duration::from_secs(60))
+            .completion(response)
+            .cancel_on(futures::future::ready(()));
+
+        let items: Vec<_> = stream.collect().await;
+
+        assert_eq!(items.len(), 1);
+        assert!(matches!(items[0], Err(OpenAIError::Cancelled)));
+    }
+}
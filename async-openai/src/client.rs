@@ -1,364 +1,1439 @@
-use std::pin::Pin;
+#[cfg(feature = "files")]
+use std::collections::VecDeque;
+use std::{collections::BTreeMap, sync::Arc};
 
-use futures::{stream::StreamExt, Stream};
+#[cfg(all(feature = "retries", feature = "_api"))]
+use backoff::backoff::Backoff;
+#[cfg(feature = "_api")]
+use bytes::Bytes;
 use reqwest::header::HeaderMap;
-use reqwest_eventsource::{Event, EventSource, RequestBuilderExt};
-use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "_post")]
+use reqwest::header::CONTENT_TYPE;
+#[cfg(feature = "_api")]
+use reqwest::header::{HeaderValue, AUTHORIZATION, USER_AGENT};
+#[cfg(feature = "_api")]
+use serde::de::DeserializeOwned;
+#[cfg(any(feature = "files", feature = "_post"))]
+use serde::Serialize;
+#[cfg(feature = "files")]
+use serde_json::Value;
 
+#[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+use crate::connector::Transport;
+#[cfg(any(
+    feature = "completions",
+    feature = "files",
+    feature = "fine-tunes",
+    feature = "images"
+))]
+use crate::runtime::BoxStream;
+#[cfg(any(feature = "completions", feature = "fine-tunes", feature = "images"))]
+use crate::sse;
+#[cfg(feature = "files")]
+use crate::types::{ListObject, ListParams};
+#[cfg(feature = "completions")]
+use crate::Completions;
+#[cfg(feature = "edits")]
+use crate::Edits;
+#[cfg(feature = "embeddings")]
+use crate::Embeddings;
+#[cfg(feature = "files")]
+use crate::Files;
+#[cfg(feature = "fine-tunes")]
+use crate::FineTunes;
+#[cfg(feature = "images")]
+use crate::Images;
+#[cfg(feature = "models")]
+use crate::Models;
+#[cfg(feature = "moderations")]
+use crate::Moderations;
+#[cfg(feature = "realtime")]
+use crate::Realtime;
 use crate::{
-    edit::Edits,
-    error::{OpenAIError, WrappedError},
-    file::Files,
-    image::Images,
-    moderation::Moderations,
-    Completions, Embeddings, FineTunes, Models,
+    auth::{AuthProvider, RequestSigner},
+    compat::CompatibilityMode,
+    config::{HttpConfig, RequestOptions},
+    env::EnvVars,
+    error::OpenAIError,
+    gateway::Gateway,
 };
+#[cfg(feature = "embeddings")]
+use crate::{coalesce::InFlight, types::CreateEmbeddingResponse, EmbeddingCache};
+#[cfg(feature = "_api")]
+use crate::{error::WrappedError, util};
+#[cfg(feature = "files")]
+use crate::{runtime::ResponseChunks, util::JsonArrayItems};
 
 #[derive(Debug, Clone)]
 /// Client is a container for api key, base url, organization id, and backoff
 /// configuration used to make API calls.
+///
+/// Client is `Send + Sync` and cheap to clone: clones share its configuration and
+/// the same underlying HTTP connection pool, so it can be cloned into worker tasks or
+/// stored in a static or web framework state. Setters called on a clone only change that clone.
 pub struct Client {
+    inner: Arc<ClientInner>,
+}
+
+/// State shared by clones of a [Client], copied on write by its setters.
+#[derive(Debug, Clone)]
+struct ClientInner {
+    http_client: reqwest::Client,
     api_key: String,
     api_base: String,
     org_id: String,
+    project_id: String,
+    user_agent: String,
+    /// Versions of beta features pinned with [Client::with_beta]
+    betas: BTreeMap<String, String>,
+    /// Headers of the gateway, if any
+    headers: HeaderMap,
+    auth_provider: Option<Arc<dyn AuthProvider>>,
+    request_signer: Option<RequestSigner>,
+    compatibility: CompatibilityMode,
+    #[cfg(feature = "retries")]
     backoff: backoff::ExponentialBackoff,
+    #[cfg(feature = "embeddings")]
+    embedding_calls: Option<Arc<InFlight<CreateEmbeddingResponse>>>,
+    #[cfg(feature = "embeddings")]
+    embedding_cache: Option<Arc<dyn EmbeddingCache>>,
+    #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+    transport: Option<Transport>,
+    /// Settings of the custom connector, if any
+    #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+    http_config: HttpConfig,
 }
 
 /// Default v1 API base url
 pub const API_BASE: &str = "https://api.openai.com/v1";
 /// Name for organization header
 pub const ORGANIZATION_HEADER: &str = "OpenAI-Organization";
+/// Name for project header
+pub const PROJECT_HEADER: &str = "OpenAI-Project";
+/// Name for the header opting in to beta features, e.g. `OpenAI-Beta: realtime=v1`
+pub const BETA_HEADER: &str = "OpenAI-Beta";
+/// Beta feature and version required by API paths with a segment, sent in the [BETA_HEADER]
+/// unless another version is pinned with [Client::with_beta] or [RequestOptions::with_beta].
+#[cfg(feature = "_api")]
+const REQUIRED_BETAS: &[(&str, &str, &str)] = &[("realtime", "realtime", "v1")];
+/// Default `User-Agent` header, the crate name and version
+pub const DEFAULT_USER_AGENT: &str = concat!("async-openai/", env!("CARGO_PKG_VERSION"));
 
 impl Default for Client {
-    /// Create client with default [API_BASE] url and default API key from OPENAI_API_KEY env var
+    /// Create client with the API key, API base and organization id of the environment
+    /// variables named by [EnvVars::default], or the default [API_BASE] url
     fn default() -> Self {
-        Self {
+        Client::from_env_vars(&EnvVars::default())
+    }
+}
+
+impl Client {
+    /// Client with default settings, without reading any environment variable
+    pub(crate) fn with_defaults() -> Self {
+        let inner = ClientInner {
+            http_client: reqwest::Client::new(),
             api_base: API_BASE.to_string(),
-            api_key: std::env::var("OPENAI_API_KEY").unwrap_or_else(|_| "".to_string()),
+            api_key: Default::default(),
             org_id: Default::default(),
+            project_id: Default::default(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            betas: BTreeMap::new(),
+            headers: HeaderMap::new(),
+            auth_provider: None,
+            request_signer: None,
+            compatibility: Default::default(),
+            #[cfg(feature = "retries")]
             backoff: Default::default(),
+            #[cfg(feature = "embeddings")]
+            embedding_calls: None,
+            #[cfg(feature = "embeddings")]
+            embedding_cache: None,
+            #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+            transport: None,
+            #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+            http_config: HttpConfig::default(),
+        };
+        Self {
+            inner: Arc::new(inner),
         }
     }
-}
 
-impl Client {
-    /// Create client with default [API_BASE] url and default API key from OPENAI_API_KEY env var
+    /// Create client with the API key, API base and organization id of the environment
+    /// variables named by [EnvVars::default], or the default [API_BASE] url
     pub fn new() -> Self {
         Default::default()
     }
 
+    /// Provide your own [reqwest::Client] to configure timeouts, proxies and so on.
+    /// It is shared by all clones of this client.
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        Arc::make_mut(&mut self.inner).http_client = http_client;
+        self
+    }
+
+    /// Use a HTTP client built from the given connection settings.
+    ///
+    /// # Panics
+    ///
+    /// Like [reqwest::Client::new], panics if the TLS backend cannot be initialized.
+    #[cfg_attr(
+        not(all(feature = "connector", not(target_arch = "wasm32"))),
+        allow(unused_mut)
+    )]
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        let http_client = config
+            .client_builder()
+            .build()
+            .expect("failed to build HTTP client");
+        #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+        {
+            let inner = Arc::make_mut(&mut self.inner);
+            inner.transport = inner.transport.as_ref().map(|t| t.with_config(&config));
+            inner.http_config = config;
+        }
+        self.with_http_client(http_client)
+    }
+
+    /// Make API calls through `connector` instead of the connection pool of the HTTP client,
+    /// e.g. a [UnixConnector](crate::UnixConnector) to reach a local server on a unix domain socket.
+    /// The path of each request is built from the API base as usual.
+    ///
+    /// Request bodies must be in memory, so file and image uploads are not supported. The timeout,
+    /// pool and HTTP/2 settings of [Client::with_http_config] apply to these calls, the others
+    /// and the settings of [Client::with_http_client] are up to the connector.
+    #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+    pub fn with_connector<C>(mut self, connector: C) -> Self
+    where
+        C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.transport = Some(Transport::new(connector, &inner.http_config));
+        self
+    }
+
     /// To use a different API key different from default OPENAI_API_KEY env var
     pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
-        self.api_key = api_key.into();
+        Arc::make_mut(&mut self.inner).api_key = api_key.into();
+        self
+    }
+
+    /// Obtain the bearer token of each request from `provider` instead of using the API key,
+    /// e.g. an [AzureAdCredential](crate::AzureAdCredential) with the `azure-ad` feature.
+    pub fn with_auth_provider<P: AuthProvider + 'static>(mut self, provider: P) -> Self {
+        Arc::make_mut(&mut self.inner).auth_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Call `sign` with every fully built request, including each retry, just before it is sent.
+    /// It can add headers computed from the method, URL and body, such as the signature required
+    /// by a gateway. Bodies of file and image uploads are streamed and not available to it.
+    ///
+    /// ```
+    /// use std::time::{SystemTime, UNIX_EPOCH};
+    /// use async_openai::Client;
+    ///
+    /// let client = Client::new().with_request_signer(|request| {
+    ///     let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    ///     let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+    ///     // e.g. an HMAC of the timestamp, path and body
+    ///     let signature = format!("{timestamp}:{}:{}", request.url().path(), body.len());
+    ///
+    ///     let headers = request.headers_mut();
+    ///     headers.insert("x-timestamp", timestamp.into());
+    ///     headers.insert("x-signature", signature.parse().unwrap());
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn with_request_signer<F>(mut self, sign: F) -> Self
+    where
+        F: Fn(&mut reqwest::Request) -> Result<(), OpenAIError> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.inner).request_signer = Some(RequestSigner::new(sign));
         self
     }
 
     /// To use a different organization id other than default
     pub fn with_org_id<S: Into<String>>(mut self, org_id: S) -> Self {
-        self.org_id = org_id.into();
+        Arc::make_mut(&mut self.inner).org_id = org_id.into();
+        self
+    }
+
+    /// Project the requests are made for, sent in the `OpenAI-Project` header. Needed with
+    /// keys which belong to several projects to attribute usage and billing to one of them.
+    pub fn with_project_id<S: Into<String>>(mut self, project_id: S) -> Self {
+        Arc::make_mut(&mut self.inner).project_id = project_id.into();
+        self
+    }
+
+    /// Opt in to `version` of the beta `feature` on every request, e.g. `("realtime", "v1")`,
+    /// sent in the [BETA_HEADER]. Betas which API paths need are sent without this, at the
+    /// version the crate was written for; pinning one replaces that version.
+    pub fn with_beta<F: Into<String>, V: Into<String>>(mut self, feature: F, version: V) -> Self {
+        Arc::make_mut(&mut self.inner)
+            .betas
+            .insert(feature.into(), version.into());
+        self
+    }
+
+    /// Identify your application to the API and gateways in between by appending `product`
+    /// to the [DEFAULT_USER_AGENT], e.g. `"my-app/1.2.0"` for `User-Agent: async-openai/0.7.0 my-app/1.2.0`.
+    /// API calls fail with [OpenAIError::InvalidArgument] when the result is not a valid header value.
+    pub fn with_user_agent<S: AsRef<str>>(mut self, product: S) -> Self {
+        Arc::make_mut(&mut self.inner).user_agent =
+            format!("{DEFAULT_USER_AGENT} {}", product.as_ref());
+        self
+    }
+
+    /// Make API calls through a proxy or gateway, using its API base and sending its headers.
+    pub fn with_gateway(mut self, gateway: Gateway) -> Self {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.api_base = gateway.api_base;
+        inner.headers = gateway.headers;
+        self
+    }
+
+    /// How strictly responses must match the types of this crate. Use [CompatibilityMode::Lenient]
+    /// with OpenAI compatible servers which leave out fields or send `null` for them.
+    pub fn with_compatibility_mode(mut self, mode: CompatibilityMode) -> Self {
+        Arc::make_mut(&mut self.inner).compatibility = mode;
         self
     }
 
     /// To use a API base url different from default [API_BASE]
     pub fn with_api_base<S: Into<String>>(mut self, api_base: S) -> Self {
-        self.api_base = api_base.into();
+        Arc::make_mut(&mut self.inner).api_base = api_base.into();
         self
     }
 
     /// Exponential backoff for retrying [rate limited](https://help.openai.com/en/articles/5955598-is-api-usage-subject-to-any-rate-limits) requests. Form submissions are not retried.
+    #[cfg(feature = "retries")]
     pub fn with_backoff(mut self, backoff: backoff::ExponentialBackoff) -> Self {
-        self.backoff = backoff;
+        Arc::make_mut(&mut self.inner).backoff = backoff;
+        self
+    }
+
+    #[cfg(feature = "embeddings")]
+    /// Share a single API call among concurrent [Embeddings::create] calls with identical
+    /// requests, such as the same text embedded by many tasks at once. Each caller receives
+    /// its own copy of the response. Callers which joined a call started by another get its
+    /// error wrapped in [OpenAIError::Coalesced].
+    ///
+    /// Only requests in flight at the same time are shared, responses are not cached.
+    /// The setting is shared by clones made after it.
+    pub fn with_embedding_coalescing(mut self, enabled: bool) -> Self {
+        Arc::make_mut(&mut self.inner).embedding_calls = enabled.then(Default::default);
+        self
+    }
+
+    #[cfg(feature = "embeddings")]
+    pub(crate) fn embedding_calls(&self) -> Option<&InFlight<CreateEmbeddingResponse>> {
+        self.inner.embedding_calls.as_deref()
+    }
+
+    #[cfg(feature = "embeddings")]
+    /// Look up the embedding of each input of [Embeddings::create] in `cache` first, and only
+    /// send the inputs which are not in it, storing their embeddings. Responses served
+    /// entirely from the cache report no token usage.
+    ///
+    /// The cache is shared by clones made after it.
+    pub fn with_embedding_cache<C: EmbeddingCache + 'static>(mut self, cache: C) -> Self {
+        Arc::make_mut(&mut self.inner).embedding_cache = Some(Arc::new(cache));
         self
     }
 
+    #[cfg(feature = "embeddings")]
+    pub(crate) fn embedding_cache(&self) -> Option<&dyn EmbeddingCache> {
+        self.inner.embedding_cache.as_deref()
+    }
+
     pub fn api_base(&self) -> &str {
-        &self.api_base
+        &self.inner.api_base
     }
 
     pub fn api_key(&self) -> &str {
-        &self.api_key
+        &self.inner.api_key
+    }
+
+    pub fn user_agent(&self) -> &str {
+        &self.inner.user_agent
     }
 
     // API groups
 
+    #[cfg(feature = "models")]
     /// To call [Models] group related APIs using this client.
-    pub fn models(&self) -> Models {
+    pub fn models(&self) -> Models<'_> {
         Models::new(self)
     }
 
+    #[cfg(feature = "completions")]
     /// To call [Completions] group related APIs using this client.
-    pub fn completions(&self) -> Completions {
+    pub fn completions(&self) -> Completions<'_> {
         Completions::new(self)
     }
 
+    #[cfg(feature = "edits")]
     /// To call [Edits] group related APIs using this client.
-    pub fn edits(&self) -> Edits {
+    pub fn edits(&self) -> Edits<'_> {
         Edits::new(self)
     }
 
+    #[cfg(feature = "images")]
     /// To call [Images] group related APIs using this client.
-    pub fn images(&self) -> Images {
+    pub fn images(&self) -> Images<'_> {
         Images::new(self)
     }
 
+    #[cfg(feature = "moderations")]
     /// To call [Moderations] group related APIs using this client.
-    pub fn moderations(&self) -> Moderations {
+    pub fn moderations(&self) -> Moderations<'_> {
         Moderations::new(self)
     }
 
+    #[cfg(feature = "files")]
     /// To call [Files] group related APIs using this client.
-    pub fn files(&self) -> Files {
+    pub fn files(&self) -> Files<'_> {
         Files::new(self)
     }
 
+    #[cfg(feature = "fine-tunes")]
     /// To call [FineTunes] group related APIs using this client.
-    pub fn fine_tunes(&self) -> FineTunes {
+    pub fn fine_tunes(&self) -> FineTunes<'_> {
         FineTunes::new(self)
     }
 
+    #[cfg(feature = "embeddings")]
     /// To call [Embeddings] group related APIs using this client.
-    pub fn embeddings(&self) -> Embeddings {
+    pub fn embeddings(&self) -> Embeddings<'_> {
         Embeddings::new(self)
     }
 
-    fn headers(&self) -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        if !self.org_id.is_empty() {
-            headers.insert(ORGANIZATION_HEADER, self.org_id.as_str().parse().unwrap());
+    #[cfg(feature = "realtime")]
+    /// To call [Realtime] group related APIs using this client.
+    pub fn realtime(&self) -> Realtime<'_> {
+        Realtime::new(self)
+    }
+
+    /// Open a connection to the API base ahead of the first request, so that the
+    /// TCP and TLS handshakes are not paid for by it.
+    ///
+    /// The connection is kept in the pool shared by this client and its clones.
+    /// The request carries no API key and is not signed. The response status is ignored,
+    /// only failing to connect is an error.
+    pub async fn preconnect(&self) -> Result<(), OpenAIError> {
+        let request = self.inner.http_client.head(self.api_base()).build()?;
+        // Sent without credentials, signature or auth provider, as it is not an API call
+        let response = self.transmit(request, &RequestOptions::default()).await?;
+        // Drain the body so the connection is returned to the pool
+        response.bytes().await?;
+        Ok(())
+    }
+
+    /// Bearer token of a request: the API key of `options`, else a token of the auth provider
+    /// if there is one, else the API key of the client. Returned with the provider it came from.
+    #[cfg(feature = "_api")]
+    async fn token(
+        &self,
+        options: &RequestOptions,
+    ) -> Result<(String, Option<&dyn AuthProvider>), OpenAIError> {
+        Ok(match (options.api_key(), &self.inner.auth_provider) {
+            (Some(api_key), _) => (api_key.to_string(), None),
+            (None, Some(provider)) => (provider.token().await?, Some(provider.as_ref())),
+            (None, None) => (self.api_key().to_string(), None),
+        })
+    }
+
+    /// Authorize and send `request`, through the custom connector if there is one. The auth
+    /// provider which gave the token is told the status of the response.
+    #[cfg(feature = "_api")]
+    async fn send(
+        &self,
+        mut request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<reqwest::Response, OpenAIError> {
+        let (token, provider) = self.token(options).await?;
+        request.headers_mut().extend(options.headers()?);
+        if let Some(beta) = self.beta_header(request.url().path(), options)? {
+            request.headers_mut().insert(BETA_HEADER, beta);
+        }
+        request.headers_mut().insert(AUTHORIZATION, bearer(&token)?);
+        if let Some(signer) = &self.inner.request_signer {
+            signer.sign(&mut request)?;
         }
-        headers
+
+        let response = self.transmit(request, options).await?;
+        if let Some(provider) = provider {
+            provider.on_response(&token, response.status());
+        }
+        Ok(response)
+    }
+
+    async fn transmit(
+        &self,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<reqwest::Response, OpenAIError> {
+        #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+        if let Some(transport) = &self.inner.transport {
+            return transport.send(request, options).await;
+        }
+
+        self.inner
+            .http_client
+            .execute(request)
+            .await
+            .map_err(|e| options.map_err(e))
+    }
+
+    /// Headers of every request. Fails when the organization or project id, which may come
+    /// from environment variables, or the user agent is not a valid header value.
+    #[cfg(feature = "_api")]
+    pub(crate) fn headers(&self) -> Result<HeaderMap, OpenAIError> {
+        let mut headers = self.inner.headers.clone();
+        let ids = [
+            (ORGANIZATION_HEADER, &self.inner.org_id, "organization id"),
+            (PROJECT_HEADER, &self.inner.project_id, "project id"),
+        ];
+        for (name, value, what) in ids {
+            if !value.is_empty() {
+                let value = HeaderValue::from_str(value).map_err(|_| {
+                    OpenAIError::InvalidArgument(format!("{what} is not a valid header value"))
+                })?;
+                headers.insert(name, value);
+            }
+        }
+        let user_agent = HeaderValue::from_str(&self.inner.user_agent).map_err(|_| {
+            OpenAIError::InvalidArgument("user agent is not a valid header value".into())
+        })?;
+        headers.insert(USER_AGENT, user_agent);
+        Ok(headers)
+    }
+
+    /// Value of the [BETA_HEADER] of a request to `path`: the betas of `options`, then those of
+    /// the client, then those the path requires. `None` when there are none.
+    #[cfg(feature = "_api")]
+    pub(crate) fn beta_header(
+        &self,
+        path: &str,
+        options: &RequestOptions,
+    ) -> Result<Option<HeaderValue>, OpenAIError> {
+        let mut betas = self.inner.betas.clone();
+        betas.extend(options.betas().clone());
+        for (segment, feature, version) in REQUIRED_BETAS {
+            if path.split('/').any(|s| s == *segment) {
+                betas
+                    .entry(feature.to_string())
+                    .or_insert_with(|| version.to_string());
+            }
+        }
+        if betas.is_empty() {
+            return Ok(None);
+        }
+
+        let value: Vec<String> = betas
+            .iter()
+            .map(|(feature, version)| format!("{feature}={version}"))
+            .collect();
+        HeaderValue::from_str(&value.join(", "))
+            .map(Some)
+            .map_err(|_| OpenAIError::InvalidArgument("beta is not a valid header value".into()))
     }
 
     /// Make a GET request to {path} and deserialize the response body
-    pub(crate) async fn get<O>(&self, path: &str) -> Result<O, OpenAIError>
+    #[cfg(any(feature = "files", feature = "fine-tunes", feature = "models"))]
+    pub(crate) async fn get<O>(
+        &self,
+        path: &str,
+        options: &RequestOptions,
+    ) -> Result<O, OpenAIError>
     where
         O: DeserializeOwned,
     {
-        let request = reqwest::Client::new()
+        let request = self
+            .inner
+            .http_client
             .get(format!("{}{path}", self.api_base()))
-            .bearer_auth(self.api_key())
-            .headers(self.headers())
+            .headers(self.headers()?)
             .build()?;
 
-        self.execute(request).await
+        self.execute(request, options).await
+    }
+
+    /// Make a GET request to {path} and return the response body as text, for file contents
+    /// which are not JSON such as fine-tune result files
+    #[cfg(feature = "files")]
+    pub(crate) async fn get_text(
+        &self,
+        path: &str,
+        options: &RequestOptions,
+    ) -> Result<String, OpenAIError> {
+        let request = self
+            .inner
+            .http_client
+            .get(format!("{}{path}", self.api_base()))
+            .headers(self.headers()?)
+            .build()?;
+
+        let bytes = self.execute_raw(request, options).await?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| OpenAIError::FileReadError(format!("file is not UTF-8 text: {e}")))
+    }
+
+    /// Make a GET request to {path} with `query` in the query string and deserialize the response body
+    #[cfg(feature = "files")]
+    pub(crate) async fn get_with_query<Q, O>(
+        &self,
+        path: &str,
+        query: &Q,
+        options: &RequestOptions,
+    ) -> Result<O, OpenAIError>
+    where
+        Q: Serialize + ?Sized,
+        O: DeserializeOwned,
+    {
+        let request = self
+            .inner
+            .http_client
+            .get(format!("{}{path}", self.api_base()))
+            .query(query)
+            .headers(self.headers()?)
+            .build()?;
+
+        self.execute(request, options).await
     }
 
     /// Make a DELETE request to {path} and deserialize the response body
-    pub(crate) async fn delete<O>(&self, path: &str) -> Result<O, OpenAIError>
+    #[cfg(any(feature = "files", feature = "models"))]
+    pub(crate) async fn delete<O>(
+        &self,
+        path: &str,
+        options: &RequestOptions,
+    ) -> Result<O, OpenAIError>
     where
         O: DeserializeOwned,
     {
-        let request = reqwest::Client::new()
+        let request = self
+            .inner
+            .http_client
             .delete(format!("{}{path}", self.api_base()))
-            .bearer_auth(self.api_key())
-            .headers(self.headers())
+            .headers(self.headers()?)
             .build()?;
 
-        self.execute(request).await
+        self.execute(request, options).await
     }
 
     /// Make a POST request to {path} and deserialize the response body
-    pub(crate) async fn post<I, O>(&self, path: &str, request: I) -> Result<O, OpenAIError>
+    #[cfg(feature = "_post")]
+    pub(crate) async fn post<I, O>(
+        &self,
+        path: &str,
+        request: I,
+        options: &RequestOptions,
+    ) -> Result<O, OpenAIError>
     where
         I: Serialize,
         O: DeserializeOwned,
     {
-        let request = reqwest::Client::new()
+        self.post_json(path, options.json_body(&request)?, options)
+            .await
+    }
+
+    /// Make a POST request to {path} with an already serialized JSON body
+    #[cfg(feature = "_post")]
+    pub(crate) async fn post_json<O>(
+        &self,
+        path: &str,
+        body: Bytes,
+        options: &RequestOptions,
+    ) -> Result<O, OpenAIError>
+    where
+        O: DeserializeOwned,
+    {
+        let request = self
+            .inner
+            .http_client
             .post(format!("{}{path}", self.api_base()))
-            .bearer_auth(self.api_key())
-            .headers(self.headers())
-            .json(&request)
+            .headers(self.headers()?)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
             .build()?;
 
-        self.execute(request).await
+        self.execute(request, options).await
+    }
+
+    /// Make a POST request to {path} with `query` in the query string and a text body of
+    /// `content_type`, such as an SDP offer, and return the text of the response body. Not retried.
+    #[cfg(feature = "realtime")]
+    pub(crate) async fn post_text<Q>(
+        &self,
+        path: &str,
+        query: &Q,
+        content_type: &str,
+        body: String,
+        options: &RequestOptions,
+    ) -> Result<String, OpenAIError>
+    where
+        Q: Serialize + ?Sized,
+    {
+        let mut request = self
+            .inner
+            .http_client
+            .post(format!("{}{path}", self.api_base()))
+            .query(query)
+            .headers(self.headers()?)
+            .header(CONTENT_TYPE, content_type)
+            .body(body)
+            .build()?;
+
+        options.apply(&mut request)?;
+        let response = self.send(request, options).await?;
+        let status = response.status();
+        let bytes = response.bytes().await.map_err(|e| options.map_err(e))?;
+        if !status.is_success() {
+            let wrapped_error: WrappedError = util::deserialize(&bytes)?;
+            return Err(OpenAIError::ApiError(wrapped_error.error));
+        }
+
+        String::from_utf8(bytes.to_vec()).map_err(|e| OpenAIError::StreamError(e.to_string()))
     }
 
+    #[cfg(feature = "multipart")]
     /// POST a form at {path} and deserialize the response body
     pub(crate) async fn post_form<O>(
         &self,
         path: &str,
         form: reqwest::multipart::Form,
+        options: &RequestOptions,
     ) -> Result<O, OpenAIError>
     where
         O: DeserializeOwned,
     {
-        let request = reqwest::Client::new()
+        let request = self
+            .inner
+            .http_client
             .post(format!("{}{path}", self.api_base()))
-            .bearer_auth(self.api_key())
-            .headers(self.headers())
+            .headers(self.headers()?)
             .multipart(form)
             .build()?;
 
-        self.execute(request).await
+        self.execute(request, options).await
     }
 
-    /// Deserialize response body from either error object or actual response object
-    async fn process_response<O>(&self, response: reqwest::Response) -> Result<O, OpenAIError>
+    /// Make a GET request to {path} and deserialize the elements of the `field` array of the
+    /// response body as they are received, instead of reading the whole body first.
+    #[cfg(feature = "files")]
+    pub(crate) async fn get_items<Q, O>(
+        &self,
+        path: &str,
+        query: &Q,
+        field: &'static str,
+        options: &RequestOptions,
+    ) -> Result<BoxStream<'static, Result<O, OpenAIError>>, OpenAIError>
     where
-        O: DeserializeOwned,
+        Q: Serialize + ?Sized,
+        O: DeserializeOwned + Send + 'static,
     {
+        let mut request = self
+            .inner
+            .http_client
+            .get(format!("{}{path}", self.api_base()))
+            .query(query)
+            .headers(self.headers()?)
+            .build()?;
+        options.apply(&mut request)?;
+
+        let response = self.send(request, options).await?;
+
+        if !response.status().is_success() {
+            let wrapped_error: WrappedError = util::deserialize(&response.bytes().await?)?;
+            return Err(OpenAIError::ApiError(wrapped_error.error));
+        }
+
+        let response = ResponseChunks::new(response);
+        let compatibility = self.inner.compatibility;
+        let items = JsonArrayItems::new(field);
+        let state = (response, items, VecDeque::new(), false, options.clone());
+        let stream = futures::stream::unfold(
+            state,
+            move |(mut response, mut items, mut parsed, mut ended, options)| async move {
+                loop {
+                    if let Some(item) = parsed.pop_front() {
+                        return Some((item, (response, items, parsed, ended, options)));
+                    }
+                    if ended {
+                        return None;
+                    }
+
+                    match response.next().await {
+                        Ok(Some(chunk)) => items.push(&chunk, |item| {
+                            parsed.push_back(compatibility.deserialize_slice(item))
+                        }),
+                        Ok(None) => {
+                            ended = true;
+                            if !items.is_done() {
+                                parsed.push_back(Err(OpenAIError::StreamError(format!(
+                                    "response ended before the end of the {field} array"
+                                ))));
+                            }
+                        }
+                        Err(e) => {
+                            ended = true;
+                            parsed.push_back(Err(options.map_err(e)));
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Make GET requests to {path} and yield the elements of the `data` array of each page,
+    /// requesting the page `after` the last element while the response `has_more`.
+    #[cfg(feature = "files")]
+    pub(crate) fn get_paginated<O>(
+        &self,
+        path: &str,
+        params: &ListParams,
+        options: &RequestOptions,
+    ) -> BoxStream<'static, Result<O, OpenAIError>>
+    where
+        O: DeserializeOwned + Send + 'static,
+    {
+        let (client, path, options) = (self.clone(), path.to_string(), options.clone());
+        let params = params.clone();
+        let page = move |after: Option<String>| {
+            let (client, path, options) = (client.clone(), path.clone(), options.clone());
+            let mut params = params.clone();
+            if after.is_some() {
+                params.after = after;
+            }
+            async move {
+                client
+                    .get_with_query::<_, ListObject<Value>>(&path, &params, &options)
+                    .await
+            }
+        };
+
+        let compatibility = self.inner.compatibility;
+        let state = (Some(page(None)), VecDeque::new(), page);
+        Box::pin(futures::stream::unfold(
+            state,
+            move |(mut next, mut items, page)| async move {
+                loop {
+                    if let Some(item) = items.pop_front() {
+                        let item = compatibility.deserialize_value(item);
+                        return Some((item, (next, items, page)));
+                    }
+                    match next.take()?.await {
+                        Ok(ListObject {
+                            data,
+                            has_more,
+                            last_id,
+                            ..
+                        }) => {
+                            let last_id = last_id.or_else(|| {
+                                data.last()
+                                    .and_then(|item| item.get("id"))
+                                    .and_then(|id| id.as_str())
+                                    .map(str::to_string)
+                            });
+                            if has_more == Some(true) && !data.is_empty() {
+                                next = last_id.map(|after| page(Some(after)));
+                            }
+                            items.extend(data);
+                        }
+                        Err(e) => return Some((Err(e), (None, items, page))),
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Body of a successful response, or the error object of a failed one
+    #[cfg(feature = "_api")]
+    async fn process_response(
+        &self,
+        response: reqwest::Response,
+        options: &RequestOptions,
+    ) -> Result<Bytes, OpenAIError> {
         let status = response.status();
-        let bytes = response.bytes().await?;
+        let bytes = response.bytes().await.map_err(|e| options.map_err(e))?;
 
         if !status.is_success() {
-            let wrapped_error: WrappedError =
-                serde_json::from_slice(bytes.as_ref()).map_err(OpenAIError::JSONDeserialize)?;
+            let wrapped_error: WrappedError = util::deserialize(&bytes)?;
 
             return Err(OpenAIError::ApiError(wrapped_error.error));
         }
 
-        let response: O =
-            serde_json::from_slice(bytes.as_ref()).map_err(OpenAIError::JSONDeserialize)?;
-        Ok(response)
+        Ok(bytes)
     }
 
     /// Execute any HTTP requests and retry on rate limit, except streaming ones as they cannot be cloned for retrying.
-    async fn execute<O>(&self, request: reqwest::Request) -> Result<O, OpenAIError>
+    ///
+    /// With a deadline in `options` each attempt times out when the deadline passes, and no
+    /// retry is scheduled whose backoff delay would end after it.
+    #[cfg(feature = "_api")]
+    async fn execute<O>(
+        &self,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<O, OpenAIError>
     where
         O: DeserializeOwned,
     {
-        let client = reqwest::Client::new();
-
-        match request.try_clone() {
-            // Only clone-able requests can be retried
-            Some(request) => {
-                backoff::future::retry(self.backoff.clone(), || async {
-                    let response = client
-                        .execute(request.try_clone().unwrap())
-                        .await
-                        .map_err(OpenAIError::Reqwest)
-                        .map_err(backoff::Error::Permanent)?;
-
-                    let status = response.status();
-                    let bytes = response
-                        .bytes()
-                        .await
-                        .map_err(OpenAIError::Reqwest)
-                        .map_err(backoff::Error::Permanent)?;
-
-                    // Deserialize response body from either error object or actual response object
-                    if !status.is_success() {
-                        let wrapped_error: WrappedError = serde_json::from_slice(bytes.as_ref())
-                            .map_err(OpenAIError::JSONDeserialize)
-                            .map_err(backoff::Error::Permanent)?;
-
-                        if status.as_u16() == 429
-                            // API returns 429 also when:
-                            // "You exceeded your current quota, please check your plan and billing details."
-                            && wrapped_error.error.r#type != "insufficient_quota"
-                        {
-                            // Rate limited retry...
-                            tracing::warn!("Rate limited: {}", wrapped_error.error.message);
-                            return Err(backoff::Error::Transient {
-                                err: OpenAIError::ApiError(wrapped_error.error),
-                                retry_after: None,
-                            });
-                        } else {
-                            return Err(backoff::Error::Permanent(OpenAIError::ApiError(
-                                wrapped_error.error,
-                            )));
-                        }
-                    }
+        let bytes = self.execute_raw(request, options).await?;
+        self.inner.compatibility.deserialize_slice(&bytes)
+    }
+
+    /// [Client::execute] returning the response body as is.
+    #[cfg(feature = "_api")]
+    async fn execute_raw(
+        &self,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<Bytes, OpenAIError> {
+        // Only clone-able requests can be retried. Bodies built from `Bytes`
+        // are cloned by reference count, so retries reuse the same buffer.
+        #[cfg(feature = "retries")]
+        if let Some(request) = request.try_clone() {
+            return self.execute_with_retries(request, options).await;
+        }
 
-                    let response: O = serde_json::from_slice(bytes.as_ref())
-                        .map_err(OpenAIError::JSONDeserialize)
-                        .map_err(backoff::Error::Permanent)?;
-                    Ok(response)
-                })
+        let mut request = request;
+        options.apply(&mut request)?;
+        let response = self.send(request, options).await?;
+        self.process_response(response, options).await
+    }
+
+    /// Execute a clone-able request, retrying it with the backoff of the client while rate limited.
+    #[cfg(all(feature = "retries", feature = "_api"))]
+    async fn execute_with_retries(
+        &self,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<Bytes, OpenAIError> {
+        let mut backoff = self.inner.backoff.clone();
+        if let Some(remaining) = options.remaining()? {
+            backoff.max_elapsed_time = Some(
+                backoff
+                    .max_elapsed_time
+                    .map_or(remaining, |max| max.min(remaining)),
+            );
+        }
+        backoff.reset();
+        let notify = |_, _| {};
+        backoff::future::Retry::new(crate::runtime::Sleeper, backoff, notify, || async {
+            let mut request = request.try_clone().unwrap();
+            options
+                .apply(&mut request)
+                .map_err(backoff::Error::Permanent)?;
+
+            let response = self
+                .send(request, options)
                 .await
+                .map_err(backoff::Error::Permanent)?;
+
+            let status = response.status();
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| options.map_err(e))
+                .map_err(backoff::Error::Permanent)?;
+
+            // Deserialize response body from either error object or actual response object
+            if !status.is_success() {
+                let wrapped_error: WrappedError =
+                    util::deserialize(&bytes).map_err(backoff::Error::Permanent)?;
+
+                if status.as_u16() == 429
+                    // API returns 429 also when:
+                    // "You exceeded your current quota, please check your plan and billing details."
+                    && wrapped_error.error.r#type != "insufficient_quota"
+                {
+                    // Rate limited retry...
+                    tracing::warn!("Rate limited: {}", wrapped_error.error.message);
+                    return Err(backoff::Error::Transient {
+                        err: OpenAIError::ApiError(wrapped_error.error),
+                        retry_after: None,
+                    });
+                } else {
+                    return Err(backoff::Error::Permanent(OpenAIError::ApiError(
+                        wrapped_error.error,
+                    )));
+                }
             }
-            None => {
-                let response = client.execute(request).await?;
-                self.process_response(response).await
-            }
-        }
+
+            Ok(bytes)
+        })
+        .await
     }
 
     /// Make HTTP POST request to receive SSE
+    #[cfg(any(feature = "completions", feature = "images"))]
     pub(crate) async fn post_stream<I, O>(
         &self,
         path: &str,
         request: I,
-    ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
+        options: &RequestOptions,
+        last_event: Option<&'static str>,
+    ) -> BoxStream<'static, Result<O, OpenAIError>>
     where
         I: Serialize,
         O: DeserializeOwned + std::marker::Send + 'static,
     {
-        let event_source = reqwest::Client::new()
+        let builder = self
+            .inner
+            .http_client
             .post(format!("{}{path}", self.api_base()))
-            .headers(self.headers())
-            .bearer_auth(self.api_key())
-            .json(&request)
-            .eventsource()
-            .unwrap();
+            .headers(match self.headers() {
+                Ok(headers) => headers,
+                Err(e) => return Box::pin(futures::stream::once(async { Err(e) })),
+            });
+        let builder = match options.json_body(&request) {
+            Ok(body) => builder.header(CONTENT_TYPE, "application/json").body(body),
+            Err(e) => return Box::pin(futures::stream::once(async { Err(e) })),
+        };
 
-        Client::stream(event_source).await
+        self.stream(builder, options, last_event).await
     }
 
     /// Make HTTP GET request to receive SSE
+    #[cfg(feature = "fine-tunes")]
     pub(crate) async fn get_stream<Q, O>(
         &self,
         path: &str,
         query: &Q,
-    ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
+        options: &RequestOptions,
+    ) -> BoxStream<'static, Result<O, OpenAIError>>
     where
         Q: Serialize + ?Sized,
         O: DeserializeOwned + std::marker::Send + 'static,
     {
-        let event_source = reqwest::Client::new()
+        let builder = self
+            .inner
+            .http_client
             .get(format!("{}{path}", self.api_base()))
             .query(query)
-            .headers(self.headers())
-            .bearer_auth(self.api_key())
-            .eventsource()
-            .unwrap();
+            .headers(match self.headers() {
+                Ok(headers) => headers,
+                Err(e) => return Box::pin(futures::stream::once(async { Err(e) })),
+            });
 
-        Client::stream(event_source).await
+        self.stream(builder, options, None).await
     }
 
+    #[cfg(any(feature = "completions", feature = "fine-tunes", feature = "images"))]
     /// Request which responds with SSE.
     /// [server-sent events](https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#event_stream_format)
+    ///
+    /// Events are parsed as the returned stream is polled, without a background task or channel in between.
+    ///
+    /// The stream ends at a `[DONE]` message, or after the event named `last_event` for APIs
+    /// which name their events instead.
+    ///
+    /// With a deadline in `options` the stream yields [OpenAIError::DeadlineExceeded] and ends
+    /// once the deadline passes.
     pub(crate) async fn stream<O>(
-        mut event_source: EventSource,
-    ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
+        &self,
+        builder: reqwest::RequestBuilder,
+        options: &RequestOptions,
+        last_event: Option<&'static str>,
+    ) -> BoxStream<'static, Result<O, OpenAIError>>
     where
         O: DeserializeOwned + std::marker::Send + 'static,
     {
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let response = match self.open_stream(builder, options).await {
+            Ok(response) => response,
+            Err(e) => return Box::pin(futures::stream::once(async { Err(e) })),
+        };
+        sse::events(
+            response,
+            self.inner.compatibility,
+            options.clone(),
+            last_event,
+        )
+    }
+
+    /// Authorize and send a streaming request like any other, failing with the API error of an
+    /// unsuccessful response.
+    #[cfg(any(feature = "completions", feature = "fine-tunes", feature = "images"))]
+    async fn open_stream(
+        &self,
+        builder: reqwest::RequestBuilder,
+        options: &RequestOptions,
+    ) -> Result<reqwest::Response, OpenAIError> {
+        let mut request = builder.build()?;
+        options.apply(&mut request)?;
 
+        let response = self.send(request, options).await?;
+        if !response.status().is_success() {
+            let bytes = response.bytes().await.map_err(|e| options.map_err(e))?;
+            let wrapped_error: WrappedError = util::deserialize(&bytes)?;
+            return Err(OpenAIError::ApiError(wrapped_error.error));
+        }
+        Ok(response)
+    }
+}
+
+/// Sensitive `Authorization` header value of `token`.
+#[cfg(feature = "_api")]
+fn bearer(token: &str) -> Result<HeaderValue, OpenAIError> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+        .map_err(|_| OpenAIError::InvalidArgument("API key is not a valid header value".into()))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "_api")]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[cfg(feature = "_api")]
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::Client;
+    #[cfg(feature = "_api")]
+    use crate::error::OpenAIError;
+
+    #[test]
+    fn test_client_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<Client>();
+    }
+
+    #[test]
+    fn test_clones_share_config() {
+        let client = Client::new().with_api_key("sk-1");
+        let clone = client.clone();
+        assert!(Arc::ptr_eq(&client.inner, &clone.inner));
+
+        let clone = clone.with_api_key("sk-2");
+        assert_eq!(client.api_key(), "sk-1");
+        assert_eq!(clone.api_key(), "sk-2");
+    }
+
+    #[cfg(feature = "_api")]
+    #[test]
+    fn test_invalid_ids_are_errors() {
+        // e.g. read from an environment variable with a trailing newline
+        let client = Client::new().with_project_id("proj_1\n");
+        assert!(matches!(
+            client.headers(),
+            Err(OpenAIError::InvalidArgument(_))
+        ));
+        let client = Client::new().with_org_id("org-1\n");
+        assert!(client.headers().is_err());
+        assert!(Client::new().with_org_id("org-1").headers().is_ok());
+        let client = Client::new().with_user_agent("my-app/1.2.0\n");
+        assert!(matches!(
+            client.headers(),
+            Err(OpenAIError::InvalidArgument(_))
+        ));
+    }
+
+    #[cfg(feature = "_api")]
+    #[tokio::test]
+    async fn test_preconnect_reuses_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let authorized = Arc::new(AtomicUsize::new(0));
+
+        let accepted = connections.clone();
+        let authorized_requests = authorized.clone();
         tokio::spawn(async move {
-            while let Some(ev) = event_source.next().await {
-                match ev {
-                    Err(e) => {
-                        if let Err(_e) = tx.send(Err(OpenAIError::StreamError(e.to_string()))) {
-                            // rx dropped
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let authorized = authorized_requests.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0; 1024];
+                    while let Ok(n) = socket.read(&mut buf).await {
+                        if n == 0 {
                             break;
                         }
+                        let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                        if request.contains("authorization:") {
+                            authorized.fetch_add(1, Ordering::SeqCst);
+                        }
+                        let response = "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n";
+                        socket.write_all(response.as_bytes()).await.unwrap();
                     }
-                    Ok(event) => match event {
-                        Event::Message(message) => {
-                            if message.data == "[DONE]" {
-                                break;
-                            }
+                });
+            }
+        });
 
-                            let response = match serde_json::from_str::<O>(&message.data) {
-                                Err(e) => Err(OpenAIError::JSONDeserialize(e)),
-                                Ok(output) => Ok(output),
-                            };
+        let signed = Arc::new(AtomicUsize::new(0));
+        let signatures = signed.clone();
+        let client = Client::new()
+            .with_api_base(format!("http://{addr}"))
+            .with_api_key("sk-1")
+            .with_request_signer(move |_| {
+                signatures.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        client.preconnect().await.unwrap();
+        client.preconnect().await.unwrap();
 
-                            if let Err(_e) = tx.send(response) {
-                                // rx dropped
-                                break;
-                            }
-                        }
-                        Event::Open => continue,
-                    },
-                }
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        assert_eq!(authorized.load(Ordering::SeqCst), 0);
+        assert_eq!(signed.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "models")]
+    #[tokio::test]
+    async fn test_deadline_exceeded() {
+        use std::time::{Duration, Instant};
+
+        use crate::{error::OpenAIError, RequestOptions};
+
+        // Accepts connections but never responds
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut sockets = vec![];
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                sockets.push(socket);
             }
+        });
+
+        let client = Client::new().with_api_base(format!("http://{addr}"));
 
-            event_source.close();
+        let started = Instant::now();
+        let options = RequestOptions::new().with_timeout(Duration::from_millis(200));
+        let response = client.models().with_options(options).list().await;
+        assert!(matches!(response, Err(OpenAIError::DeadlineExceeded)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // No request is made once the deadline has passed
+        let options = RequestOptions::new().with_deadline(Instant::now());
+        let response = client.models().with_options(options).list().await;
+        assert!(matches!(response, Err(OpenAIError::DeadlineExceeded)));
+    }
+
+    #[cfg(feature = "models")]
+    #[tokio::test]
+    async fn test_user_agent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"object":"list","data":[]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_lowercase()
+        });
+
+        let client = Client::new()
+            .with_api_base(format!("http://{addr}"))
+            .with_user_agent("my-app/1.2.0");
+        client.models().list().await.unwrap();
+
+        let expected = format!(
+            "user-agent: async-openai/{} my-app/1.2.0\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert!(request.await.unwrap().contains(&expected));
+    }
+
+    #[cfg(feature = "models")]
+    #[tokio::test]
+    async fn test_per_request_credentials() {
+        use crate::RequestOptions;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"object":"list","data":[]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_lowercase()
+        });
+
+        let client = Client::new()
+            .with_api_base(format!("http://{addr}"))
+            .with_api_key("sk-client")
+            .with_org_id("org-client")
+            .with_project_id("proj_client");
+        let options = RequestOptions::new()
+            .with_api_key("sk-customer")
+            .with_org_id("org-customer")
+            .with_project_id("proj_customer");
+        client.models().with_options(options).list().await.unwrap();
+
+        let request = request.await.unwrap();
+        assert!(request.contains("authorization: bearer sk-customer\r\n"));
+        assert!(request.contains("openai-organization: org-customer\r\n"));
+        assert!(request.contains("openai-project: proj_customer\r\n"));
+        assert!(!request.contains("client"));
+    }
+
+    #[cfg(any(feature = "completions", feature = "fine-tunes", feature = "images"))]
+    #[tokio::test]
+    async fn test_stream_reports_status() {
+        use std::sync::Mutex;
+
+        use futures::StreamExt;
+
+        use crate::{runtime::BoxFuture, AuthProvider, RequestOptions};
+
+        #[derive(Debug, Default)]
+        struct Recorder(Mutex<Vec<(String, reqwest::StatusCode)>>);
+
+        impl AuthProvider for Arc<Recorder> {
+            fn token(&self) -> BoxFuture<'_, Result<String, OpenAIError>> {
+                Box::pin(async { Ok("sk-pool".to_string()) })
+            }
+
+            fn on_response(&self, token: &str, status: reqwest::StatusCode) {
+                self.0.lock().unwrap().push((token.to_string(), status));
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let body =
+                r#"{"error":{"message":"slow down","type":"requests","param":null,"code":null}}"#;
+            let response = format!(
+                "HTTP/1.1 429 Too Many Requests\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_lowercase()
         });
 
-        Box::pin(tokio_stream::wrappers::UnboundedReceiverStream::new(rx))
+        let recorder = Arc::new(Recorder::default());
+        let client = Client::new()
+            .with_api_base(format!("http://{addr}"))
+            .with_auth_provider(recorder.clone());
+        let builder = client
+            .inner
+            .http_client
+            .post(format!("http://{addr}/realtime/sessions"));
+        let mut stream = client
+            .stream::<serde_json::Value>(builder, &RequestOptions::new(), None)
+            .await;
+
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(OpenAIError::ApiError(e))) if e.message == "slow down"
+        ));
+        let request = request.await.unwrap();
+        assert!(request.contains("authorization: bearer sk-pool\r\n"));
+        assert!(request.contains("openai-beta: realtime=v1\r\n"));
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [(
+                "sk-pool".to_string(),
+                reqwest::StatusCode::TOO_MANY_REQUESTS
+            )]
+        );
+    }
+
+    #[cfg(feature = "_api")]
+    #[test]
+    fn test_beta_header() {
+        use crate::RequestOptions;
+
+        let beta = |client: &Client, path: &str, options: &RequestOptions| {
+            client
+                .beta_header(path, options)
+                .unwrap()
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+        let options = RequestOptions::new();
+        let client = Client::new();
+        assert_eq!(beta(&client, "/v1/models", &options), None);
+        assert_eq!(
+            beta(&client, "/v1/realtime/sessions", &options).as_deref(),
+            Some("realtime=v1")
+        );
+
+        let client = client.with_beta("realtime", "v2");
+        assert_eq!(
+            beta(&client, "/v1/realtime", &options).as_deref(),
+            Some("realtime=v2")
+        );
+        let options = options.with_beta("realtime", "v3").with_beta("other", "v1");
+        assert_eq!(
+            beta(&client, "/v1/models", &options).as_deref(),
+            Some("other=v1, realtime=v3")
+        );
+        assert!(client
+            .beta_header("/v1/models", &RequestOptions::new().with_beta("a\n", "b"))
+            .is_err());
+    }
+
+    #[cfg(feature = "files")]
+    #[tokio::test]
+    async fn test_list_all_follows_pages() {
+        use futures::StreamExt;
+
+        use crate::types::{ListOrder, ListParams};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = tokio::spawn(async move {
+            let pages = [
+                r#"{"object":"list","data":[{"id":"a","object":"file","bytes":1,"created_at":0,"filename":"a.jsonl","purpose":"fine-tune"},{"id":"b","object":"file","bytes":1,"created_at":0,"filename":"b.jsonl","purpose":"fine-tune"}],"has_more":true}"#,
+                r#"{"object":"list","data":[{"id":"c","object":"file","bytes":1,"created_at":0,"filename":"c.jsonl","purpose":"fine-tune"}],"has_more":false}"#,
+            ];
+            let mut requests = vec![];
+            for body in pages {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 1024];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                requests.push(request.lines().next().unwrap().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        let client = Client::new().with_api_base(format!("http://{addr}"));
+        let ids: Vec<String> = client
+            .files()
+            .list_all(&ListParams {
+                limit: Some(2),
+                order: Some(ListOrder::Desc),
+                ..Default::default()
+            })
+            .map(|file| file.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(ids, ["a", "b", "c"]);
+
+        let requests = requests.await.unwrap();
+        assert_eq!(requests[0], "GET /files?limit=2&order=desc HTTP/1.1");
+        assert_eq!(
+            requests[1],
+            "GET /files?limit=2&order=desc&after=b HTTP/1.1"
+        );
     }
 }
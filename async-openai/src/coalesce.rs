@@ -0,0 +1,130 @@
+//! Sharing of a single API call among concurrent identical requests.
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use futures::{future::Shared, FutureExt};
+
+use crate::{
+    error::OpenAIError,
+    runtime::{BoxFuture, MaybeSend},
+};
+
+type SharedCall<T> = Shared<BoxFuture<'static, Result<T, SharedError>>>;
+
+/// Error of a shared call: the original, taken by the caller which started the call, and a
+/// copy for the callers which joined it.
+#[derive(Clone)]
+struct SharedError {
+    original: Arc<Mutex<Option<OpenAIError>>>,
+    joined: Arc<OpenAIError>,
+}
+
+impl SharedError {
+    fn new(error: OpenAIError) -> Self {
+        Self {
+            joined: Arc::new(copy(&error)),
+            original: Arc::new(Mutex::new(Some(error))),
+        }
+    }
+}
+
+/// Copy of `error`, with the message alone where the error it wraps cannot be copied.
+fn copy(error: &OpenAIError) -> OpenAIError {
+    match error {
+        OpenAIError::Reqwest(_) | OpenAIError::Transport(_) => {
+            // Both are displayed as "http error: {0}"
+            let message = error.to_string();
+            let message = message.strip_prefix("http error: ").unwrap_or(&message);
+            OpenAIError::Transport(message.into())
+        }
+        OpenAIError::ApiError(e) => OpenAIError::ApiError(e.clone()),
+        OpenAIError::JSONDeserialize(e) => {
+            OpenAIError::JSONDeserialize(serde::de::Error::custom(e))
+        }
+        OpenAIError::InvalidResponse(e) => OpenAIError::InvalidResponse(e.clone()),
+        OpenAIError::CSVDeserialize(e) => OpenAIError::CSVDeserialize(e.clone()),
+        OpenAIError::JSONSerialize(e) => OpenAIError::JSONSerialize(serde::ser::Error::custom(e)),
+        OpenAIError::FileSaveError(e) => OpenAIError::FileSaveError(e.clone()),
+        OpenAIError::FileReadError(e) => OpenAIError::FileReadError(e.clone()),
+        OpenAIError::StreamError(e) => OpenAIError::StreamError(e.clone()),
+        OpenAIError::InvalidArgument(e) => OpenAIError::InvalidArgument(e.clone()),
+        OpenAIError::InvalidConfig(e) => OpenAIError::InvalidConfig(e.clone()),
+        OpenAIError::TooManyItems(max) => OpenAIError::TooManyItems(*max),
+        OpenAIError::DeadlineExceeded => OpenAIError::DeadlineExceeded,
+        OpenAIError::Cancelled => OpenAIError::Cancelled,
+        OpenAIError::FineTuneFailed { fine_tune, message } => {
+            match serde_json::to_value(fine_tune).and_then(serde_json::from_value) {
+                Ok(fine_tune) => OpenAIError::FineTuneFailed {
+                    fine_tune,
+                    message: message.clone(),
+                },
+                Err(e) => OpenAIError::JSONSerialize(e),
+            }
+        }
+        OpenAIError::Coalesced(e) => OpenAIError::Coalesced(e.clone()),
+    }
+}
+
+/// Calls in flight keyed by their serialized request body.
+pub(crate) struct InFlight<T> {
+    calls: Arc<Mutex<HashMap<Bytes, SharedCall<T>>>>,
+}
+
+impl<T> Default for InFlight<T> {
+    fn default() -> Self {
+        Self {
+            calls: Default::default(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for InFlight<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_flight = self.calls.lock().unwrap().len();
+        f.debug_struct("InFlight")
+            .field("in_flight", &in_flight)
+            .finish()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> InFlight<T> {
+    /// Wait for the call already in flight for `key`, or start `call` when there is none.
+    ///
+    /// The call is forgotten as soon as it completes, so results are never reused by
+    /// requests made afterwards. The caller which started the call gets its error as is, the
+    /// callers which joined it get a copy wrapped in [OpenAIError::Coalesced].
+    pub(crate) async fn run<F>(&self, key: Bytes, call: F) -> Result<T, OpenAIError>
+    where
+        F: std::future::Future<Output = Result<T, OpenAIError>> + MaybeSend + 'static,
+    {
+        let (shared, started) = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get(&key) {
+                Some(shared) => (shared.clone(), false),
+                None => {
+                    let registry = self.calls.clone();
+                    let completed = key.clone();
+                    let call: BoxFuture<'static, _> = Box::pin(async move {
+                        let result = call.await.map_err(SharedError::new);
+                        registry.lock().unwrap().remove(&completed);
+                        result
+                    });
+                    let shared = call.shared();
+                    calls.insert(key, shared.clone());
+                    (shared, true)
+                }
+            }
+        };
+
+        shared.await.map_err(|error| {
+            let original = started.then(|| error.original.lock().unwrap().take());
+            original
+                .flatten()
+                .unwrap_or(OpenAIError::Coalesced(error.joined))
+        })
+    }
+}
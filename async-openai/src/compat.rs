@@ -0,0 +1,282 @@
+//! Deserialization of responses from OpenAI compatible servers which are not strictly to spec.
+#[cfg(feature = "_api")]
+use serde::{
+    de::{
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, IntoDeserializer, Visitor,
+    },
+    forward_to_deserialize_any, Deserializer,
+};
+#[cfg(feature = "_api")]
+use serde_json::{Map, Value};
+
+#[cfg(feature = "_api")]
+use crate::error::OpenAIError;
+
+/// How strictly responses must match the types of this crate, see [Client::with_compatibility_mode](crate::Client::with_compatibility_mode).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompatibilityMode {
+    /// Responses must match the OpenAI API spec
+    #[default]
+    Strict,
+    /// Accept the slightly off-spec responses of servers such as Ollama, LM Studio and llama.cpp:
+    /// - missing and `null` fields take the default value of their type, e.g. zero `usage` counts
+    /// - numbers are accepted for strings, such as numeric ids, and numeric strings for numbers
+    /// - enum variants match regardless of case
+    Lenient,
+}
+
+#[cfg(feature = "_api")]
+impl CompatibilityMode {
+    pub(crate) fn deserialize_slice<O: DeserializeOwned>(
+        self,
+        bytes: &[u8],
+    ) -> Result<O, OpenAIError> {
+        match self {
+            Self::Strict => serde_json::from_slice(bytes).map_err(OpenAIError::JSONDeserialize),
+            Self::Lenient => serde_json::from_slice(bytes)
+                .map_err(OpenAIError::JSONDeserialize)
+                .and_then(|value| self.deserialize_value(value)),
+        }
+    }
+
+    pub(crate) fn deserialize_value<O: DeserializeOwned>(
+        self,
+        value: Value,
+    ) -> Result<O, OpenAIError> {
+        match self {
+            Self::Strict => serde_json::from_value(value),
+            Self::Lenient => O::deserialize(Lenient(value)),
+        }
+        .map_err(OpenAIError::JSONDeserialize)
+    }
+}
+
+/// Deserializer of a JSON value which fills in missing values and converts between
+/// strings and numbers where the type being deserialized asks for it.
+#[cfg(feature = "_api")]
+struct Lenient(Value);
+
+#[cfg(feature = "_api")]
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Lenient {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(feature = "_api")]
+impl Lenient {
+    fn seq(values: Vec<Value>) -> SeqDeserializer<std::vec::IntoIter<Lenient>, serde_json::Error> {
+        let values: Vec<Lenient> = values.into_iter().map(Lenient).collect();
+        SeqDeserializer::new(values.into_iter())
+    }
+
+    fn map(
+        map: Map<String, Value>,
+    ) -> MapDeserializer<'static, std::vec::IntoIter<(String, Lenient)>, serde_json::Error> {
+        let entries: Vec<(String, Lenient)> = map
+            .into_iter()
+            .map(|(key, value)| (key, Lenient(value)))
+            .collect();
+        MapDeserializer::new(entries.into_iter())
+    }
+}
+
+#[cfg(feature = "_api")]
+macro_rules! lenient_number {
+    ($($method:ident: $ty:ty => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0 {
+                    Value::Null => visitor.$visit(<$ty>::default()),
+                    Value::String(s) => match s.trim().parse::<$ty>() {
+                        Ok(n) => visitor.$visit(n),
+                        Err(_) => Value::String(s).$method(visitor),
+                    },
+                    value => value.$method(visitor),
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "_api")]
+impl<'de> Deserializer<'de> for Lenient {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(values) => visitor.visit_seq(Self::seq(values)),
+            Value::Object(map) => visitor.visit_map(Self::map(map)),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    lenient_number! {
+        deserialize_i8: i8 => visit_i8,
+        deserialize_i16: i16 => visit_i16,
+        deserialize_i32: i32 => visit_i32,
+        deserialize_i64: i64 => visit_i64,
+        deserialize_u8: u8 => visit_u8,
+        deserialize_u16: u16 => visit_u16,
+        deserialize_u32: u32 => visit_u32,
+        deserialize_u64: u64 => visit_u64,
+        deserialize_f32: f32 => visit_f32,
+        deserialize_f64: f64 => visit_f64,
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_bool(false),
+            value => value.deserialize_bool(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_string(String::new()),
+            Value::Number(n) => visitor.visit_string(n.to_string()),
+            value => value.deserialize_string(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(Lenient(value)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_seq(Self::seq(vec![])),
+            Value::Array(values) => visitor.visit_seq(Self::seq(values)),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_map(Self::map(Map::new())),
+            Value::Object(map) => visitor.visit_map(Self::map(map)),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut map = match self.0 {
+            Value::Null => Map::new(),
+            Value::Object(map) => map,
+            value => return value.deserialize_map(visitor),
+        };
+        // Missing fields are deserialized from null, which gives their default
+        for field in fields {
+            map.entry(*field).or_insert(Value::Null);
+        }
+        visitor.visit_map(Self::map(map))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::String(s) => {
+                let variant = variants
+                    .iter()
+                    .find(|variant| variant.eq_ignore_ascii_case(&s))
+                    .map_or(s, |variant| variant.to_string());
+                Value::String(variant).deserialize_enum(name, variants, visitor)
+            }
+            value => value.deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char bytes byte_buf unit unit_struct tuple_struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_api")]
+mod tests {
+    use serde::Deserialize;
+
+    use super::CompatibilityMode;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        Assistant,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Usage {
+        prompt_tokens: u32,
+        total_tokens: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Response {
+        id: String,
+        created: u32,
+        role: Role,
+        choices: Vec<String>,
+        usage: Usage,
+        user: Option<String>,
+    }
+
+    #[test]
+    fn test_lenient() {
+        let json = br#"{"id":42,"created":"1680000000","role":"Assistant","choices":null}"#;
+
+        assert!(CompatibilityMode::Strict
+            .deserialize_slice::<Response>(json)
+            .is_err());
+
+        let response: Response = CompatibilityMode::Lenient.deserialize_slice(json).unwrap();
+        assert_eq!(
+            response,
+            Response {
+                id: "42".into(),
+                created: 1680000000,
+                role: Role::Assistant,
+                choices: vec![],
+                usage: Usage {
+                    prompt_tokens: 0,
+                    total_tokens: 0
+                },
+                user: None,
+            }
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     client::Client,
+    config::RequestOptions,
     error::OpenAIError,
+    fallback::FallbackPolicy,
     types::{CompletionResponseStream, CreateCompletionRequest, CreateCompletionResponse},
 };
 
@@ -9,11 +11,29 @@ use crate::{
 /// tokens at each position.
 pub struct Completions<'c> {
     client: &'c Client,
+    options: RequestOptions,
+    fallback: Option<FallbackPolicy>,
 }
 
 impl<'c> Completions<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+            fallback: None,
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Retry calls of [Completions::create] with other models when they fail, see [FallbackPolicy].
+    pub fn with_fallback(mut self, policy: FallbackPolicy) -> Self {
+        self.fallback = Some(policy);
+        self
     }
 
     /// Creates a completion for the provided prompt and parameters
@@ -26,7 +46,33 @@ impl<'c> Completions<'c> {
                 "When stream is true, use Completion::create_stream".into(),
             ));
         }
-        self.client.post("/completions", request).await
+        let Some(policy) = &self.fallback else {
+            return self
+                .client
+                .post("/completions", request, &self.options)
+                .await;
+        };
+
+        let mut result = self
+            .client
+            .post("/completions", request.clone(), &self.options)
+            .await;
+        for fallback in policy.fallbacks() {
+            match &result {
+                Err(e) if policy.should_fall_back(e) => {
+                    tracing::warn!("Falling back to {}: {e}", fallback.model)
+                }
+                _ => break,
+            }
+            let mut request = request.clone();
+            request.model = fallback.model.clone();
+            if fallback.max_tokens.is_some() {
+                request.max_tokens = fallback.max_tokens;
+            }
+            let client = fallback.client.as_ref().unwrap_or(self.client);
+            result = client.post("/completions", request, &self.options).await;
+        }
+        result
     }
 
     /// Creates a completion request for the provided prompt and parameters
@@ -48,6 +94,9 @@ impl<'c> Completions<'c> {
 
         request.stream = Some(true);
 
-        Ok(self.client.post_stream("/completions", request).await)
+        Ok(self
+            .client
+            .post_stream("/completions", request, &self.options, None)
+            .await)
     }
 }
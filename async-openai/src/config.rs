@@ -0,0 +1,568 @@
+//! Configuration of the HTTP connections made by [Client](crate::Client), and of individual API calls.
+use std::{
+    collections::BTreeMap,
+    fmt,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "_post")]
+use bytes::Bytes;
+#[cfg(feature = "_api")]
+use reqwest::header::{HeaderMap, HeaderValue};
+#[cfg(feature = "_post")]
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[cfg(feature = "_api")]
+use crate::client::{ORGANIZATION_HEADER, PROJECT_HEADER};
+use crate::error::OpenAIError;
+#[cfg(feature = "_post")]
+use crate::util::json_body;
+
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::dns::Resolve;
+
+/// Connection pool, TCP and HTTP/2 settings for the HTTP client used to make API calls.
+///
+/// Settings which are not set use the defaults of [reqwest]. On `wasm32` connections are
+/// managed by the browser and these settings are ignored.
+///
+/// ```
+/// use std::time::Duration;
+/// use async_openai::{Client, HttpConfig};
+///
+/// // Drop idle connections before the server or a load balancer closes them
+/// let config = HttpConfig::new()
+///     .with_pool_idle_timeout(Duration::from_secs(30))
+///     .with_tcp_keepalive(Duration::from_secs(60));
+///
+/// let client = Client::new().with_http_config(config);
+/// ```
+///
+/// DNS lookups can be overridden per domain, e.g. to pin `api.openai.com` to the address
+/// of a private endpoint, or replaced altogether with a [reqwest::dns::Resolve] implementation.
+///
+/// ```
+/// use std::net::SocketAddr;
+/// use async_openai::HttpConfig;
+///
+/// let config = HttpConfig::new()
+///     .with_resolve("api.openai.com", SocketAddr::from(([10, 0, 0, 12], 443)));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub struct HttpConfig {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<reqwest::Proxy>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+    http2_adaptive_window: Option<bool>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_keep_alive_timeout: Option<Duration>,
+    http2_keep_alive_while_idle: Option<bool>,
+    resolve_overrides: Vec<(String, Vec<SocketAddr>)>,
+    dns_resolver: Option<DnsResolver>,
+}
+
+/// Applies a custom resolver to a [reqwest::ClientBuilder], keeping its concrete type
+/// erased so the config stays `Clone` and `Debug`.
+#[derive(Clone)]
+struct DnsResolver(Arc<dyn Fn(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send + Sync>);
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DnsResolver")
+    }
+}
+
+impl HttpConfig {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Timeout of each request, from connecting until the response body has been read.
+    /// See [RequestOptions] for a deadline of a call including its retries.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout of making a connection
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Make connections through `proxy`. By default reqwest uses the proxies of the
+    /// `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` environment variables.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// How long an idle connection is kept in the pool. Set this lower than the idle timeout of
+    /// any proxy or load balancer in between to avoid "connection closed before message completed" errors.
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum number of idle connections kept in the pool per host
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Interval of TCP keepalive probes on open connections
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Adjust HTTP/2 flow control windows based on round trip time
+    pub fn with_http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = Some(enabled);
+        self
+    }
+
+    /// Interval of HTTP/2 PING frames that keep connections alive
+    pub fn with_http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// How long to wait for the acknowledgement of a HTTP/2 keep-alive PING before closing the connection
+    pub fn with_http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Send HTTP/2 keep-alive PINGs even when there are no requests in flight
+    pub fn with_http2_keep_alive_while_idle(mut self, enabled: bool) -> Self {
+        self.http2_keep_alive_while_idle = Some(enabled);
+        self
+    }
+
+    /// Resolve `domain` to `addr` instead of looking it up in DNS.
+    /// The port of `addr` is ignored, the port of the API base is used.
+    pub fn with_resolve(self, domain: &str, addr: SocketAddr) -> Self {
+        self.with_resolve_to_addrs(domain, &[addr])
+    }
+
+    /// Resolve `domain` to any of `addrs` instead of looking it up in DNS.
+    /// The ports of `addrs` are ignored, the port of the API base is used.
+    pub fn with_resolve_to_addrs(mut self, domain: &str, addrs: &[SocketAddr]) -> Self {
+        self.resolve_overrides
+            .push((domain.to_string(), addrs.to_vec()));
+        self
+    }
+
+    /// Resolve domains which are not overridden by [HttpConfig::with_resolve] with `resolver`
+    /// instead of the system resolver.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_dns_resolver<R: Resolve + 'static>(mut self, resolver: Arc<R>) -> Self {
+        self.dns_resolver = Some(DnsResolver(Arc::new(move |builder| {
+            builder.dns_resolver(resolver.clone())
+        })));
+        self
+    }
+
+    /// A [reqwest::ClientBuilder] with these settings applied, to further customize before building.
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.apply(reqwest::Client::builder());
+        #[cfg(target_arch = "wasm32")]
+        return reqwest::Client::builder();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if let Some(enabled) = self.http2_adaptive_window {
+            builder = builder.http2_adaptive_window(enabled);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder = builder.http2_keep_alive_timeout(timeout);
+        }
+        if let Some(enabled) = self.http2_keep_alive_while_idle {
+            builder = builder.http2_keep_alive_while_idle(enabled);
+        }
+        for (domain, addrs) in &self.resolve_overrides {
+            builder = builder.resolve_to_addrs(domain, addrs);
+        }
+        if let Some(resolver) = &self.dns_resolver {
+            builder = (resolver.0)(builder);
+        }
+
+        builder
+    }
+
+    /// Timeout of each request
+    #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// A [hyper::client::Builder] with the pool and HTTP/2 settings applied, for a custom connector.
+    /// Connecting, and so the connect timeout, proxy, DNS and TCP settings, is up to the connector.
+    #[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+    pub(crate) fn hyper_builder(&self) -> hyper::client::Builder {
+        let mut builder = hyper::Client::builder();
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(max);
+        }
+        if let Some(enabled) = self.http2_adaptive_window {
+            builder.http2_adaptive_window(enabled);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder.http2_keep_alive_timeout(timeout);
+        }
+        if let Some(enabled) = self.http2_keep_alive_while_idle {
+            builder.http2_keep_alive_while_idle(enabled);
+        }
+        builder
+    }
+}
+
+/// Options of the API calls made through an API group, set with its `with_options`.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use async_openai::{types::CreateModerationRequestArgs, Client, RequestOptions};
+///
+/// # tokio_test::block_on(async {
+/// let client = Client::new();
+/// let request = CreateModerationRequestArgs::default()
+///     .input("Lions want to eat the entire world")
+///     .build()
+///     .unwrap();
+///
+/// // Give up, including any retries, if there is no response within 5 seconds
+/// let options = RequestOptions::new().with_timeout(Duration::from_secs(5));
+/// let response = client.moderations().with_options(options).create(request).await;
+/// # });
+/// ```
+///
+/// Parameters which the typed requests cannot express, such as those of OpenAI compatible
+/// servers like vLLM or OpenRouter, can be added to the JSON body and the query string.
+///
+/// ```
+/// use async_openai::{Client, RequestOptions};
+/// use serde_json::json;
+///
+/// let options = RequestOptions::new()
+///     .with_extra_body_field("top_k", 40)
+///     .with_extra_body_field("provider", json!({"order": ["Together"]}))
+///     .with_extra_query("api-version", "2023-05-15");
+///
+/// let client = Client::new();
+/// let completions = client.completions().with_options(options);
+/// ```
+#[derive(Clone, Default)]
+pub struct RequestOptions {
+    deadline: Option<Instant>,
+    extra_body: Map<String, Value>,
+    extra_query: Vec<(String, String)>,
+    api_key: Option<String>,
+    org_id: Option<String>,
+    project_id: Option<String>,
+    betas: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for RequestOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestOptions")
+            .field("deadline", &self.deadline)
+            .field("extra_body", &self.extra_body)
+            .field("extra_query", &self.extra_query)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("org_id", &self.org_id)
+            .field("project_id", &self.project_id)
+            .field("betas", &self.betas)
+            .finish()
+    }
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Time by which a call must have completed, including its retries. Each attempt is given
+    /// the time remaining as its timeout, and a retry is not started when its backoff delay
+    /// would end after the deadline. Once the deadline has passed calls fail with
+    /// [OpenAIError::DeadlineExceeded].
+    ///
+    /// Streams are closed when the deadline passes. Not available on `wasm32`, where
+    /// `Instant::now` panics.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Deadline `timeout` from now, see [RequestOptions::with_deadline].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Fields merged into the JSON body of requests, replacing fields of the same name.
+    /// Form uploads of files and images do not have a JSON body and are sent as is.
+    pub fn with_extra_body(mut self, extra_body: Map<String, Value>) -> Self {
+        self.extra_body = extra_body;
+        self
+    }
+
+    /// Add a field to the JSON body of requests, see [RequestOptions::with_extra_body].
+    pub fn with_extra_body_field<K: Into<String>, V: Into<Value>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> Self {
+        self.extra_body.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a parameter to the query string of requests.
+    pub fn with_extra_query<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.extra_query.push((key.into(), value.into()));
+        self
+    }
+
+    /// API key of the calls, instead of that of the client or its [AuthProvider](crate::AuthProvider),
+    /// e.g. the key of the customer a multi-tenant backend is calling on behalf of.
+    pub fn with_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Organization id of the calls, instead of that of the client.
+    pub fn with_org_id<S: Into<String>>(mut self, org_id: S) -> Self {
+        self.org_id = Some(org_id.into());
+        self
+    }
+
+    /// Project id of the calls, instead of that of the client.
+    pub fn with_project_id<S: Into<String>>(mut self, project_id: S) -> Self {
+        self.project_id = Some(project_id.into());
+        self
+    }
+
+    /// Version of the beta `feature` of the calls, instead of that of the client, see
+    /// [Client::with_beta](crate::Client::with_beta).
+    pub fn with_beta<F: Into<String>, V: Into<String>>(mut self, feature: F, version: V) -> Self {
+        self.betas.insert(feature.into(), version.into());
+        self
+    }
+
+    #[cfg(feature = "_api")]
+    pub(crate) fn betas(&self) -> &BTreeMap<String, String> {
+        &self.betas
+    }
+
+    #[cfg(feature = "_api")]
+    pub(crate) fn api_key(&self) -> Option<&str> {
+        self.api_key.as_deref()
+    }
+
+    /// Headers replacing those of the client.
+    #[cfg(feature = "_api")]
+    pub(crate) fn headers(&self) -> Result<HeaderMap, OpenAIError> {
+        let mut headers = HeaderMap::new();
+        let overrides = [
+            (ORGANIZATION_HEADER, &self.org_id, "organization id"),
+            (PROJECT_HEADER, &self.project_id, "project id"),
+        ];
+        for (name, value, what) in overrides {
+            if let Some(value) = value {
+                let value = HeaderValue::from_str(value).map_err(|_| {
+                    OpenAIError::InvalidArgument(format!("{what} is not a valid header value"))
+                })?;
+                headers.insert(name, value);
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Serialize `request` with the extra body fields merged in, which needs `request` to
+    /// serialize to a JSON object.
+    #[cfg(feature = "_post")]
+    pub(crate) fn json_body<I: Serialize>(&self, request: &I) -> Result<Bytes, OpenAIError> {
+        if self.extra_body.is_empty() {
+            return json_body(request);
+        }
+
+        let mut body = match serde_json::to_value(request).map_err(OpenAIError::JSONSerialize)? {
+            Value::Object(body) => body,
+            _ => {
+                return Err(OpenAIError::InvalidArgument(
+                    "extra_body requires a JSON object body".into(),
+                ))
+            }
+        };
+        body.extend(self.extra_body.clone());
+        json_body(&body)
+    }
+
+    /// Key under which calls of `body` with these options can share a single API call: the
+    /// body followed by the credentials, query parameters and betas of the options, so calls on
+    /// behalf of different tenants are never shared. `None` with a deadline, which is specific
+    /// to each call.
+    #[cfg(feature = "embeddings")]
+    pub(crate) fn coalescing_key(&self, body: &Bytes) -> Option<Bytes> {
+        if self.deadline.is_some() {
+            return None;
+        }
+        let overrides = (
+            &self.api_key,
+            &self.org_id,
+            &self.project_id,
+            &self.extra_query,
+            &self.betas,
+        );
+        let overrides = serde_json::to_vec(&overrides).ok()?;
+        // Compact JSON bodies have no raw newline, so the separator cannot be confused
+        let mut key = Vec::with_capacity(body.len() + 1 + overrides.len());
+        key.extend_from_slice(body);
+        key.push(b'\n');
+        key.extend_from_slice(&overrides);
+        Some(key.into())
+    }
+
+    /// Time remaining until the deadline, if any.
+    pub(crate) fn remaining(&self) -> Result<Option<Duration>, OpenAIError> {
+        match self.deadline {
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => Ok(Some(remaining)),
+                _ => Err(OpenAIError::DeadlineExceeded),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Add the extra query parameters to `request`, and give it the time remaining until the
+    /// deadline as its timeout.
+    #[cfg(feature = "_api")]
+    pub(crate) fn apply(&self, request: &mut reqwest::Request) -> Result<(), OpenAIError> {
+        if !self.extra_query.is_empty() {
+            request
+                .url_mut()
+                .query_pairs_mut()
+                .extend_pairs(&self.extra_query);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(remaining) = self.remaining()? {
+            *request.timeout_mut() = Some(remaining);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = request;
+        Ok(())
+    }
+
+    /// Map a timeout caused by the deadline to [OpenAIError::DeadlineExceeded].
+    pub(crate) fn map_err(&self, error: reqwest::Error) -> OpenAIError {
+        if error.is_timeout() && self.remaining().is_err() {
+            OpenAIError::DeadlineExceeded
+        } else {
+            OpenAIError::Reqwest(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+    use super::HttpConfig;
+    #[cfg(feature = "_post")]
+    use super::RequestOptions;
+    #[cfg(feature = "_post")]
+    use crate::error::OpenAIError;
+    use crate::Client;
+
+    #[tokio::test]
+    async fn test_resolve_override() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let response = "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let config = HttpConfig::new()
+            .with_resolve("api.openai.invalid", SocketAddr::from(([127, 0, 0, 1], 0)));
+
+        let client = Client::new()
+            .with_http_config(config)
+            .with_api_base(format!("http://api.openai.invalid:{port}"));
+
+        client.preconnect().await.unwrap();
+    }
+
+    #[cfg(feature = "_post")]
+    #[test]
+    fn test_extra_body_and_query() {
+        let options = RequestOptions::new()
+            .with_extra_body_field("top_k", 40)
+            .with_extra_body_field("model", "llama")
+            .with_extra_query("api-version", "2023-05-15");
+
+        let body = options
+            .json_body(&serde_json::json!({"model": "gpt-3.5-turbo", "n": 1}))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({"model": "llama", "n": 1, "top_k": 40})
+        );
+        assert!(matches!(
+            options.json_body(&["not", "an", "object"]),
+            Err(OpenAIError::InvalidArgument(_))
+        ));
+
+        let mut request = reqwest::Request::new(
+            reqwest::Method::GET,
+            "https://api.openai.com/v1/models?limit=1".parse().unwrap(),
+        );
+        options.apply(&mut request).unwrap();
+        assert_eq!(
+            request.url().query(),
+            Some("limit=1&api-version=2023-05-15")
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! Making API calls through a custom [hyper] connector instead of reqwest's connection pool,
+//! e.g. to reach a local OpenAI compatible server listening on a unix domain socket.
+use std::{
+    fmt,
+    future::Future,
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures::{future::Either, StreamExt};
+use hyper::client::connect::{Connect, Connected, Connection};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::{
+    config::{HttpConfig, RequestOptions},
+    error::OpenAIError,
+};
+
+type SendRequest =
+    Arc<dyn Fn(hyper::Request<hyper::Body>) -> hyper::client::ResponseFuture + Send + Sync>;
+
+/// A [hyper::Client] with its connector type erased, so the client stays `Clone` and `Debug`.
+#[derive(Clone)]
+pub(crate) struct Transport {
+    /// Builds a client of the connector with the given settings
+    build: Arc<dyn Fn(&HttpConfig) -> SendRequest + Send + Sync>,
+    send: SendRequest,
+    timeout: Option<Duration>,
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transport")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport {
+    pub(crate) fn new<C>(connector: C, config: &HttpConfig) -> Self
+    where
+        C: Connect + Clone + Send + Sync + 'static,
+    {
+        let build = move |config: &HttpConfig| -> SendRequest {
+            let client = config
+                .hyper_builder()
+                .build::<_, hyper::Body>(connector.clone());
+            Arc::new(move |request| client.request(request))
+        };
+        Self {
+            send: build(config),
+            build: Arc::new(build),
+            timeout: config.timeout(),
+        }
+    }
+
+    /// The same connector with other settings
+    pub(crate) fn with_config(&self, config: &HttpConfig) -> Self {
+        Self {
+            send: (self.build)(config),
+            build: self.build.clone(),
+            timeout: config.timeout(),
+        }
+    }
+
+    /// Send `request`, converting between reqwest's and hyper's request and response types.
+    /// The shorter of the timeouts of the request and of the [HttpConfig] bounds the time
+    /// until the whole response body has been received. As with reqwest, a timeout is
+    /// [OpenAIError::DeadlineExceeded] only when the deadline of `options` has passed.
+    pub(crate) async fn send(
+        &self,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<reqwest::Response, OpenAIError> {
+        let body = match request.body() {
+            None => hyper::Body::empty(),
+            Some(body) => match body.as_bytes() {
+                Some(bytes) => hyper::Body::from(Bytes::copy_from_slice(bytes)),
+                None => {
+                    return Err(OpenAIError::InvalidArgument(
+                        "streamed request bodies, such as file uploads, cannot be sent through a custom connector".into(),
+                    ))
+                }
+            },
+        };
+
+        let mut builder = hyper::Request::builder()
+            .method(request.method().clone())
+            .uri(request.url().as_str());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = request.headers().clone();
+        }
+        let http_request = builder
+            .body(body)
+            .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))?;
+
+        let response = (self.send)(http_request);
+        let timeout = request
+            .timeout()
+            .copied()
+            .into_iter()
+            .chain(self.timeout)
+            .min();
+        let response = match timeout {
+            Some(timeout) => {
+                let deadline = tokio::time::Instant::now() + timeout;
+                tokio::time::timeout_at(deadline, response)
+                    .await
+                    .map_err(|_| match options.remaining() {
+                        Err(e) => e,
+                        Ok(_) => OpenAIError::Transport(
+                            io::Error::new(io::ErrorKind::TimedOut, "request timed out").into(),
+                        ),
+                    })?
+                    .map_err(|e| OpenAIError::Transport(e.into()))?
+                    .map(|body| with_deadline(body, deadline))
+            }
+            None => response
+                .await
+                .map_err(|e| OpenAIError::Transport(e.into()))?,
+        };
+
+        Ok(response.into())
+    }
+}
+
+/// `body`, failing with a timeout error when it has not been received by `deadline`.
+fn with_deadline(body: hyper::Body, deadline: tokio::time::Instant) -> hyper::Body {
+    let sleep = Box::pin(tokio::time::sleep_until(deadline));
+    hyper::Body::wrap_stream(futures::stream::unfold(
+        (body, sleep, false),
+        |(mut body, mut sleep, timed_out)| async move {
+            if timed_out {
+                return None;
+            }
+            let chunk = match futures::future::select(body.next(), sleep.as_mut()).await {
+                Either::Left((chunk, _)) => chunk,
+                Either::Right(_) => None,
+            };
+            match chunk {
+                Some(chunk) => Some((
+                    chunk.map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
+                    (body, sleep, false),
+                )),
+                None if sleep.is_elapsed() => {
+                    let error = io::Error::new(io::ErrorKind::TimedOut, "request timed out");
+                    Some((Err(error), (body, sleep, true)))
+                }
+                None => None,
+            }
+        },
+    ))
+}
+
+/// Connects to a unix domain socket, ignoring the host and port of the API base.
+///
+/// ```no_run
+/// use async_openai::{Client, UnixConnector};
+///
+/// // The path of the API base is used as is, the host is only sent in the Host header
+/// let client = Client::new()
+///     .with_api_base("http://localhost/v1")
+///     .with_connector(UnixConnector::new("/run/llama.sock"));
+/// ```
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct UnixConnector {
+    path: Arc<PathBuf>,
+}
+
+#[cfg(unix)]
+impl UnixConnector {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Arc::new(path.into()),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl tower_service::Service<hyper::Uri> for UnixConnector {
+    type Response = UnixConnection;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<UnixConnection>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: hyper::Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move {
+            let stream = tokio::net::UnixStream::connect(path.as_path()).await?;
+            Ok(UnixConnection(stream))
+        })
+    }
+}
+
+/// Connection made by [UnixConnector].
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct UnixConnection(tokio::net::UnixStream);
+
+#[cfg(unix)]
+impl Connection for UnixConnection {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+#[cfg(unix)]
+impl AsyncRead for UnixConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for UnixConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(unix, feature = "models"))]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::UnixListener,
+        };
+
+        use crate::{Client, UnixConnector};
+
+        let path = std::env::temp_dir().join(format!("async-openai-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(buf[..n].starts_with(b"GET /v1/models HTTP/1.1"));
+            let body = r#"{"object":"list","data":[{"id":"llama","object":"model","created":0,"owned_by":"local"}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = Client::new()
+            .with_api_base("http://localhost/v1")
+            .with_connector(UnixConnector::new(&path));
+        let models = client.models().list().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(models.data[0].id, "llama");
+    }
+
+    #[cfg(all(unix, feature = "models"))]
+    #[tokio::test]
+    async fn test_unix_socket_timeout() {
+        use std::time::Duration;
+
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::UnixListener,
+        };
+
+        use crate::{error::OpenAIError, Client, HttpConfig, UnixConnector};
+
+        let path =
+            std::env::temp_dir().join(format!("async-openai-timeout-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await.unwrap();
+            // Send the headers and part of the body, then stall
+            let response = "HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\n{\"object\"";
+            socket.write_all(response.as_bytes()).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let client = Client::new()
+            .with_api_base("http://localhost/v1")
+            .with_connector(UnixConnector::new(&path))
+            .with_http_config(HttpConfig::new().with_timeout(Duration::from_millis(200)));
+        let error = client.models().list().await.unwrap_err();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(error, OpenAIError::Reqwest(e) if e.is_body() || e.is_decode()));
+    }
+
+    #[cfg(all(unix, feature = "models"))]
+    #[tokio::test]
+    async fn test_unix_socket_timeout_without_deadline() {
+        use std::time::Duration;
+
+        use tokio::{io::AsyncReadExt, net::UnixListener};
+
+        use crate::{error::OpenAIError, Client, HttpConfig, RequestOptions, UnixConnector};
+
+        let path = std::env::temp_dir().join(format!(
+            "async-openai-no-deadline-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0; 1024];
+                    let _ = socket.read(&mut buf).await.unwrap();
+                    // Never respond
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                });
+            }
+        });
+
+        let client = Client::new()
+            .with_api_base("http://localhost/v1")
+            .with_connector(UnixConnector::new(&path))
+            .with_http_config(HttpConfig::new().with_timeout(Duration::from_millis(200)));
+        let error = client.models().list().await.unwrap_err();
+        let options = RequestOptions::new().with_timeout(Duration::from_millis(100));
+        let deadline_error = client.models().with_options(options).list().await;
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(deadline_error, Err(OpenAIError::DeadlineExceeded)));
+        // The timeout of the client is not a deadline of the call
+        let OpenAIError::Transport(error) = error else {
+            panic!("expected a transport error, got {error:?}");
+        };
+        let error = error.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,117 @@
+//! Streams of the text generated by the model, without the metadata of each chunk.
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::Stream;
+
+use crate::error::OpenAIError;
+
+/// Chunk of a streamed response which may carry generated text.
+pub trait TextDelta {
+    /// Text of the first choice in this chunk, if any.
+    fn into_text_delta(self) -> Option<String>;
+}
+
+#[cfg(feature = "completions")]
+impl TextDelta for crate::types::CreateCompletionResponse {
+    fn into_text_delta(self) -> Option<String> {
+        self.choices
+            .into_iter()
+            .find(|choice| choice.index == 0)
+            .map(|choice| choice.text)
+    }
+}
+
+/// Turn a stream of response chunks into a stream of the text of their first choice, for UI
+/// code which only shows the text. Chunks without text for the first choice are skipped,
+/// and errors are passed through.
+///
+/// ```no_run
+/// use async_openai::{types::CreateCompletionRequestArgs, Client, TextStreamExt};
+/// use futures::StreamExt;
+///
+/// # tokio_test::block_on(async {
+/// let client = Client::new();
+/// let request = CreateCompletionRequestArgs::default()
+///     .model("text-davinci-003")
+///     .prompt("Tell me a story")
+///     .build()
+///     .unwrap();
+///
+/// let mut text = client.completions().create_stream(request).await.unwrap().text_deltas();
+/// while let Some(delta) = text.next().await {
+///     print!("{}", delta.unwrap());
+/// }
+/// # });
+/// ```
+pub trait TextStreamExt: Sized {
+    fn text_deltas(self) -> TextDeltas<Self> {
+        TextDeltas { inner: self }
+    }
+}
+
+impl<S, T> TextStreamExt for S
+where
+    S: Stream<Item = Result<T, OpenAIError>>,
+    T: TextDelta,
+{
+}
+
+/// Stream returned by [TextStreamExt::text_deltas].
+#[derive(Debug)]
+pub struct TextDeltas<S> {
+    inner: S,
+}
+
+impl<S, T> Stream for TextDeltas<S>
+where
+    S: Stream<Item = Result<T, OpenAIError>> + Unpin,
+    T: TextDelta,
+{
+    type Item = Result<String, OpenAIError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => match chunk.into_text_delta() {
+                    Some(text) if !text.is_empty() => return Poll::Ready(Some(Ok(text))),
+                    _ => continue,
+                },
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "completions"))]
+mod tests {
+    use futures::StreamExt;
+
+    use super::TextStreamExt;
+    use crate::{test_utils::SimulatedStream, types::CreateCompletionResponse};
+
+    #[tokio::test]
+    async fn test_text_deltas() {
+        let response: CreateCompletionResponse = serde_json::from_str(
+            r#"{"id": "cmpl-1", "object": "text_completion", "created": 0, "model": "text-davinci-003",
+                "choices": [
+                    {"text": "Hello world", "index": 0, "logprobs": null, "finish_reason": "stop"},
+                    {"text": "Goodbye, cruel world", "index": 1, "logprobs": null, "finish_reason": "stop"}
+                ]}"#,
+        )
+        .unwrap();
+
+        let deltas: Vec<String> = SimulatedStream::new()
+            .with_chunk_size(4)
+            .completion(response)
+            .text_deltas()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(deltas, ["Hell", "o wo", "rld"]);
+    }
+}
@@ -4,7 +4,7 @@ use base64::{engine::general_purpose, Engine as _};
 use rand::{distributions::Alphanumeric, Rng};
 use reqwest::Url;
 
-use crate::error::OpenAIError;
+use crate::{error::OpenAIError, runtime::fs};
 
 fn create_paths<P: AsRef<Path>>(url: &Url, base_dir: P) -> (PathBuf, PathBuf) {
     let mut dir = PathBuf::from(base_dir.as_ref());
@@ -40,13 +40,13 @@ pub(crate) async fn download_url<P: AsRef<Path>>(
 
     let (dir, file_path) = create_paths(&parsed_url, dir);
 
-    tokio::fs::create_dir_all(dir)
+    fs::create_dir_all(&dir)
         .await
         .map_err(|e| OpenAIError::FileSaveError(e.to_string()))?;
 
-    tokio::fs::write(
+    fs::write(
         file_path.as_path(),
-        response
+        &response
             .bytes()
             .await
             .map_err(|e| OpenAIError::FileSaveError(e.to_string()))?,
@@ -68,9 +68,9 @@ pub(crate) async fn save_b64<P: AsRef<Path>>(b64: &str, dir: P) -> Result<PathBu
 
     let path = PathBuf::from(dir.as_ref()).join(filename);
 
-    tokio::fs::write(
+    fs::write(
         path.as_path(),
-        general_purpose::STANDARD
+        &general_purpose::STANDARD
             .decode(b64)
             .map_err(|e| OpenAIError::FileSaveError(e.to_string()))?,
     )
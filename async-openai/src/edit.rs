@@ -1,4 +1,5 @@
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
     types::{CreateEditRequest, CreateEditResponse},
     Client,
@@ -8,11 +9,21 @@ use crate::{
 /// an edited version of the prompt.
 pub struct Edits<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> Edits<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Creates a new edit for the provided input, instruction, and parameters
@@ -20,6 +31,6 @@ impl<'c> Edits<'c> {
         &self,
         request: CreateEditRequest,
     ) -> Result<CreateEditResponse, OpenAIError> {
-        self.client.post("/edits", request).await
+        self.client.post("/edits", request, &self.options).await
     }
 }
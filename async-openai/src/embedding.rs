@@ -1,7 +1,15 @@
+use std::{ops::Range, sync::Arc};
+
+use futures::StreamExt;
+
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
-    types::{CreateEmbeddingRequest, CreateEmbeddingResponse},
-    Client,
+    types::{
+        CreateEmbeddingBatchResponse, CreateEmbeddingRequest, CreateEmbeddingResponse, Embedding,
+        EmbeddingBatchOptions, EmbeddingInput, EmbeddingUsage,
+    },
+    Client, EmbeddingCache, EmbeddingCacheKey,
 };
 
 /// Get a vector representation of a given input that can be easily
@@ -10,25 +18,542 @@ use crate::{
 /// Related guide: [Embeddings](https://platform.openai.com/docs/guides/embeddings/what-are-embeddings)
 pub struct Embeddings<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> Embeddings<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Creates an embedding vector representing the input text.
+    ///
+    /// See [Client::with_embedding_coalescing] to share calls among identical requests, and
+    /// [Client::with_embedding_cache] to reuse the embeddings of previous calls.
     pub async fn create(
         &self,
         request: CreateEmbeddingRequest,
     ) -> Result<CreateEmbeddingResponse, OpenAIError> {
-        self.client.post("/embeddings", request).await
+        match self.client.embedding_cache() {
+            Some(cache) => self.create_cached(request, cache).await,
+            None => self.create_uncached(request).await,
+        }
+    }
+
+    /// Embeddings of the inputs found in `cache`, and of the others from the API.
+    async fn create_cached(
+        &self,
+        mut request: CreateEmbeddingRequest,
+        cache: &dyn EmbeddingCache,
+    ) -> Result<CreateEmbeddingResponse, OpenAIError> {
+        let inputs = split_inputs(std::mem::take(&mut request.input));
+        let keys: Vec<EmbeddingCacheKey> = inputs
+            .iter()
+            .map(|input| EmbeddingCacheKey::new(&request.model, request.dimensions, input))
+            .collect();
+
+        let mut data: Vec<Option<Embedding>> = keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                cache.get(key).map(|embedding| Embedding {
+                    index: index as u32,
+                    object: "embedding".into(),
+                    embedding,
+                })
+            })
+            .collect();
+        let missing: Vec<usize> = (0..inputs.len()).filter(|&i| data[i].is_none()).collect();
+
+        let mut response = CreateEmbeddingResponse {
+            object: "list".into(),
+            model: request.model.clone(),
+            data: vec![],
+            usage: EmbeddingUsage {
+                prompt_tokens: 0,
+                total_tokens: 0,
+            },
+        };
+        if !missing.is_empty() {
+            let missing_inputs: Vec<EmbeddingInput> =
+                missing.iter().map(|&i| inputs[i].clone()).collect();
+            let request = CreateEmbeddingRequest {
+                input: join_inputs(&missing_inputs),
+                ..request
+            };
+            let fetched = self.create_uncached(request).await?;
+            for mut embedding in fetched.data {
+                let Some(&index) = missing.get(embedding.index as usize) else {
+                    continue;
+                };
+                cache.put(keys[index].clone(), embedding.embedding.clone());
+                embedding.index = index as u32;
+                data[index] = Some(embedding);
+            }
+            response.model = fetched.model;
+            response.usage = fetched.usage;
+        }
+
+        response.data = data.into_iter().flatten().collect();
+        Ok(response)
+    }
+
+    async fn create_uncached(
+        &self,
+        request: CreateEmbeddingRequest,
+    ) -> Result<CreateEmbeddingResponse, OpenAIError> {
+        let dimensions = request.dimensions;
+        let response: CreateEmbeddingResponse = match self.client.embedding_calls() {
+            Some(calls) => {
+                let body = self.options.json_body(&request)?;
+                match self.options.coalescing_key(&body) {
+                    Some(key) => {
+                        let client = self.client.clone();
+                        let options = self.options.clone();
+                        let call =
+                            async move { client.post_json("/embeddings", body, &options).await };
+                        calls.run(key, call).await
+                    }
+                    None => {
+                        self.client
+                            .post_json("/embeddings", body, &self.options)
+                            .await
+                    }
+                }
+            }
+            None => {
+                self.client
+                    .post("/embeddings", request, &self.options)
+                    .await
+            }
+        }?;
+
+        if let Some(dimensions) = dimensions {
+            let wrong = response
+                .data
+                .iter()
+                .find(|embedding| embedding.embedding.len() != dimensions as usize);
+            if let Some(embedding) = wrong {
+                return Err(OpenAIError::InvalidArgument(format!(
+                    "embedding {} has {} dimensions, requested {dimensions}",
+                    embedding.index,
+                    embedding.embedding.len()
+                )));
+            }
+        }
+        Ok(response)
+    }
+
+    /// Creates embeddings for any number of inputs by splitting them into requests within the
+    /// input count and token limits of [EmbeddingBatchOptions], sending up to `max_concurrency`
+    /// requests at once.
+    ///
+    /// Embeddings are returned in input order with `index` referring to the position in the
+    /// original input. When a request fails, its error is reported for each of its inputs.
+    pub async fn create_batched(
+        &self,
+        request: CreateEmbeddingRequest,
+        options: EmbeddingBatchOptions,
+    ) -> Result<CreateEmbeddingBatchResponse, OpenAIError> {
+        let mut request = request;
+        let inputs = split_inputs(std::mem::take(&mut request.input));
+
+        let tokens: Vec<usize> = inputs
+            .iter()
+            .map(|input| match input {
+                EmbeddingInput::String(text) => options.token_estimator.estimate(text),
+                EmbeddingInput::IntegerArray(tokens) => tokens.len(),
+                _ => unreachable!("inputs are split into single inputs"),
+            })
+            .collect();
+
+        let ranges = chunk_ranges(
+            &tokens,
+            options.max_inputs_per_request,
+            options.max_tokens_per_request,
+        );
+
+        let mut responses = futures::stream::iter(ranges.into_iter().map(|range| {
+            let request = CreateEmbeddingRequest {
+                input: join_inputs(&inputs[range.clone()]),
+                ..request.clone()
+            };
+            async move { (range, self.create(request).await) }
+        }))
+        .buffer_unordered(options.max_concurrency);
+
+        let mut batch = CreateEmbeddingBatchResponse {
+            model: request.model.clone(),
+            data: Vec::with_capacity(inputs.len()),
+            usage: EmbeddingUsage {
+                prompt_tokens: 0,
+                total_tokens: 0,
+            },
+        };
+        let mut data: Vec<Option<Result<_, Arc<OpenAIError>>>> =
+            (0..inputs.len()).map(|_| None).collect();
+
+        while let Some((range, result)) = responses.next().await {
+            match result {
+                Ok(response) => {
+                    batch.model = response.model;
+                    batch.usage.prompt_tokens += response.usage.prompt_tokens;
+                    batch.usage.total_tokens += response.usage.total_tokens;
+
+                    for mut embedding in response.data {
+                        let index = range.start + embedding.index as usize;
+                        if range.contains(&index) {
+                            embedding.index = index as u32;
+                            data[index] = Some(Ok(embedding));
+                        }
+                    }
+                }
+                Err(e) => {
+                    let e = Arc::new(e);
+                    for index in range {
+                        data[index] = Some(Err(e.clone()));
+                    }
+                }
+            }
+        }
+
+        batch.data = data
+            .into_iter()
+            .enumerate()
+            .map(|(index, embedding)| {
+                embedding.unwrap_or_else(|| {
+                    Err(Arc::new(OpenAIError::InvalidArgument(format!(
+                        "no embedding in response for input {index}"
+                    ))))
+                })
+            })
+            .collect();
+
+        Ok(batch)
+    }
+}
+
+/// Split inputs into consecutive ranges of at most `max_inputs` inputs and `max_tokens` tokens.
+/// An input over the token limit on its own gets a range of its own.
+fn chunk_ranges(tokens: &[usize], max_inputs: usize, max_tokens: usize) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut total = 0;
+
+    for (index, count) in tokens.iter().enumerate() {
+        if index > start && (index - start >= max_inputs || total + count > max_tokens) {
+            ranges.push(start..index);
+            start = index;
+            total = 0;
+        }
+        total += count;
+    }
+
+    if start < tokens.len() {
+        ranges.push(start..tokens.len());
+    }
+
+    ranges
+}
+
+/// The single inputs of `input`: strings, or arrays of tokens.
+fn split_inputs(input: EmbeddingInput) -> Vec<EmbeddingInput> {
+    match input {
+        EmbeddingInput::StringArray(strings) => {
+            strings.into_iter().map(EmbeddingInput::String).collect()
+        }
+        EmbeddingInput::ArrayOfIntegerArray(arrays) => arrays
+            .into_iter()
+            .map(EmbeddingInput::IntegerArray)
+            .collect(),
+        input => vec![input],
+    }
+}
+
+fn join_inputs(inputs: &[EmbeddingInput]) -> EmbeddingInput {
+    match inputs {
+        [input] => input.clone(),
+        _ => match inputs.first() {
+            Some(EmbeddingInput::IntegerArray(_)) => EmbeddingInput::ArrayOfIntegerArray(
+                inputs
+                    .iter()
+                    .filter_map(|input| match input {
+                        EmbeddingInput::IntegerArray(tokens) => Some(tokens.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => EmbeddingInput::StringArray(
+                inputs
+                    .iter()
+                    .filter_map(|input| match input {
+                        EmbeddingInput::String(text) => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+        },
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{types::CreateEmbeddingRequestArgs, Client};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::chunk_ranges;
+    use crate::{
+        error::OpenAIError,
+        types::{CreateEmbeddingRequestArgs, Embedding, EncodingFormat},
+        Client, LruEmbeddingCache, RequestOptions,
+    };
+
+    #[test]
+    fn test_chunk_ranges() {
+        assert_eq!(chunk_ranges(&[], 2, 10), vec![]);
+        assert_eq!(
+            chunk_ranges(&[1, 1, 1, 1, 1], 2, 10),
+            vec![0..2, 2..4, 4..5]
+        );
+        assert_eq!(
+            chunk_ranges(&[4, 4, 4, 20, 1], 10, 10),
+            vec![0..2, 2..3, 3..4, 4..5]
+        );
+    }
+
+    /// Serves embedding responses on a local port, counting the requests received.
+    async fn serve_embeddings(requests: Arc<AtomicUsize>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let requests = requests.clone();
+                tokio::spawn(async move {
+                    let mut received = Vec::new();
+                    let mut buf = [0; 4096];
+                    loop {
+                        let n = socket.read(&mut buf).await.unwrap();
+                        if n == 0 {
+                            break;
+                        }
+                        received.extend_from_slice(&buf[..n]);
+                        let text = String::from_utf8_lossy(&received).to_lowercase();
+                        let Some(header_end) = text.find("\r\n\r\n") else {
+                            continue;
+                        };
+                        let content_length: usize = text
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length: "))
+                            .map_or(0, |len| len.trim().parse().unwrap());
+                        if received.len() < header_end + 4 + content_length {
+                            continue;
+                        }
+                        received.clear();
+                        requests.fetch_add(1, Ordering::SeqCst);
+
+                        let body = r#"{"object":"list","model":"text-embedding-ada-002","data":[{"object":"embedding","embedding":[0.1,0.2],"index":0}],"usage":{"prompt_tokens":1,"total_tokens":1}}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                            body.len()
+                        );
+                        socket.write_all(response.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_identical_requests() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = Client::new()
+            .with_api_base(serve_embeddings(requests.clone()).await)
+            .with_embedding_coalescing(true);
+
+        let request = CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-ada-002")
+            .input("The food was delicious and the waiter...")
+            .build()
+            .unwrap();
+
+        let embeddings = client.embeddings();
+        let (a, b, c) = futures::join!(
+            embeddings.create(request.clone()),
+            embeddings.create(request.clone()),
+            embeddings.create(request.clone()),
+        );
+        assert_eq!(a.unwrap().data[0].embedding, vec![0.1, 0.2]);
+        assert_eq!(b.unwrap().data[0].embedding, vec![0.1, 0.2]);
+        assert_eq!(c.unwrap().data[0].embedding, vec![0.1, 0.2]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Calls that completed are not reused
+        embeddings.create(request).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_per_tenant() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = Client::new()
+            .with_api_base(serve_embeddings(requests.clone()).await)
+            .with_embedding_coalescing(true);
+        let request = CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-ada-002")
+            .input("The food was delicious and the waiter...")
+            .build()
+            .unwrap();
+
+        let tenant = |api_key: &str| {
+            client
+                .embeddings()
+                .with_options(RequestOptions::new().with_api_key(api_key))
+        };
+        let (a, b) = (tenant("sk-a"), tenant("sk-b"));
+        let (a, b) = futures::join!(a.create(request.clone()), b.create(request.clone()));
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Calls with a deadline are never shared
+        let options = RequestOptions::new().with_timeout(std::time::Duration::from_secs(10));
+        let embeddings = client.embeddings().with_options(options);
+        let (a, b) = futures::join!(
+            embeddings.create(request.clone()),
+            embeddings.create(request.clone())
+        );
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0; 4096];
+                    let _ = socket.read(&mut buf).await.unwrap();
+                    let body = r#"{"error":{"message":"Invalid model","type":"invalid_request_error","param":null,"code":"model_not_found"}}"#;
+                    let response = format!(
+                        "HTTP/1.1 404 Not Found\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        let client = Client::new()
+            .with_api_base(format!("http://{addr}"))
+            .with_embedding_coalescing(true);
+        let request = CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-ada-003")
+            .input("The food was delicious and the waiter...")
+            .build()
+            .unwrap();
+        let embeddings = client.embeddings();
+
+        // A call which was not shared fails as without coalescing
+        let error = embeddings.create(request.clone()).await.unwrap_err();
+        assert!(matches!(error, OpenAIError::ApiError(_)));
+
+        // Only the caller which joined the call of another gets a wrapped error
+        let (a, b) = futures::join!(
+            embeddings.create(request.clone()),
+            embeddings.create(request.clone())
+        );
+        assert!(matches!(a, Err(OpenAIError::ApiError(_))));
+        let Err(OpenAIError::Coalesced(b)) = b else {
+            panic!("expected a coalesced error, got {b:?}");
+        };
+        assert!(matches!(*b, OpenAIError::ApiError(ref e) if e.message == "Invalid model"));
+    }
+
+    #[tokio::test]
+    async fn test_cached_inputs_are_not_sent() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = Client::new()
+            .with_api_base(serve_embeddings(requests.clone()).await)
+            .with_embedding_cache(LruEmbeddingCache::new(10));
+        let request = |input: &[&str]| {
+            CreateEmbeddingRequestArgs::default()
+                .model("text-embedding-ada-002")
+                .input(input.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+                .build()
+                .unwrap()
+        };
+
+        client.embeddings().create(request(&["a"])).await.unwrap();
+        let response = client.embeddings().create(request(&["a"])).await.unwrap();
+        assert_eq!(response.data[0].embedding, vec![0.1, 0.2]);
+        assert_eq!(response.usage.total_tokens, 0);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Only "b" is sent, and its embedding is returned at its position in the input
+        let response = client
+            .embeddings()
+            .create(request(&["a", "b"]))
+            .await
+            .unwrap();
+        let indices: Vec<u32> = response.data.iter().map(|e| e.index).collect();
+        assert_eq!(indices, [0, 1]);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_base64_embedding() {
+        let embedding: Embedding =
+            serde_json::from_str(r#"{"object":"embedding","embedding":"AACAPwAAAMA=","index":0}"#)
+                .unwrap();
+        assert_eq!(embedding.embedding, vec![1.0, -2.0]);
+
+        let truncated = serde_json::from_str::<Embedding>(
+            r#"{"object":"embedding","embedding":"AACAPwAA","index":0}"#,
+        );
+        assert!(truncated.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dimensions_are_checked() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = Client::new().with_api_base(serve_embeddings(requests).await);
+        let request = |dimensions: u32| {
+            CreateEmbeddingRequestArgs::default()
+                .model("text-embedding-3-small")
+                .input("The food was delicious")
+                .encoding_format(EncodingFormat::Base64)
+                .dimensions(dimensions)
+                .build()
+                .unwrap()
+        };
+
+        assert!(client.embeddings().create(request(2)).await.is_ok());
+        assert!(matches!(
+            client.embeddings().create(request(3)).await,
+            Err(OpenAIError::InvalidArgument(_))
+        ));
+    }
 
     #[tokio::test]
     async fn test_embedding_string() {
@@ -0,0 +1,183 @@
+//! Caching of embeddings across calls, see [Client::with_embedding_cache](crate::Client::with_embedding_cache).
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Mutex,
+};
+
+use crate::types::EmbeddingInput;
+
+/// Model, dimensions and input of a cached embedding.
+///
+/// `input_hash` is a 64-bit FNV-1a hash of the JSON of the input, stable across processes so
+/// that it can be used to look embeddings up in a store. As different inputs can have the
+/// same hash, stores should keep `input` along with the embedding and compare it on a hit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EmbeddingCacheKey {
+    pub model: String,
+    pub dimensions: Option<u32>,
+    pub input_hash: u64,
+    /// JSON of the input
+    pub input: String,
+}
+
+impl EmbeddingCacheKey {
+    /// Key of the embedding of a single `input`, a string or an array of tokens.
+    pub fn new(model: &str, dimensions: Option<u32>, input: &EmbeddingInput) -> Self {
+        let input = serde_json::to_string(input).unwrap_or_default();
+        let input_hash = input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+        Self {
+            model: model.to_string(),
+            dimensions,
+            input_hash,
+            input,
+        }
+    }
+}
+
+/// Store of embeddings consulted by [Embeddings::create](crate::Embeddings::create) before
+/// making an API call, so that inputs embedded before are not sent again.
+///
+/// [LruEmbeddingCache] keeps embeddings in memory; implement this trait to keep them in a
+/// database or on disk instead.
+pub trait EmbeddingCache: fmt::Debug + Send + Sync {
+    /// The embedding stored for `key`, if any.
+    fn get(&self, key: &EmbeddingCacheKey) -> Option<Vec<f32>>;
+
+    /// Store the embedding of `key`, received from the API.
+    fn put(&self, key: EmbeddingCacheKey, embedding: Vec<f32>);
+}
+
+/// In-memory [EmbeddingCache] of up to `capacity` embeddings, evicting the least recently
+/// used one when full.
+///
+/// ```
+/// use async_openai::{Client, LruEmbeddingCache};
+///
+/// let client = Client::new().with_embedding_cache(LruEmbeddingCache::new(10_000));
+/// ```
+pub struct LruEmbeddingCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// Embedding and last use of each key
+    embeddings: HashMap<EmbeddingCacheKey, (Vec<f32>, u64)>,
+    /// Keys by last use, least recent first
+    uses: BTreeMap<u64, EmbeddingCacheKey>,
+    clock: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &EmbeddingCacheKey) {
+        self.clock += 1;
+        if let Some((_, used)) = self.embeddings.get_mut(key) {
+            self.uses.remove(used);
+            *used = self.clock;
+            self.uses.insert(self.clock, key.clone());
+        }
+    }
+}
+
+impl LruEmbeddingCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+        }
+    }
+
+    /// Number of embeddings in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().embeddings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for LruEmbeddingCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LruEmbeddingCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl EmbeddingCache for LruEmbeddingCache {
+    fn get(&self, key: &EmbeddingCacheKey) -> Option<Vec<f32>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.touch(key);
+        entries
+            .embeddings
+            .get(key)
+            .map(|(embedding, _)| embedding.clone())
+    }
+
+    fn put(&self, key: EmbeddingCacheKey, embedding: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((stored, _)) = entries.embeddings.get_mut(&key) {
+            *stored = embedding;
+            entries.touch(&key);
+            return;
+        }
+        if entries.embeddings.len() == self.capacity {
+            let least_recent = entries.uses.keys().next().copied();
+            if let Some(evicted) = least_recent.and_then(|used| entries.uses.remove(&used)) {
+                entries.embeddings.remove(&evicted);
+            }
+        }
+        entries.clock += 1;
+        let used = entries.clock;
+        entries.uses.insert(used, key.clone());
+        entries.embeddings.insert(key, (embedding, used));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EmbeddingCache, EmbeddingCacheKey, LruEmbeddingCache};
+    use crate::types::EmbeddingInput;
+
+    fn key(text: &str) -> EmbeddingCacheKey {
+        EmbeddingCacheKey::new("model", None, &EmbeddingInput::String(text.into()))
+    }
+
+    #[test]
+    fn test_lru_embedding_cache() {
+        assert_eq!(key("a"), key("a"));
+        assert_ne!(key("a"), key("b"));
+        assert_ne!(
+            key("a"),
+            EmbeddingCacheKey::new("other", None, &EmbeddingInput::String("a".into()))
+        );
+
+        let cache = LruEmbeddingCache::new(2);
+        cache.put(key("a"), vec![1.0]);
+        cache.put(key("b"), vec![2.0]);
+        assert_eq!(cache.get(&key("a")), Some(vec![1.0]));
+
+        // "b" is the least recently used
+        cache.put(key("c"), vec![3.0]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("b")), None);
+        assert_eq!(cache.get(&key("a")), Some(vec![1.0]));
+        assert_eq!(cache.get(&key("c")), Some(vec![3.0]));
+
+        // Inputs whose hashes collide are not mistaken for each other
+        let collision = EmbeddingCacheKey {
+            input_hash: key("a").input_hash,
+            ..key("d")
+        };
+        assert_eq!(cache.get(&collision), None);
+    }
+}
@@ -0,0 +1,122 @@
+//! Names of the environment variables the client settings are read from.
+use crate::Client;
+
+/// Names of the environment variables read by [Client::from_env_vars]. The defaults are those of
+/// the official OpenAI SDKs, which [Client::new] reads, so container configurations carry over:
+/// `OPENAI_API_KEY`, `OPENAI_BASE_URL` or else `OPENAI_API_BASE`, `OPENAI_ORG_ID` and
+/// `OPENAI_PROJECT_ID`.
+///
+/// ```
+/// use async_openai::{Client, EnvVars};
+///
+/// let client = Client::from_env_vars(
+///     &EnvVars::default()
+///         .with_api_key("AZURE_OPENAI_KEY")
+///         .with_api_base("AZURE_OPENAI_ENDPOINT"),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVars {
+    api_key: Vec<String>,
+    api_base: Vec<String>,
+    org_id: Vec<String>,
+    project_id: Vec<String>,
+}
+
+impl Default for EnvVars {
+    fn default() -> Self {
+        Self {
+            api_key: vec!["OPENAI_API_KEY".into()],
+            api_base: vec!["OPENAI_BASE_URL".into(), "OPENAI_API_BASE".into()],
+            org_id: vec!["OPENAI_ORG_ID".into()],
+            project_id: vec!["OPENAI_PROJECT_ID".into()],
+        }
+    }
+}
+
+impl EnvVars {
+    /// Read the API key from `name`
+    pub fn with_api_key<S: Into<String>>(mut self, name: S) -> Self {
+        self.api_key = vec![name.into()];
+        self
+    }
+
+    /// Read the API base from `name`
+    pub fn with_api_base<S: Into<String>>(mut self, name: S) -> Self {
+        self.api_base = vec![name.into()];
+        self
+    }
+
+    /// Read the organization id from `name`
+    pub fn with_org_id<S: Into<String>>(mut self, name: S) -> Self {
+        self.org_id = vec![name.into()];
+        self
+    }
+
+    /// Read the project id from `name`
+    pub fn with_project_id<S: Into<String>>(mut self, name: S) -> Self {
+        self.project_id = vec![name.into()];
+        self
+    }
+}
+
+/// Value of the first of `names` which is set and not empty
+fn first_var(names: &[String]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+impl Client {
+    /// Create client with the settings in the environment variables named by `vars`.
+    /// Settings whose variables are not set keep their defaults.
+    pub fn from_env_vars(vars: &EnvVars) -> Self {
+        let mut client = Client::with_defaults();
+        if let Some(api_key) = first_var(&vars.api_key) {
+            client = client.with_api_key(api_key);
+        }
+        if let Some(api_base) = first_var(&vars.api_base) {
+            client = client.with_api_base(api_base);
+        }
+        if let Some(org_id) = first_var(&vars.org_id) {
+            client = client.with_org_id(org_id);
+        }
+        if let Some(project_id) = first_var(&vars.project_id) {
+            client = client.with_project_id(project_id);
+        }
+        client
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "_api")]
+mod tests {
+    use super::EnvVars;
+    use crate::{Client, API_BASE};
+
+    #[test]
+    fn test_env_vars() {
+        std::env::set_var("ASYNC_OPENAI_ENV_TEST_KEY", "sk-env");
+        std::env::set_var("ASYNC_OPENAI_ENV_TEST_BASE", "http://localhost:11434/v1");
+        std::env::set_var("ASYNC_OPENAI_ENV_TEST_ORG", "");
+
+        let client = Client::from_env_vars(
+            &EnvVars::default()
+                .with_api_key("ASYNC_OPENAI_ENV_TEST_KEY")
+                .with_api_base("ASYNC_OPENAI_ENV_TEST_BASE")
+                .with_org_id("ASYNC_OPENAI_ENV_TEST_ORG"),
+        );
+        assert_eq!(client.api_key(), "sk-env");
+        assert_eq!(client.api_base(), "http://localhost:11434/v1");
+        assert!(client
+            .headers()
+            .unwrap()
+            .get("openai-organization")
+            .is_none());
+
+        let client =
+            Client::from_env_vars(&EnvVars::default().with_api_base("ASYNC_OPENAI_ENV_TEST_UNSET"));
+        assert_eq!(client.api_base(), API_BASE);
+    }
+}
@@ -6,12 +6,25 @@ pub enum OpenAIError {
     /// Underlying error from reqwest library after an API call was made
     #[error("http error: {0}")]
     Reqwest(#[from] reqwest::Error),
+    /// Error of an API call made through a custom connector, see [Client::with_connector](crate::Client::with_connector)
+    #[error("http error: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
     /// OpenAI returns error object with details of API call failure
     #[error("{}: {}", .0.r#type, .0.message)]
     ApiError(ApiError),
     /// Error when a response cannot be deserialized into a Rust type
     #[error("failed to deserialize api response: {0}")]
     JSONDeserialize(serde_json::Error),
+    /// Error when a response deserializes but contradicts the request, such as embeddings
+    /// missing for some of the inputs
+    #[error("invalid api response: {0}")]
+    InvalidResponse(String),
+    /// Error when a fine-tune result file cannot be parsed as CSV
+    #[error("failed to deserialize result file: {0}")]
+    CSVDeserialize(String),
+    /// Error when a request cannot be serialized into a JSON body
+    #[error("failed to serialize request: {0}")]
+    JSONSerialize(serde_json::Error),
     /// Error on the client side when saving file to file system
     #[error("failed to save file: {0}")]
     FileSaveError(String),
@@ -25,10 +38,32 @@ pub enum OpenAIError {
     /// or when builder fails to build request before making API call
     #[error("invalid args: {0}")]
     InvalidArgument(String),
+    /// Invalid client settings in environment variables or a configuration file
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+    /// A list had more items than the cap given to `collect_all`
+    #[error("list has more than {0} items, raise the cap or use list_all to stream them")]
+    TooManyItems(usize),
+    /// The deadline of the API call passed before it completed
+    #[error("deadline exceeded")]
+    DeadlineExceeded,
+    /// The API call or stream was cancelled with [CancelExt](crate::CancelExt)
+    #[error("cancelled")]
+    Cancelled,
+    /// A fine-tune waited on with [FineTunes::poll_until_done](crate::FineTunes::poll_until_done)
+    /// failed or was cancelled, with the message of its last error event
+    #[error("fine-tune {} {}: {message}", .fine_tune.id, .fine_tune.status)]
+    FineTuneFailed {
+        fine_tune: Box<crate::types::FineTune>,
+        message: String,
+    },
+    /// Error of an API call started by a concurrent identical request, and shared with this one
+    #[error("{0}")]
+    Coalesced(std::sync::Arc<OpenAIError>),
 }
 
 /// OpenAI API returns error object on failure
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ApiError {
     pub message: String,
     pub r#type: String,
@@ -38,6 +73,7 @@ pub struct ApiError {
 
 /// Wrapper to deserialize the error object nested in "error" JSON key
 #[derive(Debug, Deserialize)]
+#[cfg(feature = "_api")]
 pub(crate) struct WrappedError {
     pub(crate) error: ApiError,
 }
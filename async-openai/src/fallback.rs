@@ -0,0 +1,223 @@
+//! Retrying failed API calls with other models, or other servers.
+use crate::{error::OpenAIError, Client};
+
+/// Failure which makes a [FallbackPolicy] try its next fallback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackTrigger {
+    /// The model does not exist, or the API key has no access to it
+    ModelNotFound,
+    /// The prompt and `max_tokens` do not fit in the context of the model
+    ContextLengthExceeded,
+    /// Still rate limited once retries are exhausted, or out of quota
+    RateLimited,
+}
+
+impl FallbackTrigger {
+    fn matches(self, error: &OpenAIError) -> bool {
+        let error = match error {
+            OpenAIError::ApiError(error) => error,
+            OpenAIError::Coalesced(error) => return self.matches(error),
+            _ => return false,
+        };
+        let code = error.code.as_ref().and_then(|code| code.as_str());
+        match self {
+            Self::ModelNotFound => code == Some("model_not_found"),
+            Self::ContextLengthExceeded => code == Some("context_length_exceeded"),
+            Self::RateLimited => {
+                code == Some("rate_limit_exceeded")
+                    || error.r#type == "insufficient_quota"
+                    || error.r#type == "requests"
+            }
+        }
+    }
+}
+
+/// Model to retry a failed call with, see [FallbackPolicy].
+#[derive(Debug, Clone)]
+pub struct Fallback {
+    pub(crate) model: String,
+    pub(crate) max_tokens: Option<u16>,
+    pub(crate) client: Option<Client>,
+}
+
+impl Fallback {
+    pub fn new<S: Into<String>>(model: S) -> Self {
+        Self {
+            model: model.into(),
+            max_tokens: None,
+            client: None,
+        }
+    }
+
+    /// Replace the `max_tokens` of the request, e.g. to fit the context of a smaller model.
+    pub fn with_max_tokens(mut self, max_tokens: u16) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Make the call with `client` instead, e.g. one for a local OpenAI compatible server.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+}
+
+/// Models to retry a call with, in order, when it fails with one of the triggers of the
+/// policy. By default all of [FallbackTrigger] cause a fallback. The `model` of the response
+/// tells which model served it.
+///
+/// ```
+/// use async_openai::{Client, Fallback, FallbackPolicy};
+///
+/// let local = Client::new().with_api_base("http://localhost:8080/v1");
+/// let policy = FallbackPolicy::new()
+///     .with_fallback(Fallback::new("text-curie-001").with_max_tokens(256))
+///     .with_fallback(Fallback::new("llama-2-7b").with_client(local));
+///
+/// let client = Client::new();
+/// let completions = client.completions().with_fallback(policy);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FallbackPolicy {
+    fallbacks: Vec<Fallback>,
+    triggers: Vec<FallbackTrigger>,
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self {
+            fallbacks: vec![],
+            triggers: vec![
+                FallbackTrigger::ModelNotFound,
+                FallbackTrigger::ContextLengthExceeded,
+                FallbackTrigger::RateLimited,
+            ],
+        }
+    }
+}
+
+impl FallbackPolicy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a fallback, tried after those added before it.
+    pub fn with_fallback(mut self, fallback: Fallback) -> Self {
+        self.fallbacks.push(fallback);
+        self
+    }
+
+    /// Fall back only on these failures.
+    pub fn with_triggers<I: IntoIterator<Item = FallbackTrigger>>(mut self, triggers: I) -> Self {
+        self.triggers = triggers.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn fallbacks(&self) -> &[Fallback] {
+        &self.fallbacks
+    }
+
+    /// Whether the next fallback should be tried after `error`.
+    pub(crate) fn should_fall_back(&self, error: &OpenAIError) -> bool {
+        self.triggers.iter().any(|trigger| trigger.matches(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FallbackPolicy, FallbackTrigger};
+    use crate::error::{ApiError, OpenAIError};
+
+    fn api_error(r#type: &str, code: Option<&str>) -> OpenAIError {
+        OpenAIError::ApiError(ApiError {
+            message: "".into(),
+            r#type: r#type.into(),
+            param: None,
+            code: code.map(Into::into),
+        })
+    }
+
+    #[test]
+    fn test_triggers() {
+        let policy = FallbackPolicy::new();
+        assert!(
+            policy.should_fall_back(&api_error("invalid_request_error", Some("model_not_found")))
+        );
+        assert!(policy.should_fall_back(&api_error("insufficient_quota", None)));
+        assert!(!policy.should_fall_back(&api_error("invalid_request_error", None)));
+        assert!(!policy.should_fall_back(&OpenAIError::DeadlineExceeded));
+
+        let policy = policy.with_triggers([FallbackTrigger::ContextLengthExceeded]);
+        assert!(!policy.should_fall_back(&api_error("insufficient_quota", None)));
+        assert!(policy.should_fall_back(&api_error(
+            "invalid_request_error",
+            Some("context_length_exceeded")
+        )));
+    }
+
+    #[cfg(feature = "completions")]
+    #[tokio::test]
+    async fn test_completion_falls_back() {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        };
+
+        use super::Fallback;
+        use crate::{types::CreateCompletionRequestArgs, Client};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let models = tokio::spawn(async move {
+            let mut models = vec![];
+            let responses = [
+                (
+                    "404 Not Found",
+                    r#"{"error":{"message":"no such model","type":"invalid_request_error","param":null,"code":"model_not_found"}}"#,
+                ),
+                (
+                    "200 OK",
+                    r#"{"id":"cmpl-1","object":"text_completion","created":0,"model":"text-curie-001","choices":[]}"#,
+                ),
+            ];
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let body_start = request.find("\r\n\r\n").unwrap() + 4;
+                let json: serde_json::Value = serde_json::from_str(&request[body_start..]).unwrap();
+                models.push((json["model"].clone(), json["max_tokens"].clone()));
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            models
+        });
+
+        let client = Client::new().with_api_base(format!("http://{addr}"));
+        let policy = FallbackPolicy::new()
+            .with_fallback(Fallback::new("text-curie-001").with_max_tokens(16));
+        let request = CreateCompletionRequestArgs::default()
+            .model("text-davinci-004")
+            .prompt("Say this is a test")
+            .build()
+            .unwrap();
+        let response = client
+            .completions()
+            .with_fallback(policy)
+            .create(request)
+            .await
+            .unwrap();
+        assert_eq!(response.model, "text-curie-001");
+
+        let models = models.await.unwrap();
+        assert_eq!(
+            models[0],
+            ("text-davinci-004".into(), serde_json::Value::Null)
+        );
+        assert_eq!(models[1], ("text-curie-001".into(), 16.into()));
+    }
+}
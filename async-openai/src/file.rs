@@ -1,57 +1,102 @@
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
-    types::{CreateFileRequest, DeleteFileResponse, ListFilesResponse, OpenAIFile},
-    util::create_file_part,
+    types::{
+        CreateFileRequest, DeleteFileResponse, ListFilesResponse, ListParams, OpenAIFile,
+        OpenAIFileStream,
+    },
+    util::{collect_all, create_file_part},
     Client,
 };
 
 /// Files are used to upload documents that can be used with features like [Fine-tuning](https://platform.openai.com/docs/api-reference/fine-tunes).
 pub struct Files<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> Files<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Upload a file that contains document(s) to be used across various endpoints/features. Currently, the size of all the files uploaded by one organization can be up to 1 GB. Please contact us if you need to increase the storage limit.
     pub async fn create(&self, request: CreateFileRequest) -> Result<OpenAIFile, OpenAIError> {
-        let file_part = create_file_part(&request.file.path).await?;
+        let file_part = create_file_part(&request.file.source).await?;
         let form = reqwest::multipart::Form::new()
             .part("file", file_part)
             .text("purpose", request.purpose);
-        self.client.post_form("/files", form).await
+        self.client.post_form("/files", form, &self.options).await
     }
 
     /// Returns a list of files that belong to the user's organization.
-    pub async fn list(&self) -> Result<ListFilesResponse, OpenAIError> {
-        self.client.get("/files").await
+    pub async fn list(&self, params: &ListParams) -> Result<ListFilesResponse, OpenAIError> {
+        self.client
+            .get_with_query("/files", params, &self.options)
+            .await
+    }
+
+    /// Like [Files::list], but yields each file as soon as it has been received, so that
+    /// memory use stays flat however many files there are.
+    pub async fn list_stream(&self, params: &ListParams) -> Result<OpenAIFileStream, OpenAIError> {
+        self.client
+            .get_items("/files", params, "data", &self.options)
+            .await
+    }
+
+    /// Every file from the page of `params` on, fetching the next page of the list while the
+    /// API reports there are more.
+    pub fn list_all(&self, params: &ListParams) -> OpenAIFileStream {
+        self.client.get_paginated("/files", params, &self.options)
+    }
+
+    /// Every file of [Files::list_all] in a `Vec`, or [OpenAIError::TooManyItems] once there
+    /// are more than `max_items`.
+    pub async fn collect_all(
+        &self,
+        params: &ListParams,
+        max_items: usize,
+    ) -> Result<Vec<OpenAIFile>, OpenAIError> {
+        collect_all(self.list_all(params), max_items).await
     }
 
     /// Returns information about a specific file.
     pub async fn retrieve(&self, file_id: &str) -> Result<OpenAIFile, OpenAIError> {
-        self.client.get(format!("/files/{file_id}").as_str()).await
+        self.client
+            .get(format!("/files/{file_id}").as_str(), &self.options)
+            .await
     }
 
     /// Delete a file.
     pub async fn delete(&self, file_id: &str) -> Result<DeleteFileResponse, OpenAIError> {
         self.client
-            .delete(format!("/files/{file_id}").as_str())
+            .delete(format!("/files/{file_id}").as_str(), &self.options)
             .await
     }
 
-    /// Returns the contents of the specified file
+    /// Returns the contents of the specified file, as text which need not be JSON
     pub async fn retrieve_content(&self, file_id: &str) -> Result<String, OpenAIError> {
         self.client
-            .get(format!("/files/{file_id}/content").as_str())
+            .get_text(format!("/files/{file_id}/content").as_str(), &self.options)
             .await
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{types::CreateFileRequestArgs, Client};
+    use crate::{
+        types::{CreateFileRequestArgs, ListParams},
+        Client,
+    };
 
     #[tokio::test]
     async fn test_file_mod() {
@@ -79,7 +124,7 @@ mod tests {
 
         //assert_eq!(openai_file.status, Some("processed".to_owned())); // uploaded or processed
 
-        let list_files = client.files().list().await.unwrap();
+        let list_files = client.files().list(&ListParams::default()).await.unwrap();
 
         assert_eq!(list_files.data.into_iter().last().unwrap(), openai_file);
 
@@ -104,6 +149,6 @@ mod tests {
         let delete_response = client.files().delete(&openai_file.id).await.unwrap();
 
         assert_eq!(openai_file.id, delete_response.id);
-        assert_eq!(delete_response.deleted, true);
+        assert!(delete_response.deleted);
     }
 }
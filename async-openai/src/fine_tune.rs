@@ -1,8 +1,13 @@
+use std::time::Duration;
+
+#[cfg(feature = "files")]
+use crate::types::FineTuneMetrics;
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
     types::{
-        CreateFineTuneRequest, FineTune, FineTuneEventsResponseStream, ListFineTuneEventsResponse,
-        ListFineTuneResponse,
+        CreateFineTuneRequest, FineTune, FineTuneEvent, FineTuneEventsResponseStream,
+        ListFineTuneEventsResponse, ListFineTuneResponse,
     },
     Client,
 };
@@ -12,11 +17,21 @@ use crate::{
 /// Related guide: [Fine-tune models](https://platform.openai.com/docs/guides/fine-tuning)
 pub struct FineTunes<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> FineTunes<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Creates a job that fine-tunes a specified model from a given dataset.
@@ -24,13 +39,65 @@ impl<'c> FineTunes<'c> {
     /// Response includes details of the enqueued job including job status and the name of the fine-tuned models once complete.
     ///
     /// [Learn more about Fine-tuning](https://platform.openai.com/docs/guides/fine-tuning)
+    ///
+    /// The request is checked with [CreateFineTuneRequest::validate] first.
     pub async fn create(&self, request: CreateFineTuneRequest) -> Result<FineTune, OpenAIError> {
-        self.client.post("/fine-tunes", request).await
+        request.validate()?;
+        self.client
+            .post("/fine-tunes", request, &self.options)
+            .await
+    }
+
+    /// Creates a fine-tune and waits for it to finish, see [FineTunes::poll_until_done].
+    pub async fn create_and_poll(
+        &self,
+        request: CreateFineTuneRequest,
+        poll_interval: Duration,
+        on_event: impl FnMut(&FineTuneEvent),
+    ) -> Result<FineTune, OpenAIError> {
+        let fine_tune = self.create(request).await?;
+        self.poll_until_done(&fine_tune.id, poll_interval, on_event)
+            .await
+    }
+
+    /// Retrieves the fine-tune every `poll_interval` until it succeeds, calling `on_event` with
+    /// each of its events once. Fails with [OpenAIError::FineTuneFailed] when it fails or is
+    /// cancelled.
+    pub async fn poll_until_done(
+        &self,
+        fine_tune_id: &str,
+        poll_interval: Duration,
+        mut on_event: impl FnMut(&FineTuneEvent),
+    ) -> Result<FineTune, OpenAIError> {
+        let mut seen = 0;
+        loop {
+            let fine_tune = self.retrieve(fine_tune_id).await?;
+            let events = fine_tune.events.as_deref().unwrap_or_default();
+            events.iter().skip(seen).for_each(&mut on_event);
+            seen = seen.max(events.len());
+
+            match fine_tune.status.as_str() {
+                "succeeded" => return Ok(fine_tune),
+                "failed" | "cancelled" => {
+                    let message = events
+                        .iter()
+                        .rev()
+                        .find(|event| event.level == "error")
+                        .map(|event| event.message.clone())
+                        .unwrap_or_default();
+                    return Err(OpenAIError::FineTuneFailed {
+                        fine_tune: Box::new(fine_tune),
+                        message,
+                    });
+                }
+                _ => crate::runtime::sleep(poll_interval).await,
+            }
+        }
     }
 
     /// List your organization's fine-tuning jobs
     pub async fn list(&self) -> Result<ListFineTuneResponse, OpenAIError> {
-        self.client.get("/fine-tunes").await
+        self.client.get("/fine-tunes", &self.options).await
     }
 
     /// Gets info about the fine-tune job.
@@ -38,24 +105,48 @@ impl<'c> FineTunes<'c> {
     /// [Learn more about Fine-tuning](https://platform.openai.com/docs/guides/fine-tuning)
     pub async fn retrieve(&self, fine_tune_id: &str) -> Result<FineTune, OpenAIError> {
         self.client
-            .get(format!("/fine-tunes/{fine_tune_id}").as_str())
+            .get(
+                format!("/fine-tunes/{fine_tune_id}").as_str(),
+                &self.options,
+            )
             .await
     }
 
     /// Immediately cancel a fine-tune job.
     pub async fn cancel(&self, fine_tune_id: &str) -> Result<FineTune, OpenAIError> {
         self.client
-            .post(format!("/fine-tunes/{fine_tune_id}/cancel").as_str(), ())
+            .post(
+                format!("/fine-tunes/{fine_tune_id}/cancel").as_str(),
+                (),
+                &self.options,
+            )
             .await
     }
 
+    /// Metrics of each training step of a fine-tune, from the result files it produced once
+    /// succeeded, for plotting training curves. Empty while there are no result files.
+    #[cfg(feature = "files")]
+    pub async fn results(&self, fine_tune_id: &str) -> Result<Vec<FineTuneMetrics>, OpenAIError> {
+        let fine_tune = self.retrieve(fine_tune_id).await?;
+        let files = self.client.files().with_options(self.options.clone());
+        let mut metrics = vec![];
+        for result_file in &fine_tune.result_files {
+            let csv = files.retrieve_content(&result_file.id).await?;
+            metrics.extend(FineTuneMetrics::parse_csv(&csv)?);
+        }
+        Ok(metrics)
+    }
+
     /// Get fine-grained status updates for a fine-tune job.
     pub async fn list_events(
         &self,
         fine_tune_id: &str,
     ) -> Result<ListFineTuneEventsResponse, OpenAIError> {
         self.client
-            .get(format!("/fine-tunes/{fine_tune_id}/events").as_str())
+            .get(
+                format!("/fine-tunes/{fine_tune_id}/events").as_str(),
+                &self.options,
+            )
             .await
     }
 
@@ -72,7 +163,139 @@ impl<'c> FineTunes<'c> {
             .get_stream(
                 format!("/fine-tunes/{fine_tune_id}/events").as_str(),
                 &[("stream", true)],
+                &self.options,
             )
             .await)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::{error::OpenAIError, Client};
+
+    fn fine_tune_json(status: &str, events: &[(&str, &str)]) -> String {
+        let events: Vec<_> = events
+            .iter()
+            .map(|(level, message)| {
+                serde_json::json!({
+                    "object": "fine-tune-event",
+                    "created_at": 1,
+                    "level": level,
+                    "message": message,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "id": "ft-1",
+            "object": "fine-tune",
+            "model": "curie",
+            "created_at": 1,
+            "updated_at": 1,
+            "events": events,
+            "fine_tuned_model": null,
+            "hyperparams": {"n_epochs": 4},
+            "organization_id": "org-1",
+            "status": status,
+            "training_files": [],
+            "validation_files": [],
+            "result_files": [],
+        })
+        .to_string()
+    }
+
+    async fn serve(bodies: Vec<String>) -> Client {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for body in bodies {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        Client::new().with_api_base(format!("http://{addr}"))
+    }
+
+    #[tokio::test]
+    async fn test_poll_until_done() {
+        let client = serve(vec![
+            fine_tune_json("pending", &[("info", "Job enqueued")]),
+            fine_tune_json(
+                "running",
+                &[("info", "Job enqueued"), ("info", "Job started")],
+            ),
+            fine_tune_json(
+                "succeeded",
+                &[
+                    ("info", "Job enqueued"),
+                    ("info", "Job started"),
+                    ("info", "Job succeeded"),
+                ],
+            ),
+        ])
+        .await;
+        let mut messages = vec![];
+        let fine_tune = client
+            .fine_tunes()
+            .poll_until_done("ft-1", Duration::from_millis(1), |event| {
+                messages.push(event.message.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(fine_tune.status, "succeeded");
+        assert_eq!(messages, ["Job enqueued", "Job started", "Job succeeded"]);
+
+        let client = serve(vec![fine_tune_json(
+            "failed",
+            &[("error", "Invalid training file")],
+        )])
+        .await;
+        let error = client
+            .fine_tunes()
+            .poll_until_done("ft-1", Duration::from_millis(1), |_| {})
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, OpenAIError::FineTuneFailed { ref message, .. } if message == "Invalid training file")
+        );
+    }
+
+    #[cfg(feature = "files")]
+    #[tokio::test]
+    async fn test_results_reads_csv_result_file() {
+        let mut fine_tune: serde_json::Value =
+            serde_json::from_str(&fine_tune_json("succeeded", &[])).unwrap();
+        fine_tune["result_files"] = serde_json::json!([{
+            "id": "file-1",
+            "object": "file",
+            "bytes": 200,
+            "created_at": 1,
+            "filename": "compiled_results.csv",
+            "purpose": "fine-tune-results",
+        }]);
+        let csv = concat!(
+            "step,elapsed_tokens,elapsed_examples,training_loss,training_sequence_accuracy,training_token_accuracy\n",
+            "1,25,1,1.5,0.0,0.5\n",
+            "2,50,2,0.75,1.0,1.0\n",
+        );
+        let client = serve(vec![fine_tune.to_string(), csv.to_string()]).await;
+
+        let metrics = client.fine_tunes().results("ft-1").await.unwrap();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[1].step, 2);
+        assert_eq!(metrics[1].train_loss, Some(0.75));
+        assert_eq!(metrics[1].train_token_accuracy, Some(1.0));
+    }
+}
@@ -0,0 +1,121 @@
+//! API base and headers of proxies and gateways in front of the OpenAI API.
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+/// Helicone proxy of the OpenAI API
+const HELICONE_API_BASE: &str = "https://oai.hconeai.com/v1";
+/// Cloudflare AI Gateway, followed by the account id and gateway name
+const CLOUDFLARE_API_BASE: &str = "https://gateway.ai.cloudflare.com/v1";
+/// OpenRouter API
+const OPENROUTER_API_BASE: &str = "https://openrouter.ai/api/v1";
+
+/// API base and headers of a proxy or gateway, to switch a [Client](crate::Client) to it with
+/// [Client::with_gateway](crate::Client::with_gateway). The API key of the client is still sent
+/// as the bearer token: the OpenAI key for Helicone and Cloudflare, the OpenRouter key for OpenRouter.
+///
+/// ```
+/// use async_openai::{Client, Gateway};
+///
+/// let client = Client::new()
+///     .with_api_key("sk-or-...")
+///     .with_gateway(Gateway::open_router().with_app_name("My App"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Gateway {
+    pub(crate) api_base: String,
+    pub(crate) headers: HeaderMap,
+}
+
+impl Gateway {
+    /// Any gateway at `api_base`, with headers added by [Gateway::with_header]
+    pub fn new<S: Into<String>>(api_base: S) -> Self {
+        Self {
+            api_base: api_base.into(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// [Helicone](https://helicone.ai) proxy, logging requests with `helicone_api_key`
+    pub fn helicone(helicone_api_key: &str) -> Self {
+        Self::new(HELICONE_API_BASE)
+            .with_sensitive_header("Helicone-Auth", &format!("Bearer {helicone_api_key}"))
+    }
+
+    /// [Cloudflare AI Gateway](https://developers.cloudflare.com/ai-gateway/) `gateway` of `account_id`
+    pub fn cloudflare(account_id: &str, gateway: &str) -> Self {
+        Self::new(format!(
+            "{CLOUDFLARE_API_BASE}/{account_id}/{gateway}/openai"
+        ))
+    }
+
+    /// [OpenRouter](https://openrouter.ai), see [Gateway::with_app_url] and [Gateway::with_app_name]
+    /// to have the app listed in its rankings
+    pub fn open_router() -> Self {
+        Self::new(OPENROUTER_API_BASE)
+    }
+
+    /// URL of the app, sent in the `HTTP-Referer` header used by OpenRouter
+    pub fn with_app_url(self, url: &str) -> Self {
+        self.with_header("HTTP-Referer", url)
+    }
+
+    /// Name of the app, sent in the `X-Title` header used by OpenRouter
+    pub fn with_app_name(self, name: &str) -> Self {
+        self.with_header("X-Title", name)
+    }
+
+    /// Add a header to every request, such as `Helicone-Property-*` or `cf-aig-*` settings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` or `value` is not valid in a HTTP header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(header_name(name), header_value(value));
+        self
+    }
+
+    /// Like [Gateway::with_header], for values such as keys which must not be logged
+    pub fn with_sensitive_header(mut self, name: &str, value: &str) -> Self {
+        let mut value = header_value(value);
+        value.set_sensitive(true);
+        self.headers.insert(header_name(name), value);
+        self
+    }
+}
+
+fn header_name(name: &str) -> HeaderName {
+    HeaderName::from_bytes(name.as_bytes()).expect("invalid header name")
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).expect("invalid header value")
+}
+
+#[cfg(test)]
+#[cfg(feature = "_api")]
+mod tests {
+    use super::Gateway;
+    use crate::Client;
+
+    #[test]
+    fn test_gateways() {
+        let client = Client::new().with_gateway(Gateway::helicone("sk-helicone"));
+        assert_eq!(client.api_base(), "https://oai.hconeai.com/v1");
+        assert_eq!(
+            client.headers().unwrap()["helicone-auth"],
+            "Bearer sk-helicone"
+        );
+
+        let client = Client::new().with_gateway(Gateway::cloudflare("account", "my-gateway"));
+        assert_eq!(
+            client.api_base(),
+            "https://gateway.ai.cloudflare.com/v1/account/my-gateway/openai"
+        );
+
+        let gateway = Gateway::open_router()
+            .with_app_url("https://example.com")
+            .with_app_name("Example");
+        let headers = Client::new().with_gateway(gateway).headers().unwrap();
+        assert_eq!(headers["http-referer"], "https://example.com");
+        assert_eq!(headers["x-title"], "Example");
+    }
+}
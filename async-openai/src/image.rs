@@ -1,7 +1,11 @@
+use bytes::Bytes;
+
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
     types::{
-        CreateImageEditRequest, CreateImageRequest, CreateImageVariationRequest, ImageResponse,
+        CreateImageEditRequest, CreateImageRequest, CreateImageVariationRequest,
+        ImageGenerationStream, ImageModel, ImageResponse, InputSource,
     },
     util::create_file_part,
     Client,
@@ -12,51 +16,111 @@ use crate::{
 /// Related guide: [Image generation](https://platform.openai.com/docs/guides/images/introduction)
 pub struct Images<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> Images<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Creates an image given a prompt.
+    ///
+    /// The request is checked with [CreateImageRequest::validate] first.
     pub async fn create(&self, request: CreateImageRequest) -> Result<ImageResponse, OpenAIError> {
-        self.client.post("/images/generations", request).await
+        if request.stream == Some(true) {
+            return Err(OpenAIError::InvalidArgument(
+                "When stream is true, use Images::create_stream".into(),
+            ));
+        }
+        request.validate()?;
+        self.client
+            .post("/images/generations", request, &self.options)
+            .await
+    }
+
+    /// Creates an image given a prompt, streaming `partial_images` previews of it as it is
+    /// generated, then the final image. Only supported by `gpt-image-1`.
+    ///
+    /// [ImageGenerationStream] ends after the [ImageGenerationEvent::Completed](crate::types::ImageGenerationEvent::Completed) event.
+    pub async fn create_stream(
+        &self,
+        mut request: CreateImageRequest,
+    ) -> Result<ImageGenerationStream, OpenAIError> {
+        if request.stream == Some(false) {
+            return Err(OpenAIError::InvalidArgument(
+                "When stream is false, use Images::create".into(),
+            ));
+        }
+        request.stream = Some(true);
+        request.validate()?;
+
+        Ok(self
+            .client
+            .post_stream(
+                "/images/generations",
+                request,
+                &self.options,
+                Some("image_generation.completed"),
+            )
+            .await)
     }
 
     /// Creates an edited or extended image given an original image and a prompt.
+    ///
+    /// The mask is checked with [CreateImageEditRequest::check_mask] first.
     pub async fn create_edit(
         &self,
         request: CreateImageEditRequest,
     ) -> Result<ImageResponse, OpenAIError> {
-        let image_part = create_file_part(&request.image.path).await?;
-        let mask_part = create_file_part(&request.mask.path).await?;
+        request.validate()?;
+        request.check_mask().await?;
 
-        let mut form = reqwest::multipart::Form::new()
-            .part("image", image_part)
-            .part("mask", mask_part)
-            .text("prompt", request.prompt);
+        let mut form = reqwest::multipart::Form::new();
+        // A single image keeps the `image` field that DALL·E 2 expects
+        let image_field = if request.image.len() == 1 {
+            "image"
+        } else {
+            "image[]"
+        };
+        for image in &request.image {
+            form = form.part(image_field, create_file_part(&image.source).await?);
+        }
+        let mask_part = create_file_part(&request.mask.source).await?;
+        form = form.part("mask", mask_part).text("prompt", request.prompt);
 
-        if request.n.is_some() {
-            form = form.text("n", request.n.unwrap().to_string())
+        if let Some(model) = request.model {
+            form = form.text("model", model.to_string())
         }
 
-        if request.size.is_some() {
-            form = form.text("size", request.size.unwrap().to_string())
+        if let Some(n) = request.n {
+            form = form.text("n", n.to_string())
         }
 
-        if request.response_format.is_some() {
-            form = form.text(
-                "response_format",
-                request.response_format.unwrap().to_string(),
-            )
+        if let Some(size) = request.size {
+            form = form.text("size", size.to_string())
         }
 
-        if request.user.is_some() {
-            form = form.text("user", request.user.unwrap())
+        if let Some(response_format) = request.response_format {
+            form = form.text("response_format", response_format.to_string())
         }
 
-        self.client.post_form("/images/edits", form).await
+        if let Some(user) = request.user {
+            form = form.text("user", user)
+        }
+
+        self.client
+            .post_form("/images/edits", form, &self.options)
+            .await
     }
 
     /// Creates a variation of a given image.
@@ -64,29 +128,397 @@ impl<'c> Images<'c> {
         &self,
         request: CreateImageVariationRequest,
     ) -> Result<ImageResponse, OpenAIError> {
-        let image_part = create_file_part(&request.image.path).await?;
+        let image_part = create_file_part(&request.image.source).await?;
 
         let mut form = reqwest::multipart::Form::new().part("image", image_part);
 
-        if request.n.is_some() {
-            form = form.text("n", request.n.unwrap().to_string())
+        if let Some(n) = request.n {
+            form = form.text("n", n.to_string())
         }
 
-        if request.size.is_some() {
-            form = form.text("size", request.size.unwrap().to_string())
+        if let Some(size) = request.size {
+            form = form.text("size", size.to_string())
         }
 
-        if request.response_format.is_some() {
-            form = form.text(
-                "response_format",
-                request.response_format.unwrap().to_string(),
-            )
+        if let Some(response_format) = request.response_format {
+            form = form.text("response_format", response_format.to_string())
+        }
+
+        if let Some(user) = request.user {
+            form = form.text("user", user)
         }
 
-        if request.user.is_some() {
-            form = form.text("user", request.user.unwrap())
+        self.client
+            .post_form("/images/variations", form, &self.options)
+            .await
+    }
+}
+
+impl CreateImageEditRequest {
+    /// Check the number of images against what the model supports, before making the API call.
+    pub fn validate(&self) -> Result<(), OpenAIError> {
+        if self.image.is_empty() {
+            return Err(OpenAIError::InvalidArgument(
+                "at least one image to edit is required".into(),
+            ));
+        }
+        let model = self.model.clone().unwrap_or_default();
+        let max_images = match model {
+            ImageModel::DallE2 | ImageModel::DallE3 => 1,
+            ImageModel::GptImage1 => 16,
+            // Left to the server
+            ImageModel::Other(_) => return Ok(()),
+        };
+        if self.image.len() > max_images {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{model} takes 1 to {max_images} images to edit, got {}",
+                self.image.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that the mask is a PNG with an alpha channel, and has the dimensions of the first
+    /// image when that is a PNG too, instead of the API failing with a generic "invalid mask".
+    pub async fn check_mask(&self) -> Result<(), OpenAIError> {
+        let invalid = |message: String| Err(OpenAIError::InvalidArgument(message));
+        let Some(mask) = png_header(&self.mask.source).await? else {
+            return Ok(());
+        };
+        let Some(mask) = PngInfo::parse(&mask) else {
+            return invalid("mask is not a PNG file".into());
+        };
+        if !mask.alpha {
+            return invalid(
+                "mask has no alpha channel, its fully transparent areas mark where to edit".into(),
+            );
+        }
+
+        let Some(image) = self.image.first() else {
+            return Ok(());
+        };
+        let image = png_header(&image.source).await?;
+        if let Some(image) = image.as_deref().and_then(PngInfo::parse) {
+            if (image.width, image.height) != (mask.width, mask.height) {
+                return invalid(format!(
+                    "mask is {}x{} but image is {}x{}, they must have the same dimensions",
+                    mask.width, mask.height, image.width, image.height
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Enough of `source` for [PngInfo::parse], or `None` where files cannot be read. Files are
+/// not read whole, they are uploaded separately.
+async fn png_header(source: &InputSource) -> Result<Option<Bytes>, OpenAIError> {
+    match source {
+        InputSource::Bytes { bytes, .. } => Ok(Some(bytes.clone())),
+        #[cfg(not(target_arch = "wasm32"))]
+        InputSource::Path { path } => read_png_header(path)
+            .await
+            .map(|header| Some(header.into()))
+            .map_err(|e| OpenAIError::FileReadError(format!("{}: {e}", path.display()))),
+        #[cfg(target_arch = "wasm32")]
+        InputSource::Path { .. } => Ok(None),
+    }
+}
+
+/// The signature and the IHDR and tRNS chunks of the PNG file at `path`, seeking past the
+/// other chunks and stopping at the image data. Other files are only read up to where they
+/// stop looking like a PNG.
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_png_header(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    use std::io::ErrorKind;
+
+    let mut file = crate::runtime::fs::File::open(path).await?;
+    let mut png = vec![0; PNG_SIGNATURE.len()];
+    let result: std::io::Result<()> = async {
+        file.read_exact(&mut png).await?;
+        if png != PNG_SIGNATURE {
+            return Ok(());
         }
+        loop {
+            let mut header = [0; 8];
+            file.read_exact(&mut header).await?;
+            let len = u32::from_be_bytes(header[..4].try_into().unwrap());
+            match &header[4..] {
+                // Both are at most 256 bytes, anything larger is not a valid PNG
+                b"IHDR" | b"tRNS" if len <= 256 => {
+                    png.extend_from_slice(&header);
+                    let start = png.len();
+                    // Data and CRC
+                    png.resize(start + len as usize + 4, 0);
+                    if let Err(e) = file.read_exact(&mut png[start..]).await {
+                        png.truncate(start);
+                        return Err(e);
+                    }
+                }
+                // Cut short, which PngInfo::parse reports
+                b"IHDR" | b"tRNS" => {
+                    png.extend_from_slice(&header);
+                    return Ok(());
+                }
+                b"IDAT" | b"IEND" => return Ok(()),
+                _ => file.skip(len + 4).await?,
+            }
+        }
+    }
+    .await;
+    match result {
+        // Too short to be a PNG, which PngInfo::parse reports
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(png),
+        result => result.map(|_| png),
+    }
+}
+
+/// Dimensions and transparency of a PNG image, read from its chunks before the image data.
+#[derive(Debug, PartialEq, Eq)]
+struct PngInfo {
+    width: u32,
+    height: u32,
+    alpha: bool,
+}
+
+impl PngInfo {
+    fn parse(png: &[u8]) -> Option<Self> {
+        let mut chunks = png.strip_prefix(PNG_SIGNATURE)?;
+        let mut info: Option<PngInfo> = None;
+        while chunks.len() >= 8 {
+            let len = u32::from_be_bytes(chunks[..4].try_into().ok()?) as usize;
+            let kind = &chunks[4..8];
+            let data = chunks.get(8..8 + len)?;
+            match kind {
+                b"IHDR" if data.len() >= 10 => {
+                    let color_type = data[9];
+                    info = Some(PngInfo {
+                        width: u32::from_be_bytes(data[..4].try_into().ok()?),
+                        height: u32::from_be_bytes(data[4..8].try_into().ok()?),
+                        // Grayscale with alpha, or RGBA
+                        alpha: color_type == 4 || color_type == 6,
+                    });
+                }
+                b"tRNS" => info.as_mut()?.alpha = true,
+                b"IDAT" | b"IEND" => break,
+                _ => {}
+            }
+            // Skip the CRC
+            chunks = chunks.get(8 + len + 4..)?;
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::PngInfo;
+    use crate::{
+        types::{
+            CreateImageEditRequestArgs, CreateImageRequestArgs, ImageGenerationEvent, ImageInput,
+            ImageModel,
+        },
+        Client,
+    };
+
+    /// PNG chunk of `kind`, with a zero CRC.
+    fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    /// PNG header chunks of a `width` by `height` image of `color_type`, with a `tRNS` chunk
+    /// if `transparent`.
+    fn png(width: u32, height: u32, color_type: u8, transparent: bool) -> Vec<u8> {
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, color_type, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(chunk(b"IHDR", &ihdr));
+        if transparent {
+            png.extend(chunk(b"tRNS", &[0, 0]));
+        }
+        png.extend(chunk(b"IDAT", &[]));
+        png.extend(chunk(b"IEND", &[]));
+        png
+    }
+
+    #[tokio::test]
+    async fn test_check_mask() {
+        let info = PngInfo::parse(&png(4, 2, 2, true)).unwrap();
+        assert_eq!((info.width, info.height, info.alpha), (4, 2, true));
+        assert!(!PngInfo::parse(&png(4, 2, 2, false)).unwrap().alpha);
+        assert!(PngInfo::parse(b"GIF89a").is_none());
+
+        let request = |image: Vec<u8>, mask: Vec<u8>| {
+            CreateImageEditRequestArgs::default()
+                .image(ImageInput::from_bytes("image.png", image))
+                .mask(ImageInput::from_bytes("mask.png", mask))
+                .prompt("A cat")
+                .build()
+                .unwrap()
+        };
+        let check = |image, mask| async move { request(image, mask).check_mask().await };
+
+        assert!(check(png(4, 4, 2, false), png(4, 4, 6, false))
+            .await
+            .is_ok());
+        assert!(check(png(4, 4, 2, false), png(4, 4, 2, true)).await.is_ok());
+        // Not a PNG, opaque, and of other dimensions
+        assert!(check(png(4, 4, 2, false), b"GIF89a".to_vec())
+            .await
+            .is_err());
+        assert!(check(png(4, 4, 2, false), png(4, 4, 2, false))
+            .await
+            .is_err());
+        assert!(check(png(4, 4, 2, false), png(2, 4, 6, false))
+            .await
+            .is_err());
+        // Dimensions are only compared with PNG images
+        assert!(check(b"\xff\xd8\xff".to_vec(), png(2, 4, 6, false))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_mask_of_files() {
+        let path = |name: &str| {
+            std::env::temp_dir().join(format!("async-openai-{}-{name}", std::process::id()))
+        };
+        let (image_path, mask_path) = (path("image.png"), path("mask.png"));
+
+        // The tRNS chunk of the mask follows a large text chunk, and the image data is cut
+        // short: only the chunks before it are read
+        let opaque = png(4, 4, 3, false);
+        let mut mask = opaque[..33].to_vec();
+        mask.extend(chunk(b"zTXt", &vec![b'a'; 1 << 20]));
+        mask.extend(chunk(b"tRNS", &[0]));
+        mask.extend_from_slice(&(64u32 << 20).to_be_bytes());
+        mask.extend_from_slice(b"IDAT");
+        tokio::fs::write(&image_path, &opaque).await.unwrap();
+        tokio::fs::write(&mask_path, &mask).await.unwrap();
+
+        let request = CreateImageEditRequestArgs::default()
+            .image(ImageInput::new(&image_path))
+            .mask(ImageInput::new(&mask_path))
+            .prompt("A cat")
+            .build()
+            .unwrap();
+        let transparent = request.check_mask().await;
+        tokio::fs::write(&mask_path, &opaque).await.unwrap();
+        let opaque = request.check_mask().await;
+        tokio::fs::write(&mask_path, b"PNG").await.unwrap();
+        let truncated = request.check_mask().await;
+        let _ = tokio::fs::remove_file(&image_path).await;
+        let _ = tokio::fs::remove_file(&mask_path).await;
+
+        assert!(transparent.is_ok());
+        assert!(opaque.is_err());
+        assert!(truncated.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_edit_with_several_images() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            while !request.ends_with(b"--\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"created":1,"data":[]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        let mut request = CreateImageEditRequestArgs::default();
+        request
+            .images([
+                ImageInput::from_bytes("a.png", png(4, 4, 2, false)),
+                ImageInput::from_bytes("b.jpg", b"\xff\xd8\xff".to_vec()),
+            ])
+            .mask(ImageInput::from_bytes("mask.png", png(4, 4, 6, false)))
+            .prompt("A cat");
+
+        let client = Client::new().with_api_base(format!("http://{addr}"));
+        let dall_e = request.build().unwrap();
+        assert!(client.images().create_edit(dall_e).await.is_err());
+
+        let gpt_image = request.model(ImageModel::GptImage1).build().unwrap();
+        client.images().create_edit(gpt_image).await.unwrap();
+        let sent = server.await.unwrap();
+        assert_eq!(sent.matches("name=\"image[]\"").count(), 2);
+        assert!(sent.contains("gpt-image-1"));
+    }
+
+    #[tokio::test]
+    async fn test_create_stream_ends_at_completed_image() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let events = concat!(
+                "event: image_generation.partial_image\n",
+                "data: {\"type\":\"image_generation.partial_image\",\"b64_json\":\"AA==\",\"partial_image_index\":0,\"created_at\":1}\n\n",
+                "event: image_generation.completed\n",
+                "data: {\"type\":\"image_generation.completed\",\"b64_json\":\"AAA=\",\"created_at\":2}\n\n",
+            );
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{events}",
+                events.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            // Keep the connection open, the stream must end on the completed event
+            let _ = socket.read(&mut buf).await;
+        });
+
+        let client = Client::new().with_api_base(format!("http://{addr}"));
+        let request = CreateImageRequestArgs::default()
+            .prompt("A cat")
+            .model(ImageModel::GptImage1)
+            .partial_images(1)
+            .build()
+            .unwrap();
+        let events: Vec<_> = client
+            .images()
+            .create_stream(request)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            Ok(ImageGenerationEvent::PartialImage(ref partial)) if partial.partial_image_index == 0
+        ));
+        assert!(matches!(events[1], Ok(ImageGenerationEvent::Completed(_))));
 
-        self.client.post_form("/images/variations", form).await
+        let dall_e = CreateImageRequestArgs::default()
+            .prompt("A cat")
+            .build()
+            .unwrap();
+        assert!(client.images().create_stream(dall_e).await.is_err());
     }
 }
@@ -14,6 +14,9 @@
 //!
 //! // Use organization other than default when making requests
 //! let client = Client::new().with_org_id("the-org");
+//!
+//! // Attribute usage to a project, for keys of several projects
+//! let client = Client::new().with_project_id("proj_...");
 //! ```
 //!
 //! ## Making requests
@@ -45,31 +48,146 @@
 //! # });
 //!```
 //!
+//! ## Feature flags
+//!
+//! Each API group (`completions`, `edits`, `embeddings`, `files`, `fine-tunes`, `images`, `models`, `moderations`, `realtime`)
+//! is behind a feature of the same name, all enabled by default. A service that only needs embeddings can
+//! disable default features to avoid compiling the SSE and multipart machinery:
+//!
+//! ```toml
+//! async-openai = { version = "0.7", default-features = false, features = ["native-tls", "embeddings"] }
+//! ```
+//!
+//! Without the `tokio` feature (enabled by default), retries, file uploads and saving images do not depend on a tokio
+//! runtime, so the library can be used from async-std or smol. HTTP connections are still made by reqwest, which
+//! needs a tokio reactor: wrap calls in [async-compat](https://crates.io/crates/async-compat) to provide one.
+//!
+//! Rate limited requests are retried with exponential backoff by the `retries` feature (enabled by default).
+//! Without it requests are made once and the `backoff` crate is not a dependency, for applications which retry
+//! at a higher layer.
+//!
+//! The `connector` feature makes API calls possible through any [hyper] connector, such as [UnixConnector] for
+//! local OpenAI compatible servers listening on a unix domain socket.
+//!
+//! Instead of an API key, the bearer token of each request can come from an [AuthProvider]. The `azure-ad` feature adds
+//! [AzureAdCredential] for Azure OpenAI Service, which refreshes Azure Active Directory tokens as they expire.
+//! [KeyPool] rotates requests across several API keys, benching those which are rate limited.
+//!
+//! [Client::from_env_with_prefix] reads the API key, base, organization, proxy and timeouts from environment
+//! variables with a custom prefix, and with the `config-file` feature `Client::from_config_file` reads them from
+//! named profiles of a TOML file.
+//!
+//! The `blocking` feature adds [blocking::Client] for programs which do not use async.
+//!
+//! To run in the browser, build for `wasm32-unknown-unknown` with the `wasm` feature instead of a TLS feature.
+//! Requests are then made with `fetch`, so files can only be uploaded from memory and images cannot be saved to disk.
+//!
 //! ## Examples
 //! For full working examples for all supported features see [examples](https://github.com/64bit/async-openai/tree/main/examples) directory in the repository.
 //!
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("the `wasm` feature is required to build for wasm32");
+
+mod auth;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+mod bulk;
+mod cancel;
 mod client;
+#[cfg(feature = "embeddings")]
+mod coalesce;
+mod compat;
+#[cfg(feature = "completions")]
 mod completion;
+mod config;
+#[cfg(all(feature = "connector", not(target_arch = "wasm32")))]
+mod connector;
+mod delta;
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
 mod download;
+#[cfg(feature = "edits")]
 mod edit;
+#[cfg(feature = "embeddings")]
 mod embedding;
+#[cfg(feature = "embeddings")]
+mod embedding_cache;
+mod env;
 pub mod error;
+#[cfg(feature = "completions")]
+mod fallback;
+#[cfg(feature = "files")]
 mod file;
+#[cfg(feature = "fine-tunes")]
 mod fine_tune;
+mod gateway;
+#[cfg(feature = "images")]
 mod image;
+pub mod math;
+#[cfg(feature = "models")]
 mod model;
+#[cfg(feature = "moderations")]
 mod moderation;
+#[cfg(not(target_arch = "wasm32"))]
+mod profile;
+#[cfg(feature = "realtime")]
+mod realtime;
+mod runtime;
+mod splitter;
+#[cfg(any(feature = "completions", feature = "fine-tunes", feature = "images"))]
+mod sse;
+mod template;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+#[cfg(feature = "fine-tunes")]
+mod training_file;
 pub mod types;
 mod util;
 
+pub use auth::AuthProvider;
+#[cfg(all(feature = "azure-ad", not(target_arch = "wasm32")))]
+pub use auth::AzureAdCredential;
+#[cfg(not(target_arch = "wasm32"))]
+pub use auth::{KeyPool, KeyRotation};
+pub use bulk::BulkRequest;
+pub use cancel::{CancelExt, CancelOn};
 pub use client::Client;
 pub use client::API_BASE;
+pub use client::BETA_HEADER;
+pub use client::DEFAULT_USER_AGENT;
 pub use client::ORGANIZATION_HEADER;
+pub use client::PROJECT_HEADER;
+pub use compat::CompatibilityMode;
+#[cfg(feature = "completions")]
 pub use completion::Completions;
+pub use config::{HttpConfig, RequestOptions};
+#[cfg(all(feature = "connector", unix))]
+pub use connector::{UnixConnection, UnixConnector};
+pub use delta::{TextDelta, TextDeltas, TextStreamExt};
+#[cfg(feature = "edits")]
 pub use edit::Edits;
+#[cfg(feature = "embeddings")]
 pub use embedding::Embeddings;
+#[cfg(feature = "embeddings")]
+pub use embedding_cache::{EmbeddingCache, EmbeddingCacheKey, LruEmbeddingCache};
+pub use env::EnvVars;
+#[cfg(feature = "completions")]
+pub use fallback::{Fallback, FallbackPolicy, FallbackTrigger};
+#[cfg(feature = "files")]
 pub use file::Files;
+#[cfg(feature = "fine-tunes")]
 pub use fine_tune::FineTunes;
+pub use gateway::Gateway;
+#[cfg(feature = "images")]
 pub use image::Images;
+#[cfg(feature = "models")]
 pub use model::Models;
+#[cfg(feature = "moderations")]
 pub use moderation::Moderations;
+#[cfg(not(target_arch = "wasm32"))]
+pub use profile::Profile;
+#[cfg(feature = "realtime")]
+pub use realtime::Realtime;
+pub use splitter::TextSplitter;
+pub use template::PromptTemplate;
+#[cfg(feature = "fine-tunes")]
+pub use training_file::TrainingFileBuilder;
@@ -0,0 +1,216 @@
+//! Vector math over embeddings, for comparing a handful of texts without a vector database.
+//!
+//! Functions take anything which is a slice of `f32`, such as `Vec<f32>` or
+//! [Embedding](crate::types::Embedding). Vectors compared with each other must have the same
+//! number of dimensions, which holds for embeddings of the same model.
+//!
+//! ```
+//! use async_openai::math::{cosine_similarity, top_k};
+//!
+//! let documents = [vec![1.0, 0.0], vec![0.6, 0.8], vec![0.0, 1.0]];
+//! let query = [0.8, 0.6];
+//!
+//! assert!((cosine_similarity(&query, &documents[1]) - 0.96).abs() < 1e-6);
+//! let nearest: Vec<usize> = top_k(&query, &documents, 2)
+//!     .into_iter()
+//!     .map(|(index, _)| index)
+//!     .collect();
+//! assert_eq!(nearest, [1, 0]);
+//! ```
+//!
+//! With the `ndarray` or `nalgebra` feature, `to_array2` and `to_dmatrix` turn embeddings into
+//! matrices for bulk linear algebra, and `base64_to_array2` and `base64_to_dmatrix` decode
+//! base64 embeddings into them.
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "ndarray", feature = "nalgebra"))]
+use crate::{error::OpenAIError, util};
+
+/// Sum of the products of the elements of `a` and `b`. The embeddings of OpenAI models are
+/// normalized to length 1, so this is also their cosine similarity.
+pub fn dot<A: AsRef<[f32]> + ?Sized, B: AsRef<[f32]> + ?Sized>(a: &A, b: &B) -> f32 {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    debug_assert_eq!(a.len(), b.len(), "vectors of different dimensions");
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Euclidean length of `v`.
+pub fn l2_norm<V: AsRef<[f32]> + ?Sized>(v: &V) -> f32 {
+    dot(v, v).sqrt()
+}
+
+/// Cosine of the angle between `a` and `b`, from -1 to 1. Zero when either is all zeros.
+pub fn cosine_similarity<A: AsRef<[f32]> + ?Sized, B: AsRef<[f32]> + ?Sized>(a: &A, b: &B) -> f32 {
+    let norms = l2_norm(a) * l2_norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot(a, b) / norms
+    }
+}
+
+/// Scale `v` in place to length 1, leaving it unchanged when it is all zeros.
+pub fn normalize<V: AsMut<[f32]> + ?Sized>(v: &mut V) {
+    let v = v.as_mut();
+    let norm = l2_norm(v);
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Indices and cosine similarities of the `k` embeddings most similar to `query`, most similar
+/// first. Fewer than `k` when there are fewer embeddings.
+pub fn top_k<Q, E>(query: &Q, embeddings: &[E], k: usize) -> Vec<(usize, f32)>
+where
+    Q: AsRef<[f32]> + ?Sized,
+    E: AsRef<[f32]>,
+{
+    let mut scores: Vec<(usize, f32)> = embeddings
+        .iter()
+        .map(|embedding| cosine_similarity(query, embedding))
+        .enumerate()
+        .collect();
+    let by_score =
+        |a: &(usize, f32), b: &(usize, f32)| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal);
+    if k < scores.len() {
+        scores.select_nth_unstable_by(k, by_score);
+        scores.truncate(k);
+    }
+    scores.sort_by(by_score);
+    scores
+}
+
+/// Number of dimensions of each of `embeddings`, failing when they differ.
+#[cfg(any(feature = "ndarray", feature = "nalgebra"))]
+fn dimensions<E: AsRef<[f32]>>(embeddings: &[E]) -> Result<usize, OpenAIError> {
+    let dimensions = embeddings.first().map_or(0, |e| e.as_ref().len());
+    for (index, embedding) in embeddings.iter().enumerate() {
+        check_dimensions(index, embedding.as_ref().len(), dimensions)?;
+    }
+    Ok(dimensions)
+}
+
+#[cfg(any(feature = "ndarray", feature = "nalgebra"))]
+fn check_dimensions(index: usize, len: usize, dimensions: usize) -> Result<(), OpenAIError> {
+    if len == dimensions {
+        return Ok(());
+    }
+    Err(OpenAIError::InvalidArgument(format!(
+        "embedding {index} has {len} dimensions, expected {dimensions}"
+    )))
+}
+
+/// Elements of the base64 `encoded` embeddings one after the other, decoded straight into
+/// one buffer, with the number of dimensions of each.
+#[cfg(any(feature = "ndarray", feature = "nalgebra"))]
+fn decode_row_major<S: AsRef<str>>(encoded: &[S]) -> Result<(usize, Vec<f32>), OpenAIError> {
+    let mut bytes = vec![];
+    let mut elements = vec![];
+    let mut dimensions = 0;
+    for (index, embedding) in encoded.iter().enumerate() {
+        let start = elements.len();
+        util::decode_f32_le(embedding.as_ref(), &mut bytes, &mut elements)
+            .map_err(|e| OpenAIError::InvalidArgument(format!("embedding {index}: {e}")))?;
+        if index == 0 {
+            dimensions = elements.len();
+            elements.reserve(dimensions * (encoded.len() - 1));
+        }
+        check_dimensions(index, elements.len() - start, dimensions)?;
+    }
+    Ok((dimensions, elements))
+}
+
+/// Matrix with one embedding per row. Fails when the embeddings differ in dimensions.
+#[cfg(feature = "ndarray")]
+pub fn to_array2<E: AsRef<[f32]>>(embeddings: &[E]) -> Result<ndarray::Array2<f32>, OpenAIError> {
+    let dimensions = dimensions(embeddings)?;
+    let mut elements = Vec::with_capacity(embeddings.len() * dimensions);
+    for embedding in embeddings {
+        elements.extend_from_slice(embedding.as_ref());
+    }
+    ndarray::Array2::from_shape_vec((embeddings.len(), dimensions), elements)
+        .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))
+}
+
+/// Matrix with one embedding per row, from embeddings requested with `encoding_format` base64,
+/// such as the `embedding` strings of a raw response. They are decoded straight into the
+/// buffer of the matrix, without a `Vec<f32>` per embedding.
+#[cfg(feature = "ndarray")]
+pub fn base64_to_array2<S: AsRef<str>>(encoded: &[S]) -> Result<ndarray::Array2<f32>, OpenAIError> {
+    let (dimensions, elements) = decode_row_major(encoded)?;
+    ndarray::Array2::from_shape_vec((encoded.len(), dimensions), elements)
+        .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))
+}
+
+/// Matrix with one embedding per row. Fails when the embeddings differ in dimensions.
+#[cfg(feature = "nalgebra")]
+pub fn to_dmatrix<E: AsRef<[f32]>>(
+    embeddings: &[E],
+) -> Result<nalgebra::DMatrix<f32>, OpenAIError> {
+    let dimensions = dimensions(embeddings)?;
+    Ok(nalgebra::DMatrix::from_row_iterator(
+        embeddings.len(),
+        dimensions,
+        embeddings.iter().flat_map(|e| e.as_ref().iter().copied()),
+    ))
+}
+
+/// Like [base64_to_array2], a matrix with one embedding per row from base64 embeddings.
+#[cfg(feature = "nalgebra")]
+pub fn base64_to_dmatrix<S: AsRef<str>>(
+    encoded: &[S],
+) -> Result<nalgebra::DMatrix<f32>, OpenAIError> {
+    let (dimensions, elements) = decode_row_major(encoded)?;
+    Ok(nalgebra::DMatrix::from_row_iterator(
+        encoded.len(),
+        dimensions,
+        elements,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cosine_similarity, dot, l2_norm, normalize, top_k};
+
+    #[test]
+    fn test_vector_math() {
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), 32.0);
+        assert_eq!(l2_norm(&[3.0, 4.0]), 5.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 0.0]), 0.0);
+        assert!((cosine_similarity(&[1.0, 1.0], &[-2.0, -2.0]) + 1.0).abs() < 1e-6);
+
+        let mut v = vec![3.0, 4.0];
+        normalize(&mut v);
+        assert_eq!(v, [0.6, 0.8]);
+        let mut zeros = [0.0; 2];
+        normalize(&mut zeros);
+        assert_eq!(zeros, [0.0; 2]);
+
+        let embeddings = [[0.0, 1.0], [1.0, 0.0], [1.0, 1.0], [-1.0, 0.0]];
+        let nearest = top_k(&[1.0, 0.1], &embeddings, 3);
+        let indices: Vec<usize> = nearest.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, [1, 2, 0]);
+        assert_eq!(top_k(&[1.0, 0.0], &embeddings, 10).len(), 4);
+        assert!(top_k(&[1.0, 0.0], &embeddings, 0).is_empty());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_base64_to_array2() {
+        use base64::{engine::general_purpose, Engine as _};
+
+        use super::{base64_to_array2, to_array2};
+
+        let encode = |row: &[f32]| {
+            let bytes: Vec<u8> = row.iter().flat_map(|x| x.to_le_bytes()).collect();
+            general_purpose::STANDARD.encode(bytes)
+        };
+        let rows = [[1.0, 2.0], [3.0, 4.0]];
+        let encoded: Vec<String> = rows.iter().map(|row| encode(row)).collect();
+        assert_eq!(
+            base64_to_array2(&encoded).unwrap(),
+            to_array2(&rows).unwrap()
+        );
+        assert!(base64_to_array2(&[encoded[0].clone(), encode(&[1.0])]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
     types::{DeleteModelResponse, ListModelResponse, Model},
     Client,
@@ -9,29 +10,41 @@ use crate::{
 /// models are available and the differences between them.
 pub struct Models<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> Models<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Lists the currently available models, and provides basic information
     /// about each one such as the owner and availability.
     pub async fn list(&self) -> Result<ListModelResponse, OpenAIError> {
-        self.client.get("/models").await
+        self.client.get("/models", &self.options).await
     }
 
     /// Retrieves a model instance, providing basic information about the model
     /// such as the owner and permissioning.
     pub async fn retrieve(&self, id: &str) -> Result<Model, OpenAIError> {
-        self.client.get(format!("/models/{id}").as_str()).await
+        self.client
+            .get(format!("/models/{id}").as_str(), &self.options)
+            .await
     }
 
     /// Delete a fine-tuned model. You must have the Owner role in your organization.
     pub async fn delete(&self, model: &str) -> Result<DeleteModelResponse, OpenAIError> {
         self.client
-            .delete(format!("/models/{model}").as_str())
+            .delete(format!("/models/{model}").as_str(), &self.options)
             .await
     }
 }
@@ -1,4 +1,5 @@
 use crate::{
+    config::RequestOptions,
     error::OpenAIError,
     types::{CreateModerationRequest, CreateModerationResponse},
     Client,
@@ -9,11 +10,21 @@ use crate::{
 /// Related guide: [Moderations](https://platform.openai.com/docs/guides/moderation/overview)
 pub struct Moderations<'c> {
     client: &'c Client,
+    options: RequestOptions,
 }
 
 impl<'c> Moderations<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Classifies if text violates OpenAI's Content Policy
@@ -21,6 +32,8 @@ impl<'c> Moderations<'c> {
         &self,
         request: CreateModerationRequest,
     ) -> Result<CreateModerationResponse, OpenAIError> {
-        self.client.post("/moderations", request).await
+        self.client
+            .post("/moderations", request, &self.options)
+            .await
     }
 }
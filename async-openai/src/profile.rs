@@ -0,0 +1,306 @@
+//! Client settings read from environment variables or a configuration file, so deployments
+//! can switch between OpenAI, Azure and gateways without code changes.
+use std::{collections::HashMap, fmt, time::Duration};
+
+use serde::Deserialize;
+
+use crate::{error::OpenAIError, Client, HttpConfig};
+
+/// Settings of a [Client], all optional. Read with [Profile::from_env], or from a profile of a
+/// configuration file with [Client::from_config_file] and the `config-file` feature.
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// API key. Without it or `api_key_env` the client has no API key, the `OPENAI_API_KEY`
+    /// environment variable is not read
+    pub api_key: Option<String>,
+    /// Name of an environment variable holding the API key, to keep it out of configuration files
+    pub api_key_env: Option<String>,
+    pub api_base: Option<String>,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    /// URL of a proxy for all requests, e.g. `http://proxy.internal:3128`
+    pub proxy: Option<String>,
+    /// See [HttpConfig::with_timeout]
+    pub timeout_secs: Option<u64>,
+    /// See [HttpConfig::with_connect_timeout]
+    pub connect_timeout_secs: Option<u64>,
+}
+
+impl fmt::Debug for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profile")
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("api_key_env", &self.api_key_env)
+            .field("api_base", &self.api_base)
+            .field("org_id", &self.org_id)
+            .field("project_id", &self.project_id)
+            .field("proxy", &self.proxy)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("connect_timeout_secs", &self.connect_timeout_secs)
+            .finish()
+    }
+}
+
+impl Profile {
+    /// Read the settings from the environment variables `{prefix}_API_KEY`, `{prefix}_BASE_URL`
+    /// or `{prefix}_API_BASE`, `{prefix}_ORG_ID`, `{prefix}_PROJECT_ID`, `{prefix}_PROXY`,
+    /// `{prefix}_TIMEOUT_SECS` and `{prefix}_CONNECT_TIMEOUT_SECS`.
+    pub fn from_env(prefix: &str) -> Result<Self, OpenAIError> {
+        let var = |name: &str| std::env::var(format!("{prefix}_{name}")).ok();
+        let secs = |name: &str| {
+            var(name)
+                .map(|secs| {
+                    secs.parse().map_err(|_| {
+                        OpenAIError::InvalidConfig(format!(
+                            "{prefix}_{name} is not a number of seconds: {secs}"
+                        ))
+                    })
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            api_key: var("API_KEY"),
+            api_key_env: None,
+            api_base: var("BASE_URL").or_else(|| var("API_BASE")),
+            org_id: var("ORG_ID"),
+            project_id: var("PROJECT_ID"),
+            proxy: var("PROXY"),
+            timeout_secs: secs("TIMEOUT_SECS")?,
+            connect_timeout_secs: secs("CONNECT_TIMEOUT_SECS")?,
+        })
+    }
+}
+
+/// Named profiles of a configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "config-file"), allow(dead_code))]
+struct ConfigFile {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+impl ConfigFile {
+    #[cfg_attr(not(feature = "config-file"), allow(dead_code))]
+    fn into_profile(mut self, name: Option<&str>) -> Result<Profile, OpenAIError> {
+        let name = name
+            .map(str::to_string)
+            .or(self.default_profile)
+            .unwrap_or_else(|| "default".to_string());
+        self.profiles
+            .remove(&name)
+            .ok_or_else(|| OpenAIError::InvalidConfig(format!("no profile named {name}")))
+    }
+}
+
+impl Client {
+    /// Create client with the settings of `profile`. Settings which are not set keep their
+    /// defaults, without reading the `OPENAI_*` environment variables of [Client::new]. Fails
+    /// when the variable named by `api_key_env` is not set.
+    pub fn from_profile(profile: &Profile) -> Result<Self, OpenAIError> {
+        let mut client = Client::with_defaults();
+
+        let api_key = match (&profile.api_key, &profile.api_key_env) {
+            (Some(api_key), _) => Some(api_key.clone()),
+            (None, Some(name)) => Some(std::env::var(name).map_err(|_| {
+                OpenAIError::InvalidConfig(format!("environment variable {name} is not set"))
+            })?),
+            (None, None) => None,
+        };
+        if let Some(api_key) = api_key {
+            client = client.with_api_key(api_key);
+        }
+        if let Some(api_base) = &profile.api_base {
+            client = client.with_api_base(api_base);
+        }
+        if let Some(org_id) = &profile.org_id {
+            client = client.with_org_id(org_id);
+        }
+        if let Some(project_id) = &profile.project_id {
+            client = client.with_project_id(project_id);
+        }
+
+        if profile.proxy.is_none()
+            && profile.timeout_secs.is_none()
+            && profile.connect_timeout_secs.is_none()
+        {
+            return Ok(client);
+        }
+
+        let mut config = HttpConfig::new();
+        if let Some(proxy) = &profile.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| OpenAIError::InvalidConfig(format!("invalid proxy {proxy}: {e}")))?;
+            config = config.with_proxy(proxy);
+        }
+        if let Some(secs) = profile.timeout_secs {
+            config = config.with_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = profile.connect_timeout_secs {
+            config = config.with_connect_timeout(Duration::from_secs(secs));
+        }
+
+        let http_client = config.client_builder().build()?;
+        Ok(client.with_http_client(http_client))
+    }
+
+    /// Create client from the environment variables starting with `prefix`, see [Profile::from_env].
+    ///
+    /// ```
+    /// use async_openai::Client;
+    ///
+    /// // Reads MYAPP_OPENAI_API_KEY, MYAPP_OPENAI_API_BASE, ...
+    /// let client = Client::from_env_with_prefix("MYAPP_OPENAI").unwrap();
+    /// ```
+    pub fn from_env_with_prefix(prefix: &str) -> Result<Self, OpenAIError> {
+        Client::from_profile(&Profile::from_env(prefix)?)
+    }
+
+    /// Create client from a profile of the TOML configuration file at `path`: the profile named
+    /// by the `OPENAI_PROFILE` environment variable, else the `default_profile` of the file,
+    /// else the one named `default`.
+    ///
+    /// ```toml
+    /// default_profile = "openai"
+    ///
+    /// [profiles.openai]
+    /// api_key_env = "OPENAI_API_KEY"
+    ///
+    /// [profiles.staging]
+    /// api_base = "https://gateway.staging.internal/v1"
+    /// api_key_env = "STAGING_GATEWAY_KEY"
+    /// proxy = "http://proxy.internal:3128"
+    /// timeout_secs = 60
+    /// ```
+    #[cfg(feature = "config-file")]
+    pub fn from_config_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, OpenAIError> {
+        let profile = std::env::var("OPENAI_PROFILE").ok();
+        Client::from_config_file_profile(path, profile.as_deref())
+    }
+
+    /// Create client from the profile named `profile` of the TOML configuration file at `path`,
+    /// or its default profile, see [Client::from_config_file].
+    #[cfg(feature = "config-file")]
+    pub fn from_config_file_profile<P: AsRef<std::path::Path>>(
+        path: P,
+        profile: Option<&str>,
+    ) -> Result<Self, OpenAIError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| OpenAIError::FileReadError(format!("{}: {e}", path.display())))?;
+        let file: ConfigFile = toml::from_str(&contents)
+            .map_err(|e| OpenAIError::InvalidConfig(format!("{}: {e}", path.display())))?;
+
+        Client::from_profile(&file.into_profile(profile)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, Profile};
+    use crate::Client;
+
+    #[test]
+    fn test_profile_from_env() {
+        std::env::set_var("ASYNC_OPENAI_TEST_API_KEY", "sk-test");
+        std::env::set_var("ASYNC_OPENAI_TEST_API_BASE", "http://localhost:8080/v1");
+        std::env::set_var("ASYNC_OPENAI_TEST_TIMEOUT_SECS", "30");
+
+        let profile = Profile::from_env("ASYNC_OPENAI_TEST").unwrap();
+        assert_eq!(
+            profile,
+            Profile {
+                api_key: Some("sk-test".into()),
+                api_base: Some("http://localhost:8080/v1".into()),
+                timeout_secs: Some(30),
+                ..Default::default()
+            }
+        );
+
+        let client = Client::from_profile(&profile).unwrap();
+        assert_eq!(client.api_key(), "sk-test");
+        assert_eq!(client.api_base(), "http://localhost:8080/v1");
+
+        std::env::set_var("ASYNC_OPENAI_TEST_TIMEOUT_SECS", "soon");
+        assert!(Profile::from_env("ASYNC_OPENAI_TEST").is_err());
+
+        assert!(!format!("{profile:?}").contains("sk-test"));
+    }
+
+    #[test]
+    fn test_profile_key_env_is_required() {
+        let profile = Profile {
+            api_key_env: Some("ASYNC_OPENAI_TEST_UNSET_KEY".into()),
+            api_base: Some("https://gateway.staging.internal/v1".into()),
+            ..Default::default()
+        };
+        assert!(Client::from_profile(&profile).is_err());
+
+        // Unset settings are not read from OPENAI_* either, e.g. a key set for the live tests
+        let client = Client::from_profile(&Profile {
+            api_base: profile.api_base,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(client.api_key(), "");
+    }
+
+    #[test]
+    fn test_config_file_profiles() {
+        let file: ConfigFile = serde_json::from_str(
+            r#"{
+                "default_profile": "openai",
+                "profiles": {
+                    "openai": {"api_key": "sk-openai"},
+                    "staging": {"api_base": "https://gateway.staging.internal/v1", "proxy": "http://proxy.internal:3128"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let profile = file.into_profile(None).unwrap();
+        assert_eq!(profile.api_key.as_deref(), Some("sk-openai"));
+
+        let file: ConfigFile = serde_json::from_str(r#"{"profiles": {}}"#).unwrap();
+        assert!(file.into_profile(Some("staging")).is_err());
+    }
+
+    #[cfg(feature = "config-file")]
+    #[test]
+    fn test_config_file_toml() {
+        let path = std::env::temp_dir().join(format!("async-openai-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+                default_profile = "openai"
+
+                [profiles.openai]
+                api_key = "sk-openai"
+
+                [profiles.staging]
+                api_base = "https://gateway.staging.internal/v1"
+                api_key_env = "ASYNC_OPENAI_TEST_STAGING_KEY"
+                timeout_secs = 60
+            "#,
+        )
+        .unwrap();
+        std::env::set_var("ASYNC_OPENAI_TEST_STAGING_KEY", "sk-staging");
+
+        let client = Client::from_config_file_profile(&path, None).unwrap();
+        assert_eq!(client.api_key(), "sk-openai");
+        assert_eq!(client.api_base(), crate::API_BASE);
+
+        let client = Client::from_config_file_profile(&path, Some("staging")).unwrap();
+        assert_eq!(client.api_key(), "sk-staging");
+        assert_eq!(client.api_base(), "https://gateway.staging.internal/v1");
+        assert!(Client::from_config_file_profile(&path, Some("production")).is_err());
+
+        std::fs::write(&path, "[profiles.default]\ntimeout = 60\n").unwrap();
+        let unknown_field = Client::from_config_file_profile(&path, None);
+        std::fs::remove_file(&path).unwrap();
+        assert!(unknown_field.is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    config::RequestOptions,
+    error::OpenAIError,
+    types::realtime::{
+        EphemeralSession, SessionConfig, TranscriptionSession, TranscriptionSessionConfig,
+    },
+    Client,
+};
+
+/// Start realtime sessions for speech-to-speech conversations with a model.
+///
+/// The events of a session are exchanged over a WebSocket or a WebRTC data channel, see
+/// [crate::types::realtime]. This group makes the REST calls which set them up: a backend
+/// creates an [EphemeralSession] whose short-lived key it hands to a browser or app, or
+/// answers the WebRTC offer of one with [Realtime::connect_webrtc].
+///
+/// Related guide: [Realtime](https://platform.openai.com/docs/guides/realtime)
+pub struct Realtime<'c> {
+    client: &'c Client,
+    options: RequestOptions,
+}
+
+impl<'c> Realtime<'c> {
+    pub fn new(client: &'c Client) -> Self {
+        Self {
+            client,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Make the API calls of this group with `options`, e.g. a deadline.
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Creates a session with the settings of `request`, returning the ephemeral key a client
+    /// connects to it with, which expires after a minute.
+    pub async fn create_session(
+        &self,
+        request: SessionConfig,
+    ) -> Result<EphemeralSession, OpenAIError> {
+        request.validate()?;
+        self.client
+            .post("/realtime/sessions", request, &self.options)
+            .await
+    }
+
+    /// Creates a session which only transcribes the input audio, returning the ephemeral key
+    /// a client connects to it with.
+    pub async fn create_transcription_session(
+        &self,
+        request: TranscriptionSessionConfig,
+    ) -> Result<TranscriptionSession, OpenAIError> {
+        request.validate()?;
+        self.client
+            .post("/realtime/transcription_sessions", request, &self.options)
+            .await
+    }
+
+    /// Sends the SDP offer of a WebRTC peer connection to `model`, returning the SDP answer to
+    /// set as its remote description.
+    ///
+    /// Made with the API key of the client, or with the ephemeral key of a session set in the
+    /// options of this group with [RequestOptions::with_api_key].
+    pub async fn create_call(&self, model: &str, offer_sdp: &str) -> Result<String, OpenAIError> {
+        self.client
+            .post_text(
+                "/realtime",
+                &[("model", model)],
+                "application/sdp",
+                offer_sdp.to_string(),
+                &self.options,
+            )
+            .await
+    }
+
+    /// Creates a session with the settings of `request` and answers `offer_sdp` with its
+    /// ephemeral key, so a backend can relay the offer of a browser or app without exposing
+    /// its API key. `request` must name the model.
+    pub async fn connect_webrtc(
+        &self,
+        request: SessionConfig,
+        offer_sdp: &str,
+    ) -> Result<String, OpenAIError> {
+        let Some(model) = request.model.clone() else {
+            return Err(OpenAIError::InvalidArgument(
+                "the session needs a model to connect to".into(),
+            ));
+        };
+        let session = self.create_session(request).await?;
+        let options = self
+            .options
+            .clone()
+            .with_api_key(session.client_secret.value);
+        self.client
+            .realtime()
+            .with_options(options)
+            .create_call(&model, offer_sdp)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use crate::{types::realtime::SessionConfigArgs, Client};
+
+    /// Serve `responses` of content type and body in turn, sending back each request received.
+    async fn serve(responses: Vec<(&'static str, String)>) -> (Client, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = mpsc::channel(responses.len());
+        tokio::spawn(async move {
+            for (content_type, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                while !is_complete(&request) {
+                    let mut buf = [0; 4096];
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                sender
+                    .send(String::from_utf8(request).unwrap())
+                    .await
+                    .unwrap();
+                let response = format!(
+                    "HTTP/1.1 201 Created\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (
+            Client::new()
+                .with_api_key("sk-test")
+                .with_api_base(format!("http://{addr}")),
+            receiver,
+        )
+    }
+
+    /// Whether `request` has its headers and as much body as its content length.
+    fn is_complete(request: &[u8]) -> bool {
+        let request = String::from_utf8_lossy(request).to_lowercase();
+        let Some((head, body)) = request.split_once("\r\n\r\n") else {
+            return false;
+        };
+        let length = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length: "))
+            .map_or(0, |length| length.parse().unwrap());
+        body.len() >= length
+    }
+
+    #[tokio::test]
+    async fn test_connect_webrtc() {
+        let session = serde_json::json!({
+            "id": "sess_1",
+            "object": "realtime.session",
+            "model": "gpt-4o-realtime-preview",
+            "client_secret": {"value": "ek_1", "expires_at": 1700000060},
+        });
+        let (client, mut requests) = serve(vec![
+            ("application/json", session.to_string()),
+            ("application/sdp", "v=0\r\ns=answer\r\n".into()),
+        ])
+        .await;
+
+        let request = SessionConfigArgs::default()
+            .model("gpt-4o-realtime-preview")
+            .build()
+            .unwrap();
+        let answer = client
+            .realtime()
+            .connect_webrtc(request, "v=0\r\ns=offer\r\n")
+            .await
+            .unwrap();
+        assert_eq!(answer, "v=0\r\ns=answer\r\n");
+
+        let create = requests.recv().await.unwrap().to_lowercase();
+        assert!(create.starts_with("post /realtime/sessions "));
+        assert!(create.contains("authorization: bearer sk-test"));
+        assert!(create.contains("openai-beta: realtime=v1"));
+        let call = requests.recv().await.unwrap();
+        assert!(call.starts_with("POST /realtime?model=gpt-4o-realtime-preview "));
+        assert!(call.to_lowercase().contains("authorization: bearer ek_1"));
+        assert!(call
+            .to_lowercase()
+            .contains("content-type: application/sdp"));
+        assert!(call.ends_with("v=0\r\ns=offer\r\n"));
+
+        let error = client
+            .realtime()
+            .connect_webrtc(Default::default(), "v=0\r\n")
+            .await;
+        assert!(error.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_call_escapes_model() {
+        let (client, mut requests) = serve(vec![("application/sdp", "v=0\r\n".into())]).await;
+        client
+            .realtime()
+            .create_call("model&voice=alloy #1", "v=0\r\n")
+            .await
+            .unwrap();
+        let call = requests.recv().await.unwrap();
+        assert!(call.starts_with("POST /realtime?model=model%26voice%3Dalloy+%231 "));
+    }
+}
@@ -0,0 +1,204 @@
+//! Pieces that differ between native targets and `wasm32`, where requests are made
+//! by the browser's `fetch` and futures are not `Send`, and between running with and
+//! without the `tokio` feature.
+#[cfg(feature = "files")]
+use bytes::Bytes;
+
+/// A boxed future, `Send` except on `wasm32`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type BoxFuture<'a, T> = futures::future::BoxFuture<'a, T>;
+/// A boxed future, `Send` except on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) type BoxFuture<'a, T> = futures::future::LocalBoxFuture<'a, T>;
+
+/// A boxed stream, `Send` except on `wasm32`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type BoxStream<'a, T> = futures::stream::BoxStream<'a, T>;
+/// A boxed stream, `Send` except on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) type BoxStream<'a, T> = futures::stream::LocalBoxStream<'a, T>;
+
+/// `Send` except on `wasm32`, for bounds on futures made of HTTP calls.
+#[cfg(all(feature = "embeddings", not(target_arch = "wasm32")))]
+pub(crate) trait MaybeSend: Send {}
+#[cfg(all(feature = "embeddings", not(target_arch = "wasm32")))]
+impl<T: Send> MaybeSend for T {}
+/// `Send` except on `wasm32`, for bounds on futures made of HTTP calls.
+#[cfg(all(feature = "embeddings", target_arch = "wasm32"))]
+pub(crate) trait MaybeSend {}
+#[cfg(all(feature = "embeddings", target_arch = "wasm32"))]
+impl<T> MaybeSend for T {}
+
+/// Waits between retries.
+#[cfg(all(feature = "retries", feature = "_api"))]
+pub(crate) struct Sleeper;
+
+#[cfg(all(feature = "retries", feature = "_api"))]
+impl backoff::future::Sleeper for Sleeper {
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    type Sleep = tokio::time::Sleep;
+    #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+    type Sleep = futures_timer::Delay;
+
+    fn sleep(&self, duration: std::time::Duration) -> Self::Sleep {
+        #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+        return tokio::time::sleep(duration);
+        #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+        return futures_timer::Delay::new(duration);
+    }
+}
+
+/// Wait for `duration`, with tokio's timer or with futures-timer otherwise.
+#[cfg(any(feature = "fine-tunes", feature = "test-utils", test))]
+pub(crate) async fn sleep(duration: std::time::Duration) {
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    tokio::time::sleep(duration).await;
+    #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+    futures_timer::Delay::new(duration).await;
+}
+
+/// Run `future` in its own task with tokio, or as part of the calling task otherwise.
+/// Fails with the reason the task did not complete.
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
+pub(crate) async fn spawn<F>(future: F) -> Result<F::Output, String>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "tokio")]
+    return tokio::spawn(future).await.map_err(|e| e.to_string());
+    #[cfg(not(feature = "tokio"))]
+    return Ok(future.await);
+}
+
+/// File system access, through tokio's thread pool with the `tokio` feature. Without it,
+/// files are accessed with blocking calls on the calling task.
+#[cfg(all(
+    any(feature = "fine-tunes", feature = "multipart"),
+    not(target_arch = "wasm32")
+))]
+pub(crate) mod fs {
+    use std::{io, path::Path};
+
+    #[cfg(feature = "images")]
+    pub(crate) async fn create_dir_all(path: &Path) -> io::Result<()> {
+        #[cfg(feature = "tokio")]
+        return tokio::fs::create_dir_all(path).await;
+        #[cfg(not(feature = "tokio"))]
+        return std::fs::create_dir_all(path);
+    }
+
+    /// A file read in parts, skipping those which are not needed.
+    #[cfg(feature = "images")]
+    pub(crate) struct File {
+        #[cfg(feature = "tokio")]
+        file: tokio::fs::File,
+        #[cfg(not(feature = "tokio"))]
+        file: std::fs::File,
+    }
+
+    #[cfg(feature = "images")]
+    impl File {
+        pub(crate) async fn open(path: &Path) -> io::Result<Self> {
+            #[cfg(feature = "tokio")]
+            let file = tokio::fs::File::open(path).await?;
+            #[cfg(not(feature = "tokio"))]
+            let file = std::fs::File::open(path)?;
+            Ok(Self { file })
+        }
+
+        pub(crate) async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+            #[cfg(feature = "tokio")]
+            {
+                use tokio::io::AsyncReadExt;
+                self.file.read_exact(buf).await.map(|_| ())
+            }
+            #[cfg(not(feature = "tokio"))]
+            {
+                use std::io::Read;
+                self.file.read_exact(buf)
+            }
+        }
+
+        /// Move `n` bytes forward without reading them.
+        pub(crate) async fn skip(&mut self, n: u32) -> io::Result<()> {
+            let pos = io::SeekFrom::Current(i64::from(n));
+            #[cfg(feature = "tokio")]
+            {
+                use tokio::io::AsyncSeekExt;
+                self.file.seek(pos).await.map(|_| ())
+            }
+            #[cfg(not(feature = "tokio"))]
+            {
+                use std::io::Seek;
+                self.file.seek(pos).map(|_| ())
+            }
+        }
+    }
+
+    #[cfg(any(feature = "fine-tunes", feature = "images"))]
+    pub(crate) async fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+        #[cfg(feature = "tokio")]
+        return tokio::fs::write(path, contents).await;
+        #[cfg(not(feature = "tokio"))]
+        return std::fs::write(path, contents);
+    }
+
+    /// A request body streaming the contents of the file at `path`.
+    #[cfg(feature = "multipart")]
+    pub(crate) async fn body(path: &Path) -> io::Result<reqwest::Body> {
+        #[cfg(feature = "tokio")]
+        {
+            use tokio_util::codec::{BytesCodec, FramedRead};
+
+            let file = tokio::fs::File::open(path).await?;
+            Ok(reqwest::Body::wrap_stream(FramedRead::new(
+                file,
+                BytesCodec::new(),
+            )))
+        }
+        #[cfg(not(feature = "tokio"))]
+        {
+            use std::io::Read;
+
+            let file = std::fs::File::open(path)?;
+            let chunks = futures::stream::unfold(file, |mut file| async move {
+                let mut chunk = vec![0; 64 * 1024];
+                match file.read(&mut chunk) {
+                    Ok(0) => None,
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        Some((Ok::<_, io::Error>(bytes::Bytes::from(chunk)), file))
+                    }
+                    Err(e) => Some((Err(e), file)),
+                }
+            });
+            Ok(reqwest::Body::wrap_stream(chunks))
+        }
+    }
+}
+
+/// Chunks of a response body as they are received. The wasm client can only read the
+/// body as a whole, so it is returned as a single chunk there.
+#[cfg(feature = "files")]
+pub(crate) struct ResponseChunks(Option<reqwest::Response>);
+
+#[cfg(feature = "files")]
+impl ResponseChunks {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self(Some(response))
+    }
+
+    pub(crate) async fn next(&mut self) -> Result<Option<Bytes>, reqwest::Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return match self.0.as_mut() {
+            Some(response) => response.chunk().await,
+            None => Ok(None),
+        };
+        #[cfg(target_arch = "wasm32")]
+        return match self.0.take() {
+            Some(response) => response.bytes().await.map(Some),
+            None => Ok(None),
+        };
+    }
+}
@@ -0,0 +1,262 @@
+//! Splitting long documents into chunks which fit the token limits of the API.
+use std::{fmt, ops::Range, sync::Arc};
+
+/// Function counting the tokens of a text, four characters per token by default.
+#[derive(Clone)]
+pub(crate) struct TokenEstimator(Arc<dyn Fn(&str) -> usize + Send + Sync>);
+
+impl TokenEstimator {
+    pub(crate) fn new<F>(estimator: F) -> Self
+    where
+        F: Fn(&str) -> usize + Send + Sync + 'static,
+    {
+        Self(Arc::new(estimator))
+    }
+
+    pub(crate) fn estimate(&self, text: &str) -> usize {
+        (self.0)(text)
+    }
+}
+
+impl Default for TokenEstimator {
+    fn default() -> Self {
+        Self::new(|text| (text.chars().count() + 3) / 4)
+    }
+}
+
+impl fmt::Debug for TokenEstimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenEstimator")
+    }
+}
+
+/// Splits text into chunks of at most `max_tokens` tokens, for embeddings or for summarizing
+/// long documents piece by piece. Chunks end at sentence boundaries where possible, then at
+/// word boundaries, and consecutive chunks can overlap by a number of tokens to keep context.
+///
+/// Tokens are counted with the same estimator as
+/// [EmbeddingBatchOptions](crate::types::EmbeddingBatchOptions): four characters per token by
+/// default, or a real tokenizer set with [TextSplitter::with_token_estimator]. The tokens of
+/// a chunk are the sum of those of its sentences or words.
+///
+/// ```
+/// use async_openai::TextSplitter;
+///
+/// let splitter = TextSplitter::new(10).with_overlap(5);
+/// let chunks = splitter.split("The cat sat. It was a sunny day. Then it rained.");
+/// assert_eq!(
+///     chunks,
+///     ["The cat sat. It was a sunny day.", "It was a sunny day. Then it rained."]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextSplitter {
+    max_tokens: usize,
+    overlap: usize,
+    token_estimator: TokenEstimator,
+}
+
+impl TextSplitter {
+    /// Splitter into chunks of at most `max_tokens` tokens, without overlap.
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens: max_tokens.max(1),
+            overlap: 0,
+            token_estimator: TokenEstimator::default(),
+        }
+    }
+
+    /// Repeat up to `tokens` tokens of the end of each chunk at the start of the next one,
+    /// in whole sentences or words.
+    pub fn with_overlap(mut self, tokens: usize) -> Self {
+        self.overlap = tokens;
+        self
+    }
+
+    /// Function counting the tokens of a text, see [TextSplitter].
+    pub fn with_token_estimator<F>(mut self, estimator: F) -> Self
+    where
+        F: Fn(&str) -> usize + Send + Sync + 'static,
+    {
+        self.token_estimator = TokenEstimator::new(estimator);
+        self
+    }
+
+    /// Split `text` into chunks, with surrounding whitespace trimmed.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let (pieces, sentence_ends) = self.pieces(text);
+        // Tokens of the pieces before each piece, so a run of pieces is counted in constant time
+        let mut before = vec![0];
+        for (_, tokens) in &pieces {
+            before.push(before[before.len() - 1] + tokens);
+        }
+        let tokens = |run: Range<usize>| before[run.end] - before[run.start];
+        let pieces: Vec<_> = pieces.into_iter().map(|(range, _)| range).collect();
+
+        let mut chunks = vec![];
+        let mut start = 0;
+        while start < pieces.len() {
+            let mut end = start + 1;
+            while end < pieces.len() && tokens(start..end + 1) <= self.max_tokens {
+                end += 1;
+            }
+            // End a full chunk at the last sentence end in it
+            if end < pieces.len() {
+                if let Some(last) = (start..end).rev().find(|&i| sentence_ends[i]) {
+                    end = last + 1;
+                }
+            }
+            let chunk = text[pieces[start].start..pieces[end - 1].end].trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk);
+            }
+            if end == pieces.len() {
+                break;
+            }
+
+            // Start the next chunk with as many of the last pieces as fit in the overlap
+            let mut next = end;
+            while next > start + 1 && tokens(next - 1..end) <= self.overlap {
+                next -= 1;
+            }
+            start = next;
+        }
+        chunks
+    }
+
+    /// Consecutive ranges of `text`, each of at most `max_tokens` tokens unless a single
+    /// character is more: sentences, or the words or characters of sentences which are too long.
+    /// Returned with their tokens, and with whether each range ends a sentence.
+    fn pieces(&self, text: &str) -> (Vec<(Range<usize>, usize)>, Vec<bool>) {
+        let tokens = |range: &Range<usize>| self.token_estimator.estimate(&text[range.clone()]);
+
+        let mut pieces = vec![];
+        let mut sentence_ends = vec![];
+        for sentence in boundaries(text, |prev| matches!(prev, '.' | '!' | '?')) {
+            let sentence_tokens = tokens(&sentence);
+            if sentence_tokens <= self.max_tokens {
+                pieces.push((sentence, sentence_tokens));
+                sentence_ends.push(true);
+                continue;
+            }
+            let offset = sentence.start;
+            for word in boundaries(&text[sentence], |_| true) {
+                let word = word.start + offset..word.end + offset;
+                let word_tokens = tokens(&word);
+                if word_tokens <= self.max_tokens {
+                    pieces.push((word, word_tokens));
+                    continue;
+                }
+                let mut start = word.start;
+                let mut fitting = 0;
+                for (i, c) in text[word.clone()].char_indices() {
+                    let end = word.start + i + c.len_utf8();
+                    let end_tokens = tokens(&(start..end));
+                    if end > start + c.len_utf8() && end_tokens > self.max_tokens {
+                        pieces.push((start..end - c.len_utf8(), fitting));
+                        start = end - c.len_utf8();
+                        fitting = tokens(&(start..end));
+                    } else {
+                        fitting = end_tokens;
+                    }
+                }
+                pieces.push((start..word.end, fitting));
+            }
+            sentence_ends.resize(pieces.len(), false);
+            if let Some(last) = sentence_ends.last_mut() {
+                *last = true;
+            }
+        }
+        (pieces, sentence_ends)
+    }
+}
+
+/// Ranges of `text` ending after a line break, or after the whitespace which follows a
+/// character matching `ends`.
+fn boundaries(text: &str, ends: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut prev: Option<char> = None;
+    let mut in_break = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if c == '\n' || prev.map_or(false, |prev| !prev.is_whitespace() && ends(prev)) {
+                in_break = true;
+            }
+        } else if in_break {
+            ranges.push(start..i);
+            start = i;
+            in_break = false;
+        }
+        prev = Some(c);
+    }
+    if start < text.len() {
+        ranges.push(start..text.len());
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextSplitter;
+
+    #[test]
+    fn test_split() {
+        let text = "One two three. Four five six seven eight nine ten eleven twelve.\nThirteen!";
+
+        let chunks = TextSplitter::new(100).split(text);
+        assert_eq!(chunks, [text]);
+
+        // The long sentence is split into words
+        let words = |text: &str| text.split_whitespace().count();
+        let chunks = TextSplitter::new(4).with_token_estimator(words).split(text);
+        assert_eq!(
+            chunks,
+            [
+                "One two three.",
+                "Four five six seven",
+                "eight nine ten eleven",
+                "twelve.\nThirteen!"
+            ]
+        );
+
+        let chunks = TextSplitter::new(4)
+            .with_overlap(1)
+            .with_token_estimator(words)
+            .split(text);
+        assert_eq!(
+            chunks,
+            [
+                "One two three.",
+                "Four five six seven",
+                "seven eight nine ten",
+                "ten eleven twelve.\nThirteen!"
+            ]
+        );
+
+        // Words longer than the limit are split into characters
+        let chunks = TextSplitter::new(2).split("abcdefghijkl");
+        assert_eq!(chunks, ["abcdefgh", "ijkl"]);
+        assert!(TextSplitter::new(2).split(" \n ").is_empty());
+    }
+
+    #[test]
+    fn test_split_estimates_each_sentence_once() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let splitter = TextSplitter::new(50).with_token_estimator(move |text| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            text.split_whitespace().count()
+        });
+        let text = "One two three four. ".repeat(1000);
+
+        let chunks = splitter.split(&text);
+        assert_eq!(chunks.len(), 84);
+        assert_eq!(calls.load(Ordering::Relaxed), 1000);
+    }
+}
@@ -0,0 +1,178 @@
+//! Decoding of the [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation)
+//! streamed by the API, for responses of the HTTP client and of a custom connector alike.
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    compat::CompatibilityMode, config::RequestOptions, error::OpenAIError, runtime::BoxStream,
+};
+
+/// Incremental parser of the name and `data` of server-sent events. Received bytes and the
+/// fields of the event being received are kept in buffers which are reused for every event.
+#[derive(Debug, Default)]
+struct EventData {
+    /// Received bytes, parsed up to `pos`
+    buffer: Vec<u8>,
+    pos: usize,
+    /// Name of the event being received, empty for the default `message`
+    event: Vec<u8>,
+    /// Data lines of the event being received, each followed by a newline
+    data: Vec<u8>,
+    /// Whether `event` and `data` belong to an event returned by `next`
+    dispatched: bool,
+}
+
+/// Name and data of a complete event, borrowed from the buffers of [EventData].
+#[derive(Debug, PartialEq, Eq)]
+struct Event<'a> {
+    name: &'a [u8],
+    data: &'a [u8],
+}
+
+impl EventData {
+    fn push(&mut self, chunk: &[u8]) {
+        // Only the incomplete last line is left to move to the front
+        self.buffer.drain(..self.pos);
+        self.pos = 0;
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    fn next(&mut self) -> Option<Event<'_>> {
+        if self.dispatched {
+            self.dispatched = false;
+            self.event.clear();
+            self.data.clear();
+        }
+
+        while let Some(end) = memchr::memchr(b'\n', &self.buffer[self.pos..]) {
+            let mut line = &self.buffer[self.pos..self.pos + end];
+            self.pos += end + 1;
+            if let [rest @ .., b'\r'] = line {
+                line = rest;
+            }
+
+            if line.is_empty() {
+                if self.data.is_empty() {
+                    self.event.clear();
+                    continue;
+                }
+                self.dispatched = true;
+                // Without the newline after the last data line
+                let data = &self.data[..self.data.len() - 1];
+                let name = match self.event.as_slice() {
+                    [] => b"message".as_slice(),
+                    name => name,
+                };
+                return Some(Event { name, data });
+            } else if let Some(data) = line.strip_prefix(b"data:") {
+                self.data
+                    .extend_from_slice(data.strip_prefix(b" ").unwrap_or(data));
+                self.data.push(b'\n');
+            } else if let Some(event) = line.strip_prefix(b"event:") {
+                self.event.clear();
+                self.event
+                    .extend_from_slice(event.strip_prefix(b" ").unwrap_or(event));
+            }
+        }
+        None
+    }
+}
+
+/// Parse the data of the events in the body of `response` as they are received.
+///
+/// The stream ends at a `[DONE]` message, or after the event named `last_event`. When the
+/// body cannot be read after the deadline of `options` has passed, it yields
+/// [OpenAIError::DeadlineExceeded] and ends.
+pub(crate) fn events<O>(
+    response: reqwest::Response,
+    compatibility: CompatibilityMode,
+    options: RequestOptions,
+    last_event: Option<&'static str>,
+) -> BoxStream<'static, Result<O, OpenAIError>>
+where
+    O: DeserializeOwned + Send + 'static,
+{
+    let chunks = body(response);
+    Box::pin(futures::stream::unfold(
+        (chunks, EventData::default(), false),
+        move |(mut chunks, mut events, mut ended)| {
+            let options = options.clone();
+            async move {
+                loop {
+                    if let Some(event) = events.next() {
+                        if event.data == b"[DONE]" {
+                            return None;
+                        }
+                        let item = compatibility.deserialize_slice(event.data);
+                        if last_event.map(str::as_bytes) == Some(event.name) {
+                            ended = true;
+                            events = EventData::default();
+                        }
+                        return Some((item, (chunks, events, ended)));
+                    }
+                    if ended {
+                        return None;
+                    }
+
+                    match chunks.next().await {
+                        Some(Ok(chunk)) => events.push(&chunk),
+                        None => ended = true,
+                        Some(Err(e)) => {
+                            ended = true;
+                            let error = match options.remaining() {
+                                Err(deadline_exceeded) => deadline_exceeded,
+                                Ok(_) => OpenAIError::StreamError(e.to_string()),
+                            };
+                            return Some((Err(error), (chunks, events, ended)));
+                        }
+                    }
+                }
+            }
+        },
+    ))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn body(
+    response: reqwest::Response,
+) -> impl Stream<Item = Result<Bytes, reqwest::Error>> + Send + Unpin {
+    response.bytes_stream().boxed()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn body(response: reqwest::Response) -> impl Stream<Item = Result<Bytes, reqwest::Error>> + Unpin {
+    response.bytes_stream().boxed_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, EventData};
+
+    #[test]
+    fn test_event_data() {
+        let mut events = EventData::default();
+        let event = |name: &'static str, data: &'static str| {
+            Some(Event {
+                name: name.as_bytes(),
+                data: data.as_bytes(),
+            })
+        };
+        events.push(b"data: {\"a\":1}\n\nda");
+        events.push(b"ta: [DONE]\r\n");
+        assert_eq!(events.next(), event("message", "{\"a\":1}"));
+        assert_eq!(events.next(), None);
+
+        events.push(b"\r\n");
+        assert_eq!(events.next(), event("message", "[DONE]"));
+
+        events.push(b"event: done\ndata: {}\n\ndata: {}\n\n");
+        assert_eq!(events.next(), event("done", "{}"));
+        assert_eq!(events.next(), event("message", "{}"));
+        assert_eq!(events.next(), None);
+
+        events.push(b"event: ignored\n\ndata: a\ndata:\ndata: b\n\n");
+        assert_eq!(events.next(), event("message", "a\n\nb"));
+        assert_eq!(events.next(), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! Prompts with named variables, filled in before making requests.
+use std::{collections::HashMap, fmt::Display};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{error::OpenAIError, types::Prompt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+/// Prompt text with `{{variable}}` placeholders. Variables are bound one at a time with
+/// [PromptTemplate::with], which leaves the template partially applied, or from the fields of
+/// a struct with [PromptTemplate::bind]. Rendering fails while any variable is unbound.
+///
+/// ```
+/// use async_openai::{types::CreateCompletionRequestArgs, PromptTemplate};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Summary<'a> {
+///     doc: &'a str,
+///     lang: &'a str,
+/// }
+///
+/// let template = PromptTemplate::new("Summarize {{doc}} in {{lang}}");
+/// let in_french = template.clone().with("lang", "French");
+///
+/// let prompt = template
+///     .bind(&Summary { doc: "the report", lang: "English" })
+///     .unwrap()
+///     .render()
+///     .unwrap();
+/// assert_eq!(prompt, "Summarize the report in English");
+///
+/// let request = CreateCompletionRequestArgs::default()
+///     .model("text-davinci-003")
+///     .prompt(in_french.with("doc", "the report").to_prompt().unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    parts: Vec<Part>,
+    values: HashMap<String, String>,
+}
+
+impl PromptTemplate {
+    /// Parse `template`. Whitespace around variable names is ignored, and a `{{` without a
+    /// closing `}}` is kept as text.
+    pub fn new(template: &str) -> Self {
+        let mut parts = vec![];
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let name = rest[start + 2..start + 2 + len].trim();
+            parts.push(Part::Variable(name.to_string()));
+            rest = &rest[start + 2 + len + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Self {
+            parts,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Names of the variables of the template, in order of first use.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for part in &self.parts {
+            if let Part::Variable(name) = part {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Names of the variables which are not bound yet.
+    pub fn unbound(&self) -> Vec<&str> {
+        self.variables()
+            .into_iter()
+            .filter(|name| !self.values.contains_key(*name))
+            .collect()
+    }
+
+    /// Bind variable `name` to `value`, replacing any previous value.
+    pub fn with<V: Display>(mut self, name: &str, value: V) -> Self {
+        self.values.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Bind variables to the fields of `values`, which must serialize to a map such as a
+    /// struct. Strings are inserted as is and other values as JSON.
+    pub fn bind<T: Serialize>(mut self, values: &T) -> Result<Self, OpenAIError> {
+        let Value::Object(values) =
+            serde_json::to_value(values).map_err(OpenAIError::JSONSerialize)?
+        else {
+            return Err(OpenAIError::InvalidArgument(
+                "template values must serialize to a map".into(),
+            ));
+        };
+        for (name, value) in values {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            self.values.insert(name, value);
+        }
+        Ok(self)
+    }
+
+    /// The prompt text with all variables filled in.
+    pub fn render(&self) -> Result<String, OpenAIError> {
+        let unbound = self.unbound();
+        if !unbound.is_empty() {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "unbound template variables: {}",
+                unbound.join(", ")
+            )));
+        }
+
+        let mut text = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(part) => text.push_str(part),
+                Part::Variable(name) => text.push_str(&self.values[name]),
+            }
+        }
+        Ok(text)
+    }
+
+    /// The rendered prompt of a completion request.
+    pub fn to_prompt(&self) -> Result<Prompt, OpenAIError> {
+        self.render().map(Prompt::String)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PromptTemplate;
+
+    #[test]
+    fn test_prompt_template() {
+        let template =
+            PromptTemplate::new("{{ greeting }}, {{name}}! {{name}} has {{n}} {{ unclosed");
+        assert_eq!(template.variables(), ["greeting", "name", "n"]);
+
+        let partial = template.with("greeting", "Hello").with("n", 3);
+        assert_eq!(partial.unbound(), ["name"]);
+        assert!(partial.render().is_err());
+
+        let rendered = partial
+            .bind(&serde_json::json!({"name": "Ada", "unused": true}))
+            .unwrap()
+            .render()
+            .unwrap();
+        assert_eq!(rendered, "Hello, Ada! Ada has 3 {{ unclosed");
+
+        assert!(PromptTemplate::new("").bind(&"not a map").is_err());
+    }
+}
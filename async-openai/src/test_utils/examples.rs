@@ -0,0 +1,35 @@
+//! Example payloads from the [OpenAPI spec](https://github.com/openai/openai-openapi), one per request and response type.
+//!
+//! The files are generated from the `x-oaiMeta` examples of the spec by `examples/generate.py`,
+//! which only cleans up what is not valid JSON; run it again rather than editing them by hand.
+
+pub const CANCEL_FINE_TUNE_RESPONSE: &str = include_str!("examples/cancel_fine_tune_response.json");
+pub const CREATE_COMPLETION_REQUEST: &str = include_str!("examples/create_completion_request.json");
+pub const CREATE_COMPLETION_RESPONSE: &str =
+    include_str!("examples/create_completion_response.json");
+pub const CREATE_EDIT_REQUEST: &str = include_str!("examples/create_edit_request.json");
+pub const CREATE_EDIT_RESPONSE: &str = include_str!("examples/create_edit_response.json");
+pub const CREATE_EMBEDDING_REQUEST: &str = include_str!("examples/create_embedding_request.json");
+pub const CREATE_EMBEDDING_RESPONSE: &str = include_str!("examples/create_embedding_response.json");
+pub const CREATE_FILE_RESPONSE: &str = include_str!("examples/create_file_response.json");
+pub const CREATE_FINE_TUNE_RESPONSE: &str = include_str!("examples/create_fine_tune_response.json");
+pub const CREATE_IMAGE_EDIT_RESPONSE: &str =
+    include_str!("examples/create_image_edit_response.json");
+pub const CREATE_IMAGE_REQUEST: &str = include_str!("examples/create_image_request.json");
+pub const CREATE_IMAGE_RESPONSE: &str = include_str!("examples/create_image_response.json");
+pub const CREATE_IMAGE_VARIATION_RESPONSE: &str =
+    include_str!("examples/create_image_variation_response.json");
+pub const CREATE_MODERATION_REQUEST: &str = include_str!("examples/create_moderation_request.json");
+pub const CREATE_MODERATION_RESPONSE: &str =
+    include_str!("examples/create_moderation_response.json");
+pub const DELETE_FILE_RESPONSE: &str = include_str!("examples/delete_file_response.json");
+pub const DELETE_MODEL_RESPONSE: &str = include_str!("examples/delete_model_response.json");
+pub const LIST_FILES_RESPONSE: &str = include_str!("examples/list_files_response.json");
+pub const LIST_FINE_TUNE_EVENTS_RESPONSE: &str =
+    include_str!("examples/list_fine_tune_events_response.json");
+pub const LIST_FINE_TUNES_RESPONSE: &str = include_str!("examples/list_fine_tunes_response.json");
+pub const LIST_MODELS_RESPONSE: &str = include_str!("examples/list_models_response.json");
+pub const RETRIEVE_FILE_RESPONSE: &str = include_str!("examples/retrieve_file_response.json");
+pub const RETRIEVE_FINE_TUNE_RESPONSE: &str =
+    include_str!("examples/retrieve_fine_tune_response.json");
+pub const RETRIEVE_MODEL_RESPONSE: &str = include_str!("examples/retrieve_model_response.json");
@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+"""Regenerate the example payloads in this directory from the OpenAPI spec.
+
+    python3 async-openai/src/test_utils/examples/generate.py [openapi.yaml]
+
+Writes `<operation>_request.json` from the `parameters` and `<operation>_response.json` from
+the `response` of the `x-oaiMeta` of every operation that is not deprecated. The examples in the
+spec are not always valid JSON, so they are cleaned up mechanically: trailing commas are removed,
+`...` array items are dropped, and a field whose value is `...` gets the value of the same field in
+another example, or is dropped if no example has one. Fields the types require that the spec
+leaves out are listed in `REQUIRED`.
+"""
+
+import json
+import re
+import sys
+from pathlib import Path
+
+import yaml
+
+HERE = Path(__file__).resolve().parent
+SPEC = HERE.parents[3] / "openapi.yaml"
+
+# A line that is only a placeholder, such as `.... (1024 floats total for ada)`
+PLACEHOLDER_LINE = re.compile(r"^\s*\.{3,}.*\n", re.MULTILINE)
+# The value of a field that is a placeholder: `"hyperparams": { ... },`, `"permission": [...]`
+PLACEHOLDER_VALUE = re.compile(r'(":\s*)(\{\s*\.{3}\s*\}|\[\s*(\{\s*\.{3}\s*\}|\.{3})\s*\])')
+# An array item that is a placeholder: `{ ... },`
+PLACEHOLDER_ITEM = re.compile(r"^\s*\{\s*\.{3}\s*\},?\s*\n", re.MULTILINE)
+PLACEHOLDER = "..."
+TRAILING_COMMA = re.compile(r",(\s*[}\]])")
+
+# Fields the types require that the examples leave out, by file, with the values the API returns.
+# Fields under a `name[]` key are added to every item of the array `name`.
+REQUIRED = {
+    "create_embedding_response.json": {"model": "text-similarity-babbage-001"},
+    "list_models_response.json": {"data[]": {"created": 1686935002}},
+    "retrieve_model_response.json": {"created": 1686935002},
+}
+
+
+def snake_case(name):
+    return re.sub(r"(?<!^)([A-Z])", r"_\1", name).lower()
+
+
+def parse(example):
+    """Parse an example, with placeholder values replaced by `PLACEHOLDER`."""
+    example = PLACEHOLDER_LINE.sub("", example)
+    example = PLACEHOLDER_ITEM.sub("", example)
+    example = PLACEHOLDER_VALUE.sub(rf'\1"{PLACEHOLDER}"', example)
+    return json.loads(TRAILING_COMMA.sub(r"\1", example))
+
+
+def collect(value, values):
+    """Record the first complete value of every field in `value`."""
+    if isinstance(value, dict):
+        for key, field in value.items():
+            if field != PLACEHOLDER:
+                values.setdefault(key, field)
+            collect(field, values)
+    elif isinstance(value, list):
+        for item in value:
+            collect(item, values)
+
+
+def fill(value, values):
+    """Replace placeholders with the value of the same field in another example, or drop them."""
+    if isinstance(value, dict):
+        for key in list(value):
+            if value[key] != PLACEHOLDER:
+                fill(value[key], values)
+            elif key in values:
+                value[key] = values[key]
+            else:
+                del value[key]
+    elif isinstance(value, list):
+        for item in value:
+            fill(item, values)
+
+
+def add(example, fields):
+    for key, field in fields.items():
+        if key.endswith("[]"):
+            for item in example[key[:-2]]:
+                add(item, field)
+        else:
+            example.setdefault(key, field)
+
+
+def main():
+    spec = yaml.safe_load(Path(sys.argv[1] if len(sys.argv) > 1 else SPEC).read_text())
+    examples = {}
+    for operations in spec["paths"].values():
+        for operation in operations.values():
+            meta = operation.get("x-oaiMeta")
+            if not meta or operation.get("deprecated"):
+                continue
+            name = snake_case(operation["operationId"])
+            for key, suffix in (("parameters", "request"), ("response", "response")):
+                if key in meta:
+                    examples[f"{name}_{suffix}.json"] = parse(meta[key])
+
+    values = {}
+    collect(list(examples.values()), values)
+    for name, example in examples.items():
+        fill(example, values)
+        add(example, REQUIRED.get(name, {}))
+        (HERE / name).write_text(json.dumps(example, indent=2, ensure_ascii=False) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,114 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Deserialize `json` into `T`, serialize it back and panic if any field was lost or changed on the way.
+///
+/// Fields with `null` values are ignored on both sides, as optional fields are skipped when serializing requests.
+/// Numbers are compared with a relative tolerance to account for `f32` fields.
+///
+/// ```
+/// use async_openai::{test_utils::{assert_round_trip, examples}, types::CreateCompletionResponse};
+///
+/// assert_round_trip::<CreateCompletionResponse>(examples::CREATE_COMPLETION_RESPONSE);
+/// ```
+pub fn assert_round_trip<T>(json: &str)
+where
+    T: Serialize + DeserializeOwned,
+{
+    let type_name = std::any::type_name::<T>();
+
+    let expected: Value = serde_json::from_str(json)
+        .unwrap_or_else(|e| panic!("example for {type_name} is not valid JSON: {e}"));
+    let typed: T = serde_json::from_str(json)
+        .unwrap_or_else(|e| panic!("failed to deserialize {type_name}: {e}"));
+    let actual: Value = serde_json::to_string(&typed)
+        .and_then(|serialized| serde_json::from_str(&serialized))
+        .unwrap_or_else(|e| panic!("failed to serialize {type_name}: {e}"));
+
+    let mut differences = vec![];
+    diff("", &expected, &actual, &mut differences);
+
+    if !differences.is_empty() {
+        panic!(
+            "{type_name} does not round trip:\n{}",
+            differences.join("\n")
+        );
+    }
+}
+
+fn diff(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected.iter().filter(|(_, value)| !value.is_null()) {
+                match actual.get(key) {
+                    Some(actual_value) => {
+                        diff(&format!("{path}/{key}"), value, actual_value, differences)
+                    }
+                    None => differences.push(format!("{path}/{key}: missing from type")),
+                }
+            }
+            for (key, _) in actual.iter().filter(|(_, value)| !value.is_null()) {
+                if !expected.contains_key(key) {
+                    differences.push(format!("{path}/{key}: missing from example"));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (index, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+                diff(&format!("{path}/{index}"), expected, actual, differences);
+            }
+        }
+        (Value::Number(expected), Value::Number(actual)) => {
+            let (expected, actual) = (
+                expected.as_f64().unwrap_or_default(),
+                actual.as_f64().unwrap_or_default(),
+            );
+            if (expected - actual).abs() > 1e-6 * expected.abs().max(1.0) {
+                differences.push(format!("{path}: expected {expected}, got {actual}"));
+            }
+        }
+        (expected, actual) => {
+            if expected != actual {
+                differences.push(format!("{path}: expected {expected}, got {actual}"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_round_trip;
+    use crate::{test_utils::examples, types::*};
+
+    #[test]
+    fn test_request_examples() {
+        assert_round_trip::<CreateCompletionRequest>(examples::CREATE_COMPLETION_REQUEST);
+        assert_round_trip::<CreateEditRequest>(examples::CREATE_EDIT_REQUEST);
+        assert_round_trip::<CreateImageRequest>(examples::CREATE_IMAGE_REQUEST);
+        assert_round_trip::<CreateEmbeddingRequest>(examples::CREATE_EMBEDDING_REQUEST);
+        assert_round_trip::<CreateModerationRequest>(examples::CREATE_MODERATION_REQUEST);
+    }
+
+    #[test]
+    fn test_response_examples() {
+        assert_round_trip::<CreateCompletionResponse>(examples::CREATE_COMPLETION_RESPONSE);
+        assert_round_trip::<CreateEditResponse>(examples::CREATE_EDIT_RESPONSE);
+        assert_round_trip::<ImageResponse>(examples::CREATE_IMAGE_RESPONSE);
+        assert_round_trip::<ImageResponse>(examples::CREATE_IMAGE_EDIT_RESPONSE);
+        assert_round_trip::<ImageResponse>(examples::CREATE_IMAGE_VARIATION_RESPONSE);
+        assert_round_trip::<CreateEmbeddingResponse>(examples::CREATE_EMBEDDING_RESPONSE);
+        assert_round_trip::<ListFilesResponse>(examples::LIST_FILES_RESPONSE);
+        assert_round_trip::<OpenAIFile>(examples::CREATE_FILE_RESPONSE);
+        assert_round_trip::<OpenAIFile>(examples::RETRIEVE_FILE_RESPONSE);
+        assert_round_trip::<DeleteFileResponse>(examples::DELETE_FILE_RESPONSE);
+        assert_round_trip::<FineTune>(examples::CREATE_FINE_TUNE_RESPONSE);
+        assert_round_trip::<ListFineTuneResponse>(examples::LIST_FINE_TUNES_RESPONSE);
+        assert_round_trip::<FineTune>(examples::RETRIEVE_FINE_TUNE_RESPONSE);
+        assert_round_trip::<FineTune>(examples::CANCEL_FINE_TUNE_RESPONSE);
+        assert_round_trip::<ListFineTuneEventsResponse>(examples::LIST_FINE_TUNE_EVENTS_RESPONSE);
+        assert_round_trip::<ListModelResponse>(examples::LIST_MODELS_RESPONSE);
+        assert_round_trip::<Model>(examples::RETRIEVE_MODEL_RESPONSE);
+        assert_round_trip::<DeleteModelResponse>(examples::DELETE_MODEL_RESPONSE);
+        assert_round_trip::<CreateModerationResponse>(examples::CREATE_MODERATION_RESPONSE);
+    }
+}
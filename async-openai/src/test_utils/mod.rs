@@ -0,0 +1,9 @@
+//! Utilities to test code consuming this library without making API calls.
+//!
+//! Enabled with the `test-utils` feature.
+pub mod examples;
+mod golden;
+mod stream;
+
+pub use golden::assert_round_trip;
+pub use stream::SimulatedStream;
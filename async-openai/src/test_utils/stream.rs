@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::types::{Choice, CompletionResponseStream, CreateCompletionResponse};
+
+/// Turns a complete response into a stream of chunks the way the API streams them back,
+/// so that streaming consumers (typewriter effects, aggregators) can be tested offline.
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// use async_openai::{test_utils::SimulatedStream, types::CreateCompletionResponse};
+/// use futures::StreamExt;
+///
+/// # let response: CreateCompletionResponse = serde_json::from_str(r#"{"id": "cmpl-1", "object": "text_completion", "created": 0, "model": "text-davinci-003", "choices": [{"text": "Hello world", "index": 0, "logprobs": null, "finish_reason": "stop"}]}"#).unwrap();
+/// let mut stream = SimulatedStream::new().with_chunk_size(3).completion(response);
+///
+/// while let Some(response) = stream.next().await {
+///     print!("{}", response.unwrap().choices[0].text);
+/// }
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimulatedStream {
+    chunk_size: usize,
+    delay: Duration,
+}
+
+impl Default for SimulatedStream {
+    /// Four characters per chunk and no delay between chunks
+    fn default() -> Self {
+        Self {
+            chunk_size: 4,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+impl SimulatedStream {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Number of characters of text in each chunk. Zero is treated as one.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Time to wait before yielding each chunk
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Split the text of each choice into chunks. When there are multiple choices their chunks
+    /// are interleaved. `finish_reason` is only set on the last chunk of each choice, and
+    /// `logprobs` and `usage` are omitted as they are when streaming.
+    pub fn completion(&self, response: CreateCompletionResponse) -> CompletionResponseStream {
+        let CreateCompletionResponse {
+            id,
+            object,
+            created,
+            model,
+            system_fingerprint,
+            choices,
+            ..
+        } = response;
+
+        let choices: Vec<(u32, Vec<String>, Option<String>)> = choices
+            .into_iter()
+            .map(|choice| {
+                (
+                    choice.index,
+                    self.split(&choice.text),
+                    choice.finish_reason,
+                )
+            })
+            .collect();
+
+        let rounds = choices
+            .iter()
+            .map(|(_, pieces, _)| pieces.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut chunks = vec![];
+        for round in 0..rounds {
+            for (index, pieces, finish_reason) in choices.iter() {
+                if let Some(text) = pieces.get(round) {
+                    let is_last = round + 1 == pieces.len();
+                    chunks.push(CreateCompletionResponse {
+                        id: id.clone(),
+                        object: object.clone(),
+                        created,
+                        model: model.clone(),
+                        system_fingerprint: system_fingerprint.clone(),
+                        choices: vec![Choice {
+                            text: text.clone(),
+                            index: *index,
+                            logprobs: None,
+                            finish_reason: if is_last { finish_reason.clone() } else { None },
+                        }],
+                        usage: None,
+                    });
+                }
+            }
+        }
+
+        let delay = self.delay;
+        Box::pin(futures::stream::iter(chunks).then(move |chunk| async move {
+            if !delay.is_zero() {
+                crate::runtime::sleep(delay).await;
+            }
+            Ok(chunk)
+        }))
+    }
+
+    fn split(&self, text: &str) -> Vec<String> {
+        if text.is_empty() {
+            // Still yield a chunk so that finish_reason is delivered
+            return vec![String::new()];
+        }
+
+        text.chars()
+            .collect::<Vec<char>>()
+            .chunks(self.chunk_size)
+            .map(|chunk| chunk.iter().collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::SimulatedStream;
+    use crate::types::CreateCompletionResponse;
+
+    #[tokio::test]
+    async fn test_simulated_completion_stream() {
+        let response: CreateCompletionResponse = serde_json::from_str(
+            r#"{
+                "id": "cmpl-1",
+                "object": "text_completion",
+                "created": 1,
+                "model": "text-davinci-003",
+                "choices": [
+                    {"text": "Hello, wörld!", "index": 0, "logprobs": null, "finish_reason": "stop"},
+                    {"text": "Hi", "index": 1, "logprobs": null, "finish_reason": "length"}
+                ],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 2, "total_tokens": 3}
+            }"#,
+        )
+        .unwrap();
+
+        let chunks: Vec<CreateCompletionResponse> = SimulatedStream::new()
+            .with_chunk_size(5)
+            .completion(response)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.usage.is_none()));
+
+        let first: Vec<_> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.choices.iter())
+            .filter(|choice| choice.index == 0)
+            .collect();
+        let text: String = first.iter().map(|choice| choice.text.as_str()).collect();
+        assert_eq!(text, "Hello, wörld!");
+        assert_eq!(first.last().unwrap().finish_reason.as_deref(), Some("stop"));
+        assert!(first[..2].iter().all(|choice| choice.finish_reason.is_none()));
+    }
+}
@@ -0,0 +1,192 @@
+//! Building fine-tuning training files which pass the API's format checks.
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use crate::{error::OpenAIError, splitter::TokenEstimator, types::TrainingExample};
+#[cfg(feature = "files")]
+use crate::{
+    types::{CreateFileRequest, FileInput, OpenAIFile},
+    Client,
+};
+
+/// Collects prompt/completion pairs and writes them as a JSONL training file for
+/// [FineTunes::create](crate::FineTunes::create), checking the format rules which the API
+/// otherwise only reports once the fine-tune fails:
+///
+/// - prompts and completions are not empty
+/// - each example is within the token limit, 4096 by default
+/// - there are at least 10 examples
+///
+/// Tokens are estimated at four characters per token by default, or counted by a real
+/// tokenizer set with [TrainingFileBuilder::with_token_estimator].
+///
+/// ```
+/// use async_openai::TrainingFileBuilder;
+///
+/// let mut builder = TrainingFileBuilder::new().with_min_examples(1);
+/// builder.push(("What is the capital of France? ->", " Paris"));
+/// assert_eq!(
+///     builder.to_jsonl().unwrap(),
+///     "{\"prompt\":\"What is the capital of France? ->\",\"completion\":\" Paris\"}\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrainingFileBuilder {
+    examples: Vec<TrainingExample>,
+    min_examples: usize,
+    max_tokens: usize,
+    token_estimator: TokenEstimator,
+}
+
+impl Default for TrainingFileBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrainingFileBuilder {
+    pub fn new() -> Self {
+        Self {
+            examples: vec![],
+            min_examples: 10,
+            max_tokens: 4096,
+            token_estimator: TokenEstimator::default(),
+        }
+    }
+
+    /// Require at least `min_examples` examples, 10 by default as for the API.
+    pub fn with_min_examples(mut self, min_examples: usize) -> Self {
+        self.min_examples = min_examples;
+        self
+    }
+
+    /// Limit each example to `max_tokens` tokens, the context length of the model to fine-tune.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Function counting the tokens of a text, see [TrainingFileBuilder].
+    pub fn with_token_estimator<F>(mut self, estimator: F) -> Self
+    where
+        F: Fn(&str) -> usize + Send + Sync + 'static,
+    {
+        self.token_estimator = TokenEstimator::new(estimator);
+        self
+    }
+
+    /// Add `examples`, such as `(prompt, completion)` pairs.
+    pub fn with_examples<E: Into<TrainingExample>>(
+        mut self,
+        examples: impl IntoIterator<Item = E>,
+    ) -> Self {
+        self.examples.extend(examples.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add an example, such as a `(prompt, completion)` pair.
+    pub fn push(&mut self, example: impl Into<TrainingExample>) {
+        self.examples.push(example.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// Check the examples against the rules of [TrainingFileBuilder].
+    pub fn validate(&self) -> Result<(), OpenAIError> {
+        if self.examples.len() < self.min_examples {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "a training file needs at least {} examples, got {}",
+                self.min_examples,
+                self.examples.len()
+            )));
+        }
+
+        for (index, example) in self.examples.iter().enumerate() {
+            let invalid = |message: String| {
+                Err(OpenAIError::InvalidArgument(format!(
+                    "training example {index}: {message}"
+                )))
+            };
+            if example.prompt.is_empty() || example.completion.is_empty() {
+                return invalid("prompt and completion must not be empty".into());
+            }
+
+            let tokens = self.token_estimator.estimate(&example.prompt)
+                + self.token_estimator.estimate(&example.completion);
+            if tokens > self.max_tokens {
+                return invalid(format!(
+                    "{tokens} tokens is over the limit of {}",
+                    self.max_tokens
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The examples as JSON Lines, once they pass [TrainingFileBuilder::validate].
+    pub fn to_jsonl(&self) -> Result<String, OpenAIError> {
+        self.validate()?;
+        let mut jsonl = String::new();
+        for example in &self.examples {
+            jsonl.push_str(&serde_json::to_string(example).map_err(OpenAIError::JSONSerialize)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+
+    /// Write the examples as JSON Lines to `path`, see [TrainingFileBuilder::to_jsonl].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), OpenAIError> {
+        let jsonl = self.to_jsonl()?;
+        crate::runtime::fs::write(path.as_ref(), jsonl.as_bytes())
+            .await
+            .map_err(|e| OpenAIError::FileSaveError(e.to_string()))
+    }
+
+    /// Upload the examples as a `fine-tune` file named `filename`, whose ID is the
+    /// `training_file` of a fine-tuning job.
+    #[cfg(feature = "files")]
+    pub async fn upload(&self, client: &Client, filename: &str) -> Result<OpenAIFile, OpenAIError> {
+        let jsonl = self.to_jsonl()?;
+        client
+            .files()
+            .create(CreateFileRequest {
+                file: FileInput::from_bytes(filename, jsonl),
+                purpose: "fine-tune".into(),
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrainingFileBuilder;
+
+    #[test]
+    fn test_training_file_rules() {
+        let pairs = (0..10).map(|i| (format!("{i} + {i} ="), format!(" {}", i + i)));
+        let builder = TrainingFileBuilder::new().with_examples(pairs);
+        let jsonl = builder.to_jsonl().unwrap();
+        assert_eq!(jsonl.lines().count(), 10);
+        assert_eq!(
+            jsonl.lines().next().unwrap(),
+            r#"{"prompt":"0 + 0 =","completion":" 0"}"#
+        );
+
+        // Too few, empty, and too long
+        assert!(TrainingFileBuilder::new()
+            .with_examples([("1 + 1 =", " 2")])
+            .validate()
+            .is_err());
+        let mut empty = builder.clone();
+        empty.push(("1 + 1 =", ""));
+        assert!(empty.validate().is_err());
+        assert!(builder.clone().with_max_tokens(2).validate().is_err());
+    }
+}
@@ -3,15 +3,28 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use bytes::Bytes;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
 use crate::{
     download::{download_url, save_b64},
-    error::OpenAIError,
+    runtime,
 };
+use crate::{error::OpenAIError, util};
 
 use super::{
-    EmbeddingInput, FileInput, ImageData, ImageInput, ImageResponse, ImageSize, ModerationInput,
-    Prompt, ResponseFormat, Stop,
+    Category, CategoryScore, ContentModerationResult, CreateEmbeddingResponse,
+    CreateFineTuneRequest, CreateImageRequest, CreateModerationResponse, Embedding, EmbeddingInput,
+    FileInput, FineTuneMetrics, Hyperparameter, ImageBackground, ImageInput, ImageModel,
+    ImageModeration, ImageOutputFormat, ImageQuality, ImageSize, InputSource, ModerationCategory,
+    ModerationInput, ModerationThresholds, Prompt, ResponseFormat, Stop, TrainingExample,
 };
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
+use super::{ImageData, ImageResponse};
 
 macro_rules! impl_from {
     ($from_typ:ty, $to_typ:ty) => {
@@ -67,28 +80,170 @@ impl_from!(&str, EmbeddingInput);
 impl_from!(String, EmbeddingInput);
 impl_from!(&String, EmbeddingInput);
 
-macro_rules! file_path_input {
+impl Default for InputSource {
+    fn default() -> Self {
+        InputSource::Path {
+            path: PathBuf::new(),
+        }
+    }
+}
+
+macro_rules! file_input {
     ($for_typ:ty) => {
         impl $for_typ {
             pub fn new<P: AsRef<Path>>(path: P) -> Self {
                 Self {
-                    path: PathBuf::from(path.as_ref()),
+                    source: InputSource::Path {
+                        path: PathBuf::from(path.as_ref()),
+                    },
+                }
+            }
+
+            /// Upload `bytes` from memory under the given file name.
+            pub fn from_bytes(filename: impl Into<String>, bytes: impl Into<Bytes>) -> Self {
+                Self {
+                    source: InputSource::Bytes {
+                        filename: filename.into(),
+                        bytes: bytes.into(),
+                    },
                 }
             }
         }
 
         impl<P: AsRef<Path>> From<P> for $for_typ {
             fn from(path: P) -> Self {
-                Self {
-                    path: PathBuf::from(path.as_ref()),
-                }
+                Self::new(path)
             }
         }
     };
 }
 
-file_path_input!(ImageInput);
-file_path_input!(FileInput);
+file_input!(ImageInput);
+file_input!(FileInput);
+
+impl<P: Into<String>, C: Into<String>> From<(P, C)> for TrainingExample {
+    fn from((prompt, completion): (P, C)) -> Self {
+        TrainingExample {
+            prompt: prompt.into(),
+            completion: completion.into(),
+        }
+    }
+}
+
+impl FineTuneMetrics {
+    /// Parse the CSV of a fine-tune result file, with the column names of the legacy
+    /// fine-tunes (`training_loss`, `validation_token_accuracy`, ...) or of fine-tuning jobs
+    /// (`train_loss`, `valid_mean_token_accuracy`, ...). Other columns are ignored.
+    pub fn parse_csv(csv: &str) -> Result<Vec<FineTuneMetrics>, OpenAIError> {
+        let invalid = OpenAIError::CSVDeserialize;
+        let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+        let header: Vec<&str> = lines.next().unwrap_or_default().split(',').collect();
+        let column = |names: &[&str]| {
+            header
+                .iter()
+                .position(|column| names.contains(&column.trim()))
+        };
+        let step = column(&["step"]).ok_or_else(|| invalid("has no step column".into()))?;
+        let train_loss = column(&["train_loss", "training_loss"]);
+        let train_accuracy = column(&["train_accuracy", "training_sequence_accuracy"]);
+        let train_token_accuracy =
+            column(&["train_mean_token_accuracy", "training_token_accuracy"]);
+        let valid_loss = column(&["valid_loss", "validation_loss"]);
+        let valid_accuracy = column(&["valid_accuracy", "validation_sequence_accuracy"]);
+        let valid_token_accuracy =
+            column(&["valid_mean_token_accuracy", "validation_token_accuracy"]);
+
+        lines
+            .enumerate()
+            .map(|(row, line)| {
+                let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+                if cells.len() != header.len() {
+                    return Err(invalid(format!(
+                        "row {} has {} cells, the header has {}",
+                        row + 1,
+                        cells.len(),
+                        header.len()
+                    )));
+                }
+                let value = |column: Option<usize>| -> Result<Option<f32>, OpenAIError> {
+                    match column.and_then(|column| cells.get(column)) {
+                        None | Some(&"") => Ok(None),
+                        Some(cell) => cell.parse().map(Some).map_err(|_| {
+                            invalid(format!("row {}: {cell:?} is not a number", row + 1))
+                        }),
+                    }
+                };
+                Ok(FineTuneMetrics {
+                    step: cells
+                        .get(step)
+                        .and_then(|cell| cell.parse().ok())
+                        .ok_or_else(|| invalid(format!("row {}: invalid step", row + 1)))?,
+                    train_loss: value(train_loss)?,
+                    train_accuracy: value(train_accuracy)?,
+                    train_token_accuracy: value(train_token_accuracy)?,
+                    valid_loss: value(valid_loss)?,
+                    valid_accuracy: value(valid_accuracy)?,
+                    valid_token_accuracy: value(valid_token_accuracy)?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl CreateFineTuneRequest {
+    /// Check that no hyperparameter is [Hyperparameter::Auto], which the legacy `/fine-tunes`
+    /// endpoint does not accept, before making the API call.
+    pub fn validate(&self) -> Result<(), OpenAIError> {
+        let auto = [
+            ("n_epochs", self.n_epochs == Some(Hyperparameter::Auto)),
+            ("batch_size", self.batch_size == Some(Hyperparameter::Auto)),
+            (
+                "learning_rate_multiplier",
+                self.learning_rate_multiplier == Some(Hyperparameter::Auto),
+            ),
+        ];
+        match auto.iter().find(|(_, auto)| *auto) {
+            Some((name, _)) => Err(OpenAIError::InvalidArgument(format!(
+                "{name} cannot be auto for legacy fine-tunes, leave it unset for the default"
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> From<T> for Hyperparameter<T> {
+    fn from(value: T) -> Self {
+        Hyperparameter::Value(value)
+    }
+}
+
+impl<T: Serialize> Serialize for Hyperparameter<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Hyperparameter::Auto => serializer.serialize_str("auto"),
+            Hyperparameter::Value(value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Hyperparameter<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw<T> {
+            Value(T),
+            Sentinel(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Value(value) => Ok(Hyperparameter::Value(value)),
+            Raw::Sentinel(sentinel) if sentinel == "auto" => Ok(Hyperparameter::Auto),
+            Raw::Sentinel(other) => Err(de::Error::custom(format!(
+                "expected \"auto\" or a number, got {other:?}"
+            ))),
+        }
+    }
+}
 
 impl Display for ImageSize {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -104,6 +259,197 @@ impl Display for ImageSize {
     }
 }
 
+impl Display for ImageModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImageModel::DallE2 => "dall-e-2",
+                ImageModel::DallE3 => "dall-e-3",
+                ImageModel::GptImage1 => "gpt-image-1",
+                ImageModel::Other(model) => model,
+            }
+        )
+    }
+}
+
+impl From<&str> for ImageModel {
+    fn from(model: &str) -> Self {
+        match model {
+            "dall-e-2" => ImageModel::DallE2,
+            "dall-e-3" => ImageModel::DallE3,
+            "gpt-image-1" => ImageModel::GptImage1,
+            other => ImageModel::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ImageModel {
+    fn from(model: String) -> Self {
+        model.as_str().into()
+    }
+}
+
+impl Serialize for ImageModel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageModel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(ImageModel::from)
+    }
+}
+
+impl Display for ImageOutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImageOutputFormat::Png => "png",
+                ImageOutputFormat::Webp => "webp",
+                ImageOutputFormat::Jpeg => "jpeg",
+            }
+        )
+    }
+}
+
+impl Display for ImageBackground {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImageBackground::Auto => "auto",
+                ImageBackground::Transparent => "transparent",
+                ImageBackground::Opaque => "opaque",
+            }
+        )
+    }
+}
+
+impl Display for ImageModeration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImageModeration::Auto => "auto",
+                ImageModeration::Low => "low",
+            }
+        )
+    }
+}
+
+impl Display for ImageQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImageQuality::Standard => "standard",
+                ImageQuality::Hd => "hd",
+                ImageQuality::Low => "low",
+                ImageQuality::Medium => "medium",
+                ImageQuality::High => "high",
+                ImageQuality::Auto => "auto",
+            }
+        )
+    }
+}
+
+impl CreateImageRequest {
+    /// Check the options against what the model supports, before making the API call.
+    pub fn validate(&self) -> Result<(), OpenAIError> {
+        let model = self.model.clone().unwrap_or_default();
+        let unsupported = |option: &str| {
+            Err(OpenAIError::InvalidArgument(format!(
+                "{option} is not supported by {model}"
+            )))
+        };
+
+        match model {
+            ImageModel::GptImage1 => {
+                if self.response_format.is_some() {
+                    return unsupported("response_format");
+                }
+            }
+            ImageModel::DallE2 | ImageModel::DallE3 => {
+                if self.output_format.is_some() {
+                    return unsupported("output_format");
+                }
+                if self.output_compression.is_some() {
+                    return unsupported("output_compression");
+                }
+                if self.stream == Some(true) {
+                    return unsupported("stream");
+                }
+                if self.partial_images.is_some() {
+                    return unsupported("partial_images");
+                }
+                if self.background.is_some() {
+                    return unsupported("background");
+                }
+                if self.moderation.is_some() {
+                    return unsupported("moderation");
+                }
+            }
+            // Left to the server
+            ImageModel::Other(_) => {}
+        }
+
+        if let Some(quality) = self.quality {
+            let supported = match model {
+                ImageModel::DallE2 => quality == ImageQuality::Standard,
+                ImageModel::DallE3 => matches!(quality, ImageQuality::Standard | ImageQuality::Hd),
+                ImageModel::GptImage1 => {
+                    !matches!(quality, ImageQuality::Standard | ImageQuality::Hd)
+                }
+                ImageModel::Other(_) => true,
+            };
+            if !supported {
+                return unsupported(&format!("quality {quality}"));
+            }
+        }
+
+        if self.background == Some(ImageBackground::Transparent)
+            && self.output_format == Some(ImageOutputFormat::Jpeg)
+        {
+            return Err(OpenAIError::InvalidArgument(
+                "a transparent background requires the png or webp output_format".into(),
+            ));
+        }
+
+        if let Some(partial_images) = self.partial_images {
+            if partial_images > 3 {
+                return Err(OpenAIError::InvalidArgument(format!(
+                    "partial_images must be between 0 and 3, got {partial_images}"
+                )));
+            }
+        }
+
+        if let Some(compression) = self.output_compression {
+            if compression > 100 {
+                return Err(OpenAIError::InvalidArgument(format!(
+                    "output_compression must be between 0 and 100, got {compression}"
+                )));
+            }
+            if !matches!(
+                self.output_format,
+                Some(ImageOutputFormat::Webp | ImageOutputFormat::Jpeg)
+            ) {
+                return Err(OpenAIError::InvalidArgument(
+                    "output_compression requires the webp or jpeg output_format".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Display for ResponseFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -117,9 +463,11 @@ impl Display for ResponseFormat {
     }
 }
 
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
 impl ImageResponse {
     /// Save each image in a dedicated Tokio task and return paths to saved files.
     /// For [ResponseFormat::Url] each file is downloaded in dedicated Tokio task.
+    /// Without the `tokio` feature images are saved concurrently within the calling task.
     pub async fn save<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>, OpenAIError> {
         let exists = match Path::try_exists(dir.as_ref()) {
             Ok(exists) => exists,
@@ -134,7 +482,7 @@ impl ImageResponse {
         let mut handles = vec![];
         for id in self.data.clone() {
             let dir_buf = PathBuf::from(dir.as_ref());
-            handles.push(tokio::spawn(async move { id.save(dir_buf).await }));
+            handles.push(runtime::spawn(async move { id.save(dir_buf).await }));
         }
 
         let results = futures::future::join_all(handles).await;
@@ -147,7 +495,7 @@ impl ImageResponse {
                     Ok(path) => paths.push(path),
                     Err(e) => errors.push(e),
                 },
-                Err(e) => errors.push(OpenAIError::FileSaveError(e.to_string())),
+                Err(e) => errors.push(OpenAIError::FileSaveError(e)),
             }
         }
 
@@ -165,6 +513,7 @@ impl ImageResponse {
     }
 }
 
+#[cfg(all(feature = "images", not(target_arch = "wasm32")))]
 impl ImageData {
     async fn save<P: AsRef<Path>>(&self, dir: P) -> Result<PathBuf, OpenAIError> {
         match self {
@@ -174,6 +523,211 @@ impl ImageData {
     }
 }
 
+impl CreateEmbeddingResponse {
+    /// The embeddings ordered by their `index`, checking that there is exactly one for each
+    /// of the `inputs` sent, however the response ordered them. Fails with
+    /// [OpenAIError::InvalidResponse] on missing, duplicate or out of range indices.
+    pub fn in_input_order(&self, inputs: usize) -> Result<Vec<&Embedding>, OpenAIError> {
+        let mut ordered: Vec<Option<&Embedding>> = vec![None; inputs];
+        for embedding in &self.data {
+            let index = embedding.index as usize;
+            match ordered.get_mut(index) {
+                Some(slot @ None) => *slot = Some(embedding),
+                Some(Some(_)) => {
+                    return Err(OpenAIError::InvalidResponse(format!(
+                        "more than one embedding in response for input {index}"
+                    )))
+                }
+                None => {
+                    return Err(OpenAIError::InvalidResponse(format!(
+                        "embedding in response for input {index} of only {inputs} inputs"
+                    )))
+                }
+            }
+        }
+        ordered
+            .into_iter()
+            .enumerate()
+            .map(|(index, embedding)| {
+                embedding.ok_or_else(|| {
+                    OpenAIError::InvalidResponse(format!(
+                        "no embedding in response for input {index}"
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Each of `inputs` paired with its embedding, see [CreateEmbeddingResponse::in_input_order].
+    pub fn zip_inputs<'a, T>(
+        &'a self,
+        inputs: &'a [T],
+    ) -> Result<Vec<(&'a T, &'a Embedding)>, OpenAIError> {
+        let embeddings = self.in_input_order(inputs.len())?;
+        Ok(inputs.iter().zip(embeddings).collect())
+    }
+}
+
+impl ModerationCategory {
+    /// Every category, in the order of the API reference.
+    pub const ALL: [ModerationCategory; 13] = [
+        ModerationCategory::Hate,
+        ModerationCategory::HateThreatening,
+        ModerationCategory::Harassment,
+        ModerationCategory::HarassmentThreatening,
+        ModerationCategory::SelfHarm,
+        ModerationCategory::SelfHarmIntent,
+        ModerationCategory::SelfHarmInstructions,
+        ModerationCategory::Sexual,
+        ModerationCategory::SexualMinors,
+        ModerationCategory::Violence,
+        ModerationCategory::ViolenceGraphic,
+        ModerationCategory::Illicit,
+        ModerationCategory::IllicitViolent,
+    ];
+
+    /// Name of the category in the API, such as `hate/threatening`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationCategory::Hate => "hate",
+            ModerationCategory::HateThreatening => "hate/threatening",
+            ModerationCategory::SelfHarm => "self-harm",
+            ModerationCategory::Sexual => "sexual",
+            ModerationCategory::SexualMinors => "sexual/minors",
+            ModerationCategory::Violence => "violence",
+            ModerationCategory::ViolenceGraphic => "violence/graphic",
+            ModerationCategory::Harassment => "harassment",
+            ModerationCategory::HarassmentThreatening => "harassment/threatening",
+            ModerationCategory::SelfHarmIntent => "self-harm/intent",
+            ModerationCategory::SelfHarmInstructions => "self-harm/instructions",
+            ModerationCategory::Illicit => "illicit",
+            ModerationCategory::IllicitViolent => "illicit/violent",
+        }
+    }
+}
+
+impl Display for ModerationCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Category {
+    /// Whether the API flagged `category`, false for categories the model does not return.
+    pub fn get(&self, category: ModerationCategory) -> bool {
+        self.try_get(category).unwrap_or(false)
+    }
+
+    /// Whether the API flagged `category`, or `None` when the model does not return it.
+    pub fn try_get(&self, category: ModerationCategory) -> Option<bool> {
+        match category {
+            ModerationCategory::Hate => Some(self.hate),
+            ModerationCategory::HateThreatening => Some(self.hate_threatening),
+            ModerationCategory::SelfHarm => Some(self.self_harm),
+            ModerationCategory::Sexual => Some(self.sexual),
+            ModerationCategory::SexualMinors => Some(self.sexual_minors),
+            ModerationCategory::Violence => Some(self.violence),
+            ModerationCategory::ViolenceGraphic => Some(self.violence_graphic),
+            ModerationCategory::Harassment => self.harassment,
+            ModerationCategory::HarassmentThreatening => self.harassment_threatening,
+            ModerationCategory::SelfHarmIntent => self.self_harm_intent,
+            ModerationCategory::SelfHarmInstructions => self.self_harm_instructions,
+            ModerationCategory::Illicit => self.illicit,
+            ModerationCategory::IllicitViolent => self.illicit_violent,
+        }
+    }
+}
+
+impl CategoryScore {
+    /// Score of `category` from 0 to 1, or 0 for categories the model does not return.
+    pub fn get(&self, category: ModerationCategory) -> f32 {
+        self.try_get(category).unwrap_or(0.0)
+    }
+
+    /// Score of `category` from 0 to 1, or `None` when the model does not return it.
+    pub fn try_get(&self, category: ModerationCategory) -> Option<f32> {
+        match category {
+            ModerationCategory::Hate => Some(self.hate),
+            ModerationCategory::HateThreatening => Some(self.hate_threatening),
+            ModerationCategory::SelfHarm => Some(self.self_harm),
+            ModerationCategory::Sexual => Some(self.sexual),
+            ModerationCategory::SexualMinors => Some(self.sexual_minors),
+            ModerationCategory::Violence => Some(self.violence),
+            ModerationCategory::ViolenceGraphic => Some(self.violence_graphic),
+            ModerationCategory::Harassment => self.harassment,
+            ModerationCategory::HarassmentThreatening => self.harassment_threatening,
+            ModerationCategory::SelfHarmIntent => self.self_harm_intent,
+            ModerationCategory::SelfHarmInstructions => self.self_harm_instructions,
+            ModerationCategory::Illicit => self.illicit,
+            ModerationCategory::IllicitViolent => self.illicit_violent,
+        }
+    }
+}
+
+impl ContentModerationResult {
+    /// Whether the API flagged the input for `category`.
+    pub fn is_flagged_for(&self, category: ModerationCategory) -> bool {
+        self.categories.get(category)
+    }
+
+    /// Categories flagged by `thresholds`, see [ModerationThresholds].
+    pub fn flagged_categories(&self, thresholds: &ModerationThresholds) -> Vec<ModerationCategory> {
+        self.iter()
+            .filter(
+                |(category, flagged, score)| match thresholds.thresholds.get(category) {
+                    Some(threshold) => score >= threshold,
+                    None => *flagged,
+                },
+            )
+            .map(|(category, _, _)| category)
+            .collect()
+    }
+
+    /// Each category returned by the model, with whether the API flagged it and its score.
+    ///
+    /// ```
+    /// # fn report(result: &async_openai::types::ContentModerationResult) {
+    /// for (category, flagged, score) in result.iter() {
+    ///     if flagged {
+    ///         println!("{category}: {score:.2}");
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (ModerationCategory, bool, f32)> + '_ {
+        ModerationCategory::ALL
+            .into_iter()
+            .filter_map(move |category| {
+                let flagged = self.categories.try_get(category)?;
+                let score = self.category_scores.try_get(category)?;
+                Some((category, flagged, score))
+            })
+    }
+
+    /// Whether any category is flagged by `thresholds`.
+    pub fn is_flagged_with(&self, thresholds: &ModerationThresholds) -> bool {
+        !self.flagged_categories(thresholds).is_empty()
+    }
+}
+
+impl CreateModerationResponse {
+    /// Each of `inputs` paired with its result. Results are in input order; fails when their
+    /// number differs from the number of inputs.
+    pub fn zip_inputs<'a, T>(
+        &'a self,
+        inputs: &'a [T],
+    ) -> Result<Vec<(&'a T, &'a ContentModerationResult)>, OpenAIError> {
+        if inputs.len() != self.results.len() {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{} moderation results for {} inputs",
+                self.results.len(),
+                inputs.len()
+            )));
+        }
+        Ok(inputs.iter().zip(&self.results).collect())
+    }
+}
+
 impl Default for ModerationInput {
     fn default() -> Self {
         ModerationInput::String("".to_owned())
@@ -313,3 +867,327 @@ macro_rules! impl_from_for_array_of_integer_array {
 
 impl_from_for_array_of_integer_array!(u32, EmbeddingInput);
 impl_from_for_array_of_integer_array!(u16, Prompt);
+
+/// Deserializes the vector of an [Embedding](super::Embedding) from an array of floats, or
+/// from the little-endian `f32`s of a response with `encoding_format` base64.
+pub(crate) fn deserialize_embedding<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<f32>, D::Error> {
+    struct EmbeddingVisitor;
+
+    impl<'de> Visitor<'de> for EmbeddingVisitor {
+        type Value = Vec<f32>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("an array of floats or a base64 string")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<f32>, A::Error> {
+            let mut embedding = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(value) = seq.next_element()? {
+                embedding.push(value);
+            }
+            Ok(embedding)
+        }
+
+        fn visit_str<E: de::Error>(self, encoded: &str) -> Result<Vec<f32>, E> {
+            let mut embedding = Vec::with_capacity(encoded.len() * 3 / 16);
+            util::decode_f32_le(encoded, &mut vec![], &mut embedding).map_err(E::custom)?;
+            Ok(embedding)
+        }
+    }
+
+    deserializer.deserialize_any(EmbeddingVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::OpenAIError;
+    use crate::types::{
+        ContentModerationResult, CreateEmbeddingResponse, CreateFineTuneRequestArgs,
+        CreateImageRequestArgs, CreateModerationResponse, FineTuneHyperparams, FineTuneMetrics,
+        Hyperparameter, ImageBackground, ImageModel, ImageModeration, ImageOutputFormat,
+        ImageQuality, ModerationCategory, ModerationThresholds, ResponseFormat,
+    };
+
+    fn response(indices: &[u32]) -> CreateEmbeddingResponse {
+        let data: Vec<_> = indices
+            .iter()
+            .map(|index| serde_json::json!({"object": "embedding", "embedding": [*index as f32], "index": index}))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "object": "list",
+            "model": "text-embedding-ada-002",
+            "data": data,
+            "usage": {"prompt_tokens": 0, "total_tokens": 0},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_zip_inputs() {
+        let inputs = ["a", "b", "c"];
+        let shuffled = response(&[2, 0, 1]);
+        let zipped = shuffled.zip_inputs(&inputs).unwrap();
+        let pairs: Vec<(&str, f32)> = zipped
+            .iter()
+            .map(|(input, embedding)| (**input, embedding.embedding[0]))
+            .collect();
+        assert_eq!(pairs, [("a", 0.0), ("b", 1.0), ("c", 2.0)]);
+
+        // Missing, duplicate and out of range indices
+        for indices in [&[0, 2][..], &[0, 1, 1], &[0, 1, 3]] {
+            assert!(matches!(
+                response(indices).zip_inputs(&inputs),
+                Err(OpenAIError::InvalidResponse(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_moderation_results() {
+        let scores = |violence: f32| {
+            serde_json::json!({
+                "hate": 0.0, "hate/threatening": 0.0, "self-harm": 0.0, "sexual": 0.0,
+                "sexual/minors": 0.0, "violence": violence, "violence/graphic": 0.0,
+            })
+        };
+        let flags = |violence: bool| {
+            serde_json::json!({
+                "hate": false, "hate/threatening": false, "self-harm": false, "sexual": false,
+                "sexual/minors": false, "violence": violence, "violence/graphic": false,
+            })
+        };
+        let response: CreateModerationResponse = serde_json::from_value(serde_json::json!({
+            "id": "modr-1",
+            "model": "text-moderation-005",
+            "results": [
+                {"flagged": true, "categories": flags(true), "category_scores": scores(0.9)},
+                {"flagged": false, "categories": flags(false), "category_scores": scores(0.3)},
+            ],
+        }))
+        .unwrap();
+
+        let inputs = ["kill it", "bake it"];
+        let zipped = response.zip_inputs(&inputs).unwrap();
+        assert_eq!(*zipped[0].0, "kill it");
+        assert!(zipped[0].1.is_flagged_for(ModerationCategory::Violence));
+        assert!(!zipped[0].1.is_flagged_for(ModerationCategory::Hate));
+        assert!(response.zip_inputs(&inputs[..1]).is_err());
+
+        let strict = ModerationThresholds::new().with_threshold(ModerationCategory::Violence, 0.2);
+        assert_eq!(
+            zipped[1].1.flagged_categories(&strict),
+            [ModerationCategory::Violence]
+        );
+        assert!(!zipped[1].1.is_flagged_with(&ModerationThresholds::new()));
+        assert_eq!(
+            ModerationCategory::ViolenceGraphic.to_string(),
+            "violence/graphic"
+        );
+
+        // Categories the model does not return are not iterated
+        assert_eq!(zipped[0].1.iter().count(), 7);
+        assert!(!zipped[0].1.is_flagged_for(ModerationCategory::Illicit));
+
+        let mut categories = flags(false);
+        categories["illicit"] = true.into();
+        categories["illicit/violent"] = false.into();
+        let mut category_scores = scores(0.0);
+        category_scores["illicit"] = 0.8.into();
+        category_scores["illicit/violent"] = 0.1.into();
+        let newer: ContentModerationResult = serde_json::from_value(serde_json::json!({
+            "flagged": true,
+            "categories": categories,
+            "category_scores": category_scores,
+        }))
+        .unwrap();
+        let flagged: Vec<_> = newer.iter().filter(|(_, flagged, _)| *flagged).collect();
+        assert_eq!(flagged, [(ModerationCategory::Illicit, true, 0.8)]);
+    }
+
+    #[test]
+    fn test_validate_image_request() {
+        let request = |model: ImageModel| {
+            let mut args = CreateImageRequestArgs::default();
+            args.prompt("A cat").model(model);
+            args
+        };
+
+        assert!(request(ImageModel::DallE3)
+            .response_format(ResponseFormat::B64Json)
+            .build()
+            .unwrap()
+            .validate()
+            .is_ok());
+        assert!(request(ImageModel::GptImage1)
+            .response_format(ResponseFormat::Url)
+            .build()
+            .unwrap()
+            .validate()
+            .is_err());
+        assert!(request(ImageModel::DallE2)
+            .output_format(ImageOutputFormat::Webp)
+            .build()
+            .unwrap()
+            .validate()
+            .is_err());
+
+        let compressed = |format: ImageOutputFormat, compression: u8| {
+            request(ImageModel::GptImage1)
+                .output_format(format)
+                .output_compression(compression)
+                .build()
+                .unwrap()
+                .validate()
+        };
+        assert!(compressed(ImageOutputFormat::Jpeg, 80).is_ok());
+        assert!(compressed(ImageOutputFormat::Png, 80).is_err());
+        assert!(compressed(ImageOutputFormat::Webp, 101).is_err());
+
+        let transparent = |format: ImageOutputFormat| {
+            request(ImageModel::GptImage1)
+                .background(ImageBackground::Transparent)
+                .output_format(format)
+                .build()
+                .unwrap()
+                .validate()
+        };
+        assert!(transparent(ImageOutputFormat::Webp).is_ok());
+        assert!(transparent(ImageOutputFormat::Jpeg).is_err());
+        assert!(request(ImageModel::DallE3)
+            .background(ImageBackground::Opaque)
+            .build()
+            .unwrap()
+            .validate()
+            .is_err());
+
+        let moderation = |model: ImageModel| {
+            request(model)
+                .moderation(ImageModeration::Low)
+                .build()
+                .unwrap()
+        };
+        assert!(moderation(ImageModel::GptImage1).validate().is_ok());
+        assert!(moderation(ImageModel::DallE3).validate().is_err());
+        assert_eq!(
+            serde_json::to_value(moderation(ImageModel::GptImage1)).unwrap()["moderation"],
+            "low"
+        );
+
+        let quality = |model: ImageModel, quality: ImageQuality| {
+            request(model).quality(quality).build().unwrap().validate()
+        };
+        assert!(quality(ImageModel::DallE3, ImageQuality::Hd).is_ok());
+        assert!(quality(ImageModel::GptImage1, ImageQuality::High).is_ok());
+        assert!(quality(ImageModel::GptImage1, ImageQuality::Hd).is_err());
+        assert!(quality(ImageModel::DallE2, ImageQuality::Low).is_err());
+
+        // Other models are sent by name, their options are checked by the server
+        let other = request("flux-schnell".into())
+            .quality(ImageQuality::Hd)
+            .output_format(ImageOutputFormat::Webp)
+            .build()
+            .unwrap();
+        assert!(other.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&other).unwrap()["model"],
+            "flux-schnell"
+        );
+        let model: ImageModel = serde_json::from_str(r#""gpt-image-1""#).unwrap();
+        assert_eq!(model, ImageModel::GptImage1);
+        let model: ImageModel = serde_json::from_str(r#""gpt-image-2""#).unwrap();
+        assert_eq!(model, ImageModel::Other("gpt-image-2".into()));
+        assert_eq!(serde_json::to_value(model).unwrap(), "gpt-image-2");
+    }
+
+    #[test]
+    fn test_hyperparameters() {
+        let request = CreateFineTuneRequestArgs::default()
+            .training_file("file-abc")
+            .n_epochs(3)
+            .batch_size(Hyperparameter::Auto)
+            .learning_rate_multiplier(0.5)
+            .build()
+            .unwrap();
+        // The legacy endpoint does not take auto
+        assert!(request.validate().is_err());
+        let json = serde_json::to_value(request).unwrap();
+        assert_eq!(json["n_epochs"], 3);
+        assert_eq!(json["batch_size"], "auto");
+        assert_eq!(json["learning_rate_multiplier"], 0.5);
+
+        let hyperparams: FineTuneHyperparams = serde_json::from_value(serde_json::json!({
+            "n_epochs": "auto",
+            "batch_size": 4,
+            "learning_rate_multiplier": "auto",
+        }))
+        .unwrap();
+        assert_eq!(hyperparams.n_epochs, Hyperparameter::Auto);
+        assert_eq!(hyperparams.batch_size, Some(Hyperparameter::Value(4)));
+        assert_eq!(
+            hyperparams.learning_rate_multiplier,
+            Some(Hyperparameter::Auto)
+        );
+        assert!(serde_json::from_str::<Hyperparameter<u32>>(r#""max""#).is_err());
+
+        let hyperparams: FineTuneHyperparams =
+            serde_json::from_value(serde_json::json!({ "batch_size": 4 })).unwrap();
+        assert_eq!(hyperparams.n_epochs, Hyperparameter::Auto);
+
+        // Classification hyperparams of legacy fine-tunes are kept
+        let legacy = serde_json::json!({
+            "n_epochs": 4,
+            "batch_size": 1,
+            "learning_rate_multiplier": 0.5,
+            "prompt_loss_weight": 0.25,
+            "compute_classification_metrics": true,
+            "classification_n_classes": 3,
+            "classification_positive_class": " yes",
+            "classification_betas": [0.5, 1.0],
+        });
+        let hyperparams: FineTuneHyperparams = serde_json::from_value(legacy.clone()).unwrap();
+        assert_eq!(hyperparams.classification_n_classes, Some(3));
+        assert_eq!(serde_json::to_value(hyperparams).unwrap(), legacy);
+
+        let request = CreateFineTuneRequestArgs::default()
+            .training_file("file-abc")
+            .n_epochs(4)
+            .build()
+            .unwrap();
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_result_file() {
+        let legacy = "step,elapsed_tokens,elapsed_examples,training_loss,training_sequence_accuracy,training_token_accuracy,validation_loss\n\
+            1,25,1,1.5,0.0,0.5,2.0\n\
+            2,50,2,1.25,1.0,0.75,\n";
+        let metrics = FineTuneMetrics::parse_csv(legacy).unwrap();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(
+            metrics[0],
+            FineTuneMetrics {
+                step: 1,
+                train_loss: Some(1.5),
+                train_accuracy: Some(0.0),
+                train_token_accuracy: Some(0.5),
+                valid_loss: Some(2.0),
+                ..Default::default()
+            }
+        );
+        assert_eq!(metrics[1].valid_loss, None);
+
+        let jobs = "step,train_loss,train_accuracy,valid_loss,valid_mean_token_accuracy\n3,0.5,0.9,0.6,0.8\n";
+        let metrics = FineTuneMetrics::parse_csv(jobs).unwrap();
+        assert_eq!(metrics[0].step, 3);
+        assert_eq!(metrics[0].valid_token_accuracy, Some(0.8));
+
+        assert!(FineTuneMetrics::parse_csv("loss\n1.0\n").is_err());
+        assert!(FineTuneMetrics::parse_csv("step,train_loss\n1,high\n").is_err());
+        assert!(matches!(
+            FineTuneMetrics::parse_csv("step,train_loss\n1,0.5,0.25\n"),
+            Err(OpenAIError::CSVDeserialize(_))
+        ));
+    }
+}
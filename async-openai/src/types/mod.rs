@@ -1,6 +1,8 @@
 //! Types used in OpenAI API requests and responses.
 //! These types are created from component schemas in the [OpenAPI spec](https://github.com/openai/openai-openapi)
 mod impls;
+#[cfg(feature = "realtime")]
+pub mod realtime;
 mod types;
 use derive_builder::UninitializedFieldError;
 pub use types::*;
@@ -0,0 +1,1460 @@
+//! Types of the [Realtime API](https://platform.openai.com/docs/guides/realtime), for
+//! speech-to-speech conversations over a WebSocket or WebRTC connection.
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose, Engine as _};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OpenAIError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Modality {
+    Text,
+    Audio,
+}
+
+/// Voice the model speaks with. It cannot be changed once the model has responded with audio.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Voice {
+    #[default]
+    Alloy,
+    Ash,
+    Ballad,
+    Coral,
+    Echo,
+    Sage,
+    Shimmer,
+    Verse,
+    Marin,
+    Cedar,
+}
+
+/// Encoding of audio: 16-bit PCM at 24kHz mono little-endian, or G.711 at 8kHz for telephony.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    #[default]
+    #[serde(rename = "pcm16")]
+    Pcm16,
+    #[serde(rename = "g711_ulaw")]
+    G711Ulaw,
+    #[serde(rename = "g711_alaw")]
+    G711Alaw,
+}
+
+/// Transcription of the input audio, which runs alongside the conversation.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AudioTranscription {
+    /// `whisper-1`, `gpt-4o-transcribe` or `gpt-4o-mini-transcribe`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Language of the input audio in ISO-639-1 format, e.g. `en`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Text to guide the style of the transcription or to continue a previous one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+/// How eagerly semantic voice activity detection ends the user's turn.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Eagerness {
+    Low,
+    Medium,
+    High,
+    #[default]
+    Auto,
+}
+
+/// Detection of the end of the user's turn, after which the model responds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TurnDetection {
+    /// Turns end after a period of silence.
+    ServerVad {
+        /// Level from 0 to 1 above which audio is speech, 0.5 by default. Higher in noisy
+        /// environments.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        threshold: Option<f32>,
+        /// Audio kept before the detected speech, 300ms by default.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        prefix_padding_ms: Option<u32>,
+        /// Silence which ends the turn, 500ms by default.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        silence_duration_ms: Option<u32>,
+        /// Whether to respond when the turn ends, true by default.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        create_response: Option<bool>,
+        /// Whether speech interrupts the response being given, true by default.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interrupt_response: Option<bool>,
+    },
+    /// Turns end when a model judges the user has finished speaking.
+    SemanticVad {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        eagerness: Option<Eagerness>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        create_response: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interrupt_response: Option<bool>,
+    },
+}
+
+/// Tool the model can call during the session.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Tool {
+    Function {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// JSON Schema of the arguments.
+        parameters: serde_json::Value,
+    },
+}
+
+impl Tool {
+    /// A function taking arguments described by the JSON Schema `parameters`.
+    pub fn function<N: Into<String>, D: Into<String>>(
+        name: N,
+        description: D,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Tool::Function {
+            name: name.into(),
+            description: Some(description.into()),
+            parameters,
+        }
+    }
+}
+
+/// How the model chooses the tools it calls, `auto` by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    Auto,
+    None,
+    /// Call at least one tool.
+    Required,
+    /// Call the function of this name.
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => {
+                use serde::ser::SerializeMap;
+
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("type", "function")?;
+                map.serialize_entry("name", name)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolChoice {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Mode(String),
+            Function { name: String },
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Mode(mode) => match mode.as_str() {
+                "auto" => Ok(ToolChoice::Auto),
+                "none" => Ok(ToolChoice::None),
+                "required" => Ok(ToolChoice::Required),
+                _ => Err(serde::de::Error::custom(format!(
+                    "expected auto, none, required or a function, got {mode:?}"
+                ))),
+            },
+            Raw::Function { name } => Ok(ToolChoice::Function(name)),
+        }
+    }
+}
+
+/// Maximum number of output tokens of a response, from 1 to 4096, or `inf` for the maximum of
+/// the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxTokens {
+    Inf,
+    Tokens(u32),
+}
+
+/// Settings of a realtime session, set when creating it or sent in a `session.update` event.
+/// Fields which are not set keep their current value.
+///
+/// ```
+/// use async_openai::types::realtime::{AudioFormat, SessionConfigArgs, TurnDetection, Voice};
+///
+/// let session = SessionConfigArgs::default()
+///     .instructions("You are a helpful assistant.")
+///     .voice(Voice::Coral)
+///     .input_audio_format(AudioFormat::G711Ulaw)
+///     .turn_detection(TurnDetection::ServerVad {
+///         threshold: Some(0.6),
+///         prefix_padding_ms: None,
+///         silence_duration_ms: Some(700),
+///         create_response: None,
+///         interrupt_response: None,
+///     })
+///     .build()
+///     .unwrap();
+/// assert!(session.validate().is_ok());
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Builder)]
+#[builder(name = "SessionConfigArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct SessionConfig {
+    /// The realtime model, e.g. `gpt-4o-realtime-preview`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Whether the model responds with text, or with text and audio.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+
+    /// System instructions of the model for the whole session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<Voice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_format: Option<AudioFormat>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_audio_format: Option<AudioFormat>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_transcription: Option<AudioTranscription>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_detection: Option<TurnDetection>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Sampling temperature from 0.6 to 1.2, 0.8 by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_response_output_tokens: Option<MaxTokens>,
+}
+
+impl SessionConfig {
+    /// Check the ranges of the numeric settings, before sending them.
+    pub fn validate(&self) -> Result<(), OpenAIError> {
+        let invalid = |message: String| Err(OpenAIError::InvalidArgument(message));
+        if let Some(temperature) = self.temperature {
+            if !(0.6..=1.2).contains(&temperature) {
+                return invalid(format!(
+                    "temperature must be between 0.6 and 1.2, got {temperature}"
+                ));
+            }
+        }
+        if let Some(TurnDetection::ServerVad {
+            threshold: Some(threshold),
+            ..
+        }) = self.turn_detection
+        {
+            if !(0.0..=1.0).contains(&threshold) {
+                return invalid(format!(
+                    "turn detection threshold must be between 0 and 1, got {threshold}"
+                ));
+            }
+        }
+        if let Some(MaxTokens::Tokens(tokens)) = self.max_response_output_tokens {
+            if !(1..=4096).contains(&tokens) {
+                return invalid(format!(
+                    "max_response_output_tokens must be between 1 and 4096, got {tokens}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for MaxTokens {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MaxTokens::Inf => serializer.serialize_str("inf"),
+            MaxTokens::Tokens(tokens) => serializer.serialize_u32(*tokens),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MaxTokens {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Tokens(u32),
+            Sentinel(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Tokens(tokens) => Ok(MaxTokens::Tokens(tokens)),
+            Raw::Sentinel(sentinel) if sentinel == "inf" => Ok(MaxTokens::Inf),
+            Raw::Sentinel(other) => Err(serde::de::Error::custom(format!(
+                "expected \"inf\" or a number of tokens, got {other:?}"
+            ))),
+        }
+    }
+}
+
+/// Role of the author of a message item.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+/// Processing status of an item or a response.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    InProgress,
+    Completed,
+    Incomplete,
+    Cancelled,
+    Failed,
+}
+
+/// Part of the content of a message item.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    InputText {
+        text: String,
+    },
+    InputAudio {
+        /// Base64 encoded audio, when the item is created by the client.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        audio: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+    /// Reference to a previous item, in the input of an out-of-band response.
+    ItemReference {
+        id: String,
+    },
+    Text {
+        text: String,
+    },
+    Audio {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        audio: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+}
+
+/// Item of a conversation: a message, a function call of the model, or its output.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Item {
+    Message {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status: Option<Status>,
+        role: Role,
+        content: Vec<ContentPart>,
+    },
+    FunctionCall {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status: Option<Status>,
+        call_id: String,
+        name: String,
+        /// JSON of the arguments.
+        arguments: String,
+    },
+    FunctionCallOutput {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        call_id: String,
+        output: String,
+    },
+}
+
+/// Whether a response is added to the default conversation, or is out-of-band.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseConversation {
+    #[default]
+    Auto,
+    None,
+}
+
+/// Settings of a single response, requested with `response.create`, overriding those of the
+/// session.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Builder)]
+#[builder(name = "ResponseConfigArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ResponseConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<Voice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_audio_format: Option<AudioFormat>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<MaxTokens>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation: Option<ResponseConversation>,
+
+    /// Input of an out-of-band response, instead of the conversation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<Vec<Item>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+/// Session as reported by the server, with the settings in effect.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    #[serde(flatten)]
+    pub config: SessionConfig,
+}
+
+/// Reduction of the noise of the input audio, for the kind of microphone it is recorded with.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NoiseReduction {
+    /// A headset or other microphone close to the speaker.
+    NearField,
+    /// A laptop or conference room microphone.
+    FarField,
+}
+
+/// Settings of a transcription session, which transcribes the input audio without the model
+/// responding, e.g. for live captions.
+///
+/// ```
+/// use async_openai::types::realtime::{
+///     AudioTranscription, NoiseReduction, TranscriptionSessionConfigArgs,
+/// };
+///
+/// let session = TranscriptionSessionConfigArgs::default()
+///     .input_audio_transcription(AudioTranscription {
+///         model: Some("gpt-4o-transcribe".into()),
+///         language: Some("en".into()),
+///         prompt: None,
+///     })
+///     .input_audio_noise_reduction(NoiseReduction::NearField)
+///     .build()
+///     .unwrap();
+/// assert!(session.validate().is_ok());
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Builder)]
+#[builder(name = "TranscriptionSessionConfigArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct TranscriptionSessionConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_format: Option<AudioFormat>,
+
+    /// The transcription model, and the language and prompt of the audio.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_transcription: Option<AudioTranscription>,
+
+    /// Detection of the end of each turn, which is transcribed once committed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_detection: Option<TurnDetection>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_noise_reduction: Option<NoiseReduction>,
+
+    /// Extra fields of the transcription events, e.g.
+    /// `item.input_audio_transcription.logprobs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+}
+
+impl TranscriptionSessionConfig {
+    /// Check the ranges of the numeric settings, before sending them.
+    pub fn validate(&self) -> Result<(), OpenAIError> {
+        SessionConfig {
+            turn_detection: self.turn_detection.clone(),
+            ..Default::default()
+        }
+        .validate()
+    }
+}
+
+/// Transcription session as reported by the server. It has a [ClientSecret] once created
+/// with the REST API.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranscriptionSession {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<ClientSecret>,
+    #[serde(flatten)]
+    pub config: TranscriptionSessionConfig,
+}
+
+/// Short-lived key of a session, for a browser or app to connect with instead of an API key.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClientSecret {
+    pub value: String,
+    /// Unix timestamp (in seconds) after which the key can no longer be used to connect.
+    pub expires_at: u64,
+}
+
+/// Session created with the REST API, which a client connects to with its [ClientSecret].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EphemeralSession {
+    pub id: String,
+    pub client_secret: ClientSecret,
+    #[serde(flatten)]
+    pub config: SessionConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Conversation {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Usage {
+    pub total_tokens: u32,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_token_details: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_token_details: Option<serde_json::Value>,
+}
+
+/// Response of the model, made of output items.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Response {
+    pub id: String,
+    pub status: Status,
+    /// Why the response is incomplete, cancelled or failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_details: Option<serde_json::Value>,
+    pub output: Vec<Item>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Error of an `error` event, or of a failed transcription.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ErrorDetails {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub code: Option<String>,
+    pub message: String,
+    pub param: Option<String>,
+    /// ID of the client event which caused the error.
+    pub event_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateLimit {
+    /// `requests` or `tokens`.
+    pub name: String,
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_seconds: f32,
+}
+
+/// Event sent by the client over the connection, serialized as the JSON of a text frame.
+/// `event_id` is echoed back in the `error` event of a client event which failed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ClientEvent {
+    /// Change the settings of the session.
+    #[serde(rename = "session.update")]
+    SessionUpdate {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        session: SessionConfig,
+    },
+    /// Change the settings of a transcription session.
+    #[serde(rename = "transcription_session.update")]
+    TranscriptionSessionUpdate {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        session: TranscriptionSessionConfig,
+    },
+    /// Add base64 encoded audio in the input audio format to the input buffer.
+    #[serde(rename = "input_audio_buffer.append")]
+    InputAudioBufferAppend {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        audio: String,
+    },
+    /// Make the audio of the input buffer a user message. Not needed with turn detection,
+    /// which commits the buffer when the user stops speaking.
+    #[serde(rename = "input_audio_buffer.commit")]
+    InputAudioBufferCommit {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+    },
+    /// Discard the audio of the input buffer.
+    #[serde(rename = "input_audio_buffer.clear")]
+    InputAudioBufferClear {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+    },
+    /// Add an item to the conversation, after `previous_item_id` or at the end.
+    #[serde(rename = "conversation.item.create")]
+    ConversationItemCreate {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        previous_item_id: Option<String>,
+        item: Item,
+    },
+    #[serde(rename = "conversation.item.retrieve")]
+    ConversationItemRetrieve {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        item_id: String,
+    },
+    /// Cut the audio of an assistant message the user interrupted at `audio_end_ms`, removing
+    /// the transcript the user has not heard.
+    #[serde(rename = "conversation.item.truncate")]
+    ConversationItemTruncate {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        item_id: String,
+        content_index: u32,
+        audio_end_ms: u32,
+    },
+    #[serde(rename = "conversation.item.delete")]
+    ConversationItemDelete {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        item_id: String,
+    },
+    /// Ask the model to respond, needed without turn detection or after adding items.
+    #[serde(rename = "response.create")]
+    ResponseCreate {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        response: Option<ResponseConfig>,
+    },
+    #[serde(rename = "response.cancel")]
+    ResponseCancel {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        response_id: Option<String>,
+    },
+    /// Stop playing the audio of a response, over WebRTC.
+    #[serde(rename = "output_audio_buffer.clear")]
+    OutputAudioBufferClear {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+    },
+    /// Event of a type this crate does not know, when deserializing.
+    #[serde(other)]
+    Unknown,
+}
+
+impl ClientEvent {
+    pub fn session_update(session: SessionConfig) -> Self {
+        ClientEvent::SessionUpdate {
+            event_id: None,
+            session,
+        }
+    }
+
+    pub fn transcription_session_update(session: TranscriptionSessionConfig) -> Self {
+        ClientEvent::TranscriptionSessionUpdate {
+            event_id: None,
+            session,
+        }
+    }
+
+    /// Append a chunk of 16-bit PCM samples, at 24kHz mono, to the input audio buffer.
+    pub fn append_audio(samples: &[i16]) -> Self {
+        ClientEvent::InputAudioBufferAppend {
+            event_id: None,
+            audio: encode_pcm16(samples),
+        }
+    }
+
+    pub fn commit_audio() -> Self {
+        ClientEvent::InputAudioBufferCommit { event_id: None }
+    }
+
+    pub fn clear_audio() -> Self {
+        ClientEvent::InputAudioBufferClear { event_id: None }
+    }
+
+    /// Add the `output` of the function call `call_id` to the conversation.
+    pub fn function_output<C: Into<String>, O: Into<String>>(call_id: C, output: O) -> Self {
+        ClientEvent::ConversationItemCreate {
+            event_id: None,
+            previous_item_id: None,
+            item: Item::FunctionCallOutput {
+                id: None,
+                call_id: call_id.into(),
+                output: output.into(),
+            },
+        }
+    }
+
+    /// Ask the model to respond with the settings of the session.
+    pub fn create_response() -> Self {
+        ClientEvent::ResponseCreate {
+            event_id: None,
+            response: None,
+        }
+    }
+}
+
+/// Audio of a response, in the output audio format, as it is generated.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseAudioDeltaEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    /// Base64 encoded audio.
+    pub delta: String,
+}
+
+impl ResponseAudioDeltaEvent {
+    /// The 16-bit PCM samples of the delta, with the `pcm16` output audio format.
+    pub fn samples(&self) -> Result<Vec<i16>, OpenAIError> {
+        decode_pcm16(&self.delta)
+    }
+}
+
+/// Event received from the server over the connection, deserialized from the JSON of a text
+/// frame. Events of types added to the API after this crate are [ServerEvent::Unknown].
+///
+/// ```
+/// use async_openai::types::realtime::ServerEvent;
+///
+/// let frame = r#"{"type":"response.text.delta","event_id":"event_1","response_id":"resp_1",
+///     "item_id":"item_1","output_index":0,"content_index":0,"delta":"Hello"}"#;
+/// match serde_json::from_str(frame).unwrap() {
+///     ServerEvent::ResponseTextDelta(event) => assert_eq!(event.delta, "Hello"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ServerEvent {
+    #[serde(rename = "error")]
+    Error(ErrorEvent),
+    #[serde(rename = "session.created")]
+    SessionCreated(SessionEvent),
+    #[serde(rename = "session.updated")]
+    SessionUpdated(SessionEvent),
+    #[serde(rename = "transcription_session.created")]
+    TranscriptionSessionCreated(TranscriptionSessionEvent),
+    #[serde(rename = "transcription_session.updated")]
+    TranscriptionSessionUpdated(TranscriptionSessionEvent),
+    #[serde(rename = "conversation.created")]
+    ConversationCreated(ConversationCreatedEvent),
+    #[serde(rename = "conversation.item.created")]
+    ConversationItemCreated(ConversationItemCreatedEvent),
+    #[serde(rename = "conversation.item.retrieved")]
+    ConversationItemRetrieved(ConversationItemRetrievedEvent),
+    #[serde(rename = "conversation.item.input_audio_transcription.delta")]
+    InputAudioTranscriptionDelta(InputAudioTranscriptionDeltaEvent),
+    #[serde(rename = "conversation.item.input_audio_transcription.completed")]
+    InputAudioTranscriptionCompleted(InputAudioTranscriptionCompletedEvent),
+    #[serde(rename = "conversation.item.input_audio_transcription.failed")]
+    InputAudioTranscriptionFailed(InputAudioTranscriptionFailedEvent),
+    #[serde(rename = "conversation.item.truncated")]
+    ConversationItemTruncated(ConversationItemTruncatedEvent),
+    #[serde(rename = "conversation.item.deleted")]
+    ConversationItemDeleted(ConversationItemDeletedEvent),
+    #[serde(rename = "input_audio_buffer.committed")]
+    InputAudioBufferCommitted(InputAudioBufferCommittedEvent),
+    #[serde(rename = "input_audio_buffer.cleared")]
+    InputAudioBufferCleared(InputAudioBufferClearedEvent),
+    #[serde(rename = "input_audio_buffer.speech_started")]
+    InputAudioBufferSpeechStarted(InputAudioBufferSpeechStartedEvent),
+    #[serde(rename = "input_audio_buffer.speech_stopped")]
+    InputAudioBufferSpeechStopped(InputAudioBufferSpeechStoppedEvent),
+    #[serde(rename = "response.created")]
+    ResponseCreated(ResponseEvent),
+    #[serde(rename = "response.done")]
+    ResponseDone(ResponseEvent),
+    #[serde(rename = "response.output_item.added")]
+    ResponseOutputItemAdded(ResponseOutputItemEvent),
+    #[serde(rename = "response.output_item.done")]
+    ResponseOutputItemDone(ResponseOutputItemEvent),
+    #[serde(rename = "response.content_part.added")]
+    ResponseContentPartAdded(ResponseContentPartEvent),
+    #[serde(rename = "response.content_part.done")]
+    ResponseContentPartDone(ResponseContentPartEvent),
+    #[serde(rename = "response.text.delta")]
+    ResponseTextDelta(ResponseTextDeltaEvent),
+    #[serde(rename = "response.text.done")]
+    ResponseTextDone(ResponseTextDoneEvent),
+    #[serde(rename = "response.audio_transcript.delta")]
+    ResponseAudioTranscriptDelta(ResponseAudioTranscriptDeltaEvent),
+    #[serde(rename = "response.audio_transcript.done")]
+    ResponseAudioTranscriptDone(ResponseAudioTranscriptDoneEvent),
+    #[serde(rename = "response.audio.delta")]
+    ResponseAudioDelta(ResponseAudioDeltaEvent),
+    #[serde(rename = "response.audio.done")]
+    ResponseAudioDone(ResponseAudioDoneEvent),
+    #[serde(rename = "response.function_call_arguments.delta")]
+    ResponseFunctionCallArgumentsDelta(ResponseFunctionCallArgumentsDeltaEvent),
+    #[serde(rename = "response.function_call_arguments.done")]
+    ResponseFunctionCallArgumentsDone(ResponseFunctionCallArgumentsDoneEvent),
+    #[serde(rename = "rate_limits.updated")]
+    RateLimitsUpdated(RateLimitsUpdatedEvent),
+    /// The audio of a response started playing, over WebRTC.
+    #[serde(rename = "output_audio_buffer.started")]
+    OutputAudioBufferStarted(OutputAudioBufferEvent),
+    #[serde(rename = "output_audio_buffer.stopped")]
+    OutputAudioBufferStopped(OutputAudioBufferEvent),
+    #[serde(rename = "output_audio_buffer.cleared")]
+    OutputAudioBufferCleared(OutputAudioBufferEvent),
+    #[serde(other)]
+    Unknown,
+}
+
+/// A client event failed, or the connection has a problem. Errors do not close the session.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ErrorEvent {
+    pub event_id: String,
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SessionEvent {
+    pub event_id: String,
+    pub session: Session,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranscriptionSessionEvent {
+    pub event_id: String,
+    pub session: TranscriptionSession,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversationCreatedEvent {
+    pub event_id: String,
+    pub conversation: Conversation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversationItemCreatedEvent {
+    pub event_id: String,
+    pub previous_item_id: Option<String>,
+    pub item: Item,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversationItemRetrievedEvent {
+    pub event_id: String,
+    pub item: Item,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioTranscriptionDeltaEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub delta: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioTranscriptionCompletedEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub transcript: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioTranscriptionFailedEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversationItemTruncatedEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub audio_end_ms: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConversationItemDeletedEvent {
+    pub event_id: String,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioBufferCommittedEvent {
+    pub event_id: String,
+    pub previous_item_id: Option<String>,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioBufferClearedEvent {
+    pub event_id: String,
+}
+
+/// Turn detection heard speech, e.g. to stop playing the response the user interrupts.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioBufferSpeechStartedEvent {
+    pub event_id: String,
+    /// Time from the start of the audio sent in the session.
+    pub audio_start_ms: u32,
+    /// Item of the user message which will be created.
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InputAudioBufferSpeechStoppedEvent {
+    pub event_id: String,
+    pub audio_end_ms: u32,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseEvent {
+    pub event_id: String,
+    pub response: Response,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseOutputItemEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub output_index: u32,
+    pub item: Item,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseContentPartEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub part: ContentPart,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseTextDeltaEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub delta: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseTextDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseAudioTranscriptDeltaEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub delta: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseAudioTranscriptDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub transcript: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseAudioDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseFunctionCallArgumentsDeltaEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub call_id: String,
+    pub delta: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseFunctionCallArgumentsDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub call_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// JSON of the arguments.
+    pub arguments: String,
+}
+
+/// Call of a function by the model, to answer with [ToolCall::reply] as for a tool call of a
+/// chat completion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub call_id: String,
+    /// The function call item of the response.
+    pub item_id: String,
+    pub response_id: String,
+    /// Name of the function. The server may only report it with the `function_call` item of
+    /// `response.output_item.added`.
+    pub name: Option<String>,
+    /// JSON of the arguments.
+    pub arguments: String,
+}
+
+impl ToolCall {
+    /// Deserialize the arguments, e.g. into the type whose schema the function was declared with.
+    pub fn arguments<T: serde::de::DeserializeOwned>(&self) -> Result<T, OpenAIError> {
+        serde_json::from_str(&self.arguments).map_err(OpenAIError::JSONDeserialize)
+    }
+
+    /// Events to send with the `output` of the function, adding it to the conversation then
+    /// asking the model to respond with it.
+    pub fn reply<O: Into<String>>(&self, output: O) -> [ClientEvent; 2] {
+        [
+            ClientEvent::function_output(&self.call_id, output),
+            ClientEvent::create_response(),
+        ]
+    }
+}
+
+impl From<ResponseFunctionCallArgumentsDoneEvent> for ToolCall {
+    fn from(event: ResponseFunctionCallArgumentsDoneEvent) -> Self {
+        ToolCall {
+            call_id: event.call_id,
+            item_id: event.item_id,
+            response_id: event.response_id,
+            name: event.name,
+            arguments: event.arguments,
+        }
+    }
+}
+
+impl ServerEvent {
+    /// The function call of a `response.function_call_arguments.done` event, whose arguments
+    /// are complete.
+    pub fn tool_call(&self) -> Option<ToolCall> {
+        match self {
+            ServerEvent::ResponseFunctionCallArgumentsDone(event) => Some(event.clone().into()),
+            _ => None,
+        }
+    }
+}
+
+/// Limits of the session, updated at the start of each response.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateLimitsUpdatedEvent {
+    pub event_id: String,
+    pub rate_limits: Vec<RateLimit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OutputAudioBufferEvent {
+    pub event_id: String,
+    pub response_id: String,
+}
+
+/// Base64 of the little-endian bytes of `samples`, as audio is sent to the Realtime API.
+pub fn encode_pcm16(samples: &[i16]) -> String {
+    let bytes: Vec<u8> = samples
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .collect();
+    general_purpose::STANDARD.encode(bytes)
+}
+
+/// 16-bit PCM samples of base64 encoded audio received from the Realtime API.
+pub fn decode_pcm16(audio: &str) -> Result<Vec<i16>, OpenAIError> {
+    let bytes = general_purpose::STANDARD
+        .decode(audio)
+        .map_err(|e| OpenAIError::InvalidArgument(format!("invalid base64 audio: {e}")))?;
+    if bytes.len() % 2 != 0 {
+        return Err(OpenAIError::InvalidArgument(
+            "16-bit PCM audio has an odd number of bytes".into(),
+        ));
+    }
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        decode_pcm16, AudioFormat, ClientEvent, ContentPart, Item, MaxTokens, Modality,
+        ResponseAudioDeltaEvent, ResponseConfigArgs, Role, ServerEvent, SessionConfig,
+        SessionConfigArgs, Status, Tool, ToolChoice, TranscriptionSessionConfigArgs, TurnDetection,
+    };
+
+    #[test]
+    fn test_session_config() {
+        let session = SessionConfigArgs::default()
+            .modalities([Modality::Text, Modality::Audio])
+            .output_audio_format(AudioFormat::G711Alaw)
+            .turn_detection(TurnDetection::SemanticVad {
+                eagerness: None,
+                create_response: Some(false),
+                interrupt_response: None,
+            })
+            .max_response_output_tokens(MaxTokens::Inf)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&session).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "modalities": ["text", "audio"],
+                "output_audio_format": "g711_alaw",
+                "turn_detection": {"type": "semantic_vad", "create_response": false},
+                "max_response_output_tokens": "inf",
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<SessionConfig>(json).unwrap(),
+            session
+        );
+
+        let invalid = |session: &mut SessionConfigArgs| session.build().unwrap().validate();
+        assert!(invalid(SessionConfigArgs::default().temperature(1.5)).is_err());
+        assert!(invalid(
+            SessionConfigArgs::default().max_response_output_tokens(MaxTokens::Tokens(0))
+        )
+        .is_err());
+        assert!(invalid(
+            SessionConfigArgs::default().turn_detection(TurnDetection::ServerVad {
+                threshold: Some(2.0),
+                prefix_padding_ms: None,
+                silence_duration_ms: None,
+                create_response: None,
+                interrupt_response: None,
+            })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_audio_events() {
+        let samples = [0, 1, -1, i16::MAX, i16::MIN];
+        let append = ClientEvent::append_audio(&samples);
+        let json = serde_json::to_value(&append).unwrap();
+        assert_eq!(json["type"], "input_audio_buffer.append");
+        assert_eq!(
+            decode_pcm16(json["audio"].as_str().unwrap()).unwrap(),
+            samples
+        );
+        assert_eq!(
+            serde_json::to_string(&ClientEvent::commit_audio()).unwrap(),
+            r#"{"type":"input_audio_buffer.commit"}"#
+        );
+
+        let delta: ResponseAudioDeltaEvent = serde_json::from_value(json!({
+            "event_id": "event_1",
+            "response_id": "resp_1",
+            "item_id": "item_1",
+            "output_index": 0,
+            "content_index": 0,
+            "delta": "AQD//w==",
+        }))
+        .unwrap();
+        assert_eq!(delta.samples().unwrap(), [1, -1]);
+        assert!(decode_pcm16("AQ==").is_err());
+    }
+
+    #[test]
+    fn test_events() {
+        let create = ClientEvent::ConversationItemCreate {
+            event_id: None,
+            previous_item_id: None,
+            item: Item::Message {
+                id: None,
+                status: None,
+                role: Role::User,
+                content: vec![ContentPart::InputText {
+                    text: "Hello".into(),
+                }],
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&create).unwrap(),
+            json!({
+                "type": "conversation.item.create",
+                "item": {
+                    "type": "message",
+                    "role": "user",
+                    "content": [{"type": "input_text", "text": "Hello"}],
+                },
+            })
+        );
+        let respond = ClientEvent::ResponseCreate {
+            event_id: Some("event_1".into()),
+            response: Some(
+                ResponseConfigArgs::default()
+                    .modalities([Modality::Text])
+                    .build()
+                    .unwrap(),
+            ),
+        };
+        assert_eq!(
+            serde_json::to_string(&respond).unwrap(),
+            r#"{"type":"response.create","event_id":"event_1","response":{"modalities":["text"]}}"#
+        );
+
+        let session: ServerEvent = serde_json::from_value(json!({
+            "type": "session.created",
+            "event_id": "event_1",
+            "session": {
+                "id": "sess_1",
+                "object": "realtime.session",
+                "model": "gpt-4o-realtime-preview",
+                "modalities": ["text", "audio"],
+                "voice": "alloy",
+                "turn_detection": null,
+                "temperature": 0.8,
+                "max_response_output_tokens": "inf",
+            },
+        }))
+        .unwrap();
+        let ServerEvent::SessionCreated(session) = session else {
+            panic!("{session:?}");
+        };
+        assert_eq!(session.session.id, "sess_1");
+        assert_eq!(session.session.config.turn_detection, None);
+        assert_eq!(
+            session.session.config.max_response_output_tokens,
+            Some(MaxTokens::Inf)
+        );
+
+        let done: ServerEvent = serde_json::from_value(json!({
+            "type": "response.done",
+            "event_id": "event_2",
+            "response": {
+                "id": "resp_1",
+                "object": "realtime.response",
+                "status": "completed",
+                "output": [{
+                    "id": "item_1",
+                    "type": "function_call",
+                    "status": "completed",
+                    "call_id": "call_1",
+                    "name": "get_weather",
+                    "arguments": "{}",
+                }],
+                "usage": {"total_tokens": 3, "input_tokens": 2, "output_tokens": 1},
+            },
+        }))
+        .unwrap();
+        let ServerEvent::ResponseDone(done) = done else {
+            panic!("{done:?}");
+        };
+        assert_eq!(done.response.status, Status::Completed);
+        assert!(
+            matches!(done.response.output[0], Item::FunctionCall { ref name, .. } if name == "get_weather")
+        );
+
+        let unknown: ServerEvent =
+            serde_json::from_str(r#"{"type":"conversation.item.added","event_id":"event_3"}"#)
+                .unwrap();
+        assert_eq!(unknown, ServerEvent::Unknown);
+    }
+
+    #[test]
+    fn test_tool_calls() {
+        let session = SessionConfigArgs::default()
+            .tools([Tool::function(
+                "get_weather",
+                "Weather of a city",
+                json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+            )])
+            .tool_choice(ToolChoice::Function("get_weather".into()))
+            .build()
+            .unwrap();
+        let session = serde_json::to_value(session).unwrap();
+        assert_eq!(session["tools"][0]["type"], "function");
+        assert_eq!(
+            session["tool_choice"],
+            json!({"type": "function", "name": "get_weather"})
+        );
+        assert_eq!(
+            serde_json::to_value(ToolChoice::Required).unwrap(),
+            "required"
+        );
+        assert_eq!(
+            serde_json::from_value::<ToolChoice>(session["tool_choice"].clone()).unwrap(),
+            ToolChoice::Function("get_weather".into())
+        );
+
+        let event: ServerEvent = serde_json::from_value(json!({
+            "type": "response.function_call_arguments.done",
+            "event_id": "event_1",
+            "response_id": "resp_1",
+            "item_id": "item_1",
+            "output_index": 0,
+            "call_id": "call_1",
+            "name": "get_weather",
+            "arguments": "{\"city\":\"Paris\"}",
+        }))
+        .unwrap();
+        let call = event.tool_call().unwrap();
+        assert_eq!(call.name.as_deref(), Some("get_weather"));
+        let arguments: serde_json::Value = call.arguments().unwrap();
+        assert_eq!(arguments["city"], "Paris");
+
+        let [output, respond] = call.reply("22C and sunny");
+        assert_eq!(
+            serde_json::to_value(output).unwrap(),
+            json!({
+                "type": "conversation.item.create",
+                "item": {
+                    "type": "function_call_output",
+                    "call_id": "call_1",
+                    "output": "22C and sunny",
+                },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(respond).unwrap(),
+            json!({"type": "response.create"})
+        );
+    }
+
+    #[test]
+    fn test_transcription_session() {
+        let session = TranscriptionSessionConfigArgs::default()
+            .input_audio_format(AudioFormat::Pcm16)
+            .input_audio_noise_reduction(super::NoiseReduction::FarField)
+            .include(["item.input_audio_transcription.logprobs".to_string()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(ClientEvent::transcription_session_update(session)).unwrap(),
+            json!({
+                "type": "transcription_session.update",
+                "session": {
+                    "input_audio_format": "pcm16",
+                    "input_audio_noise_reduction": {"type": "far_field"},
+                    "include": ["item.input_audio_transcription.logprobs"],
+                },
+            })
+        );
+
+        let invalid = TranscriptionSessionConfigArgs::default()
+            .turn_detection(TurnDetection::ServerVad {
+                threshold: Some(2.0),
+                prefix_padding_ms: None,
+                silence_duration_ms: None,
+                create_response: None,
+                interrupt_response: None,
+            })
+            .build()
+            .unwrap();
+        assert!(invalid.validate().is_err());
+
+        let updated: ServerEvent = serde_json::from_value(json!({
+            "type": "transcription_session.updated",
+            "event_id": "event_1",
+            "session": {
+                "id": "sess_1",
+                "object": "realtime.transcription_session",
+                "input_audio_transcription": {"model": "gpt-4o-transcribe"},
+                "turn_detection": null,
+            },
+        }))
+        .unwrap();
+        let ServerEvent::TranscriptionSessionUpdated(updated) = updated else {
+            panic!("{updated:?}");
+        };
+        assert_eq!(updated.session.client_secret, None);
+        assert_eq!(
+            updated
+                .session
+                .config
+                .input_audio_transcription
+                .unwrap()
+                .model
+                .as_deref(),
+            Some("gpt-4o-transcribe")
+        );
+    }
+}
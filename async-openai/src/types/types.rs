@@ -1,12 +1,12 @@
-use std::{collections::HashMap, path::PathBuf, pin::Pin};
+use std::{collections::HashMap, path::PathBuf};
 
+use bytes::Bytes;
 use derive_builder::Builder;
-use futures::Stream;
 use serde::{Deserialize, Serialize};
 
-use crate::error::OpenAIError;
+use crate::{error::OpenAIError, runtime::BoxStream, splitter::TokenEstimator};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Model {
     pub id: String,
     pub object: String,
@@ -14,13 +14,65 @@ pub struct Model {
     pub owned_by: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ListModelResponse {
+/// Sort order of listed objects by their `created_at` timestamp.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListOrder {
+    Asc,
+    Desc,
+}
+
+/// Cursor pagination parameters of list endpoints, sent in the query string.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder, PartialEq, Eq)]
+#[builder(name = "ListParamsArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ListParams {
+    /// Number of objects to return per page, between 1 and the maximum of the endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    /// Sort order by the `created_at` timestamp of the objects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<ListOrder>,
+
+    /// Object ID to list the objects after, usually the last ID of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+
+    /// Object ID to list the objects before, usually the first ID of the following page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+}
+
+/// Page of a list endpoint with cursor pagination, see [ListParams].
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ListObject<T> {
     pub object: String,
-    pub data: Vec<Model>,
+    pub data: Vec<T>,
+    /// ID of the first object of the page, to list the page `before` it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_id: Option<String>,
+    /// ID of the last object of the page, to list the page `after` it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_id: Option<String>,
+    /// Whether there are more objects after this page, missing from lists which are not paginated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
+}
+
+/// List of an endpoint without pagination, which returns every object at once.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct LegacyListObject<T> {
+    pub object: String,
+    pub data: Vec<T>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+pub type ListModelResponse = LegacyListObject<Model>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum Prompt {
     String(String),
@@ -30,14 +82,14 @@ pub enum Prompt {
     ArrayOfIntegerArray(Vec<Vec<u16>>),
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum Stop {
     String(String),           // nullable: true
     StringArray(Vec<String>), // minItems: 1; maxItems: 4
 }
 
-#[derive(Clone, Serialize, Default, Debug, Builder)]
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Builder)]
 #[builder(name = "CreateCompletionRequestArgs")]
 #[builder(pattern = "mutable")]
 #[builder(setter(into, strip_option), default)]
@@ -129,12 +181,18 @@ pub struct CreateCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logit_bias: Option<HashMap<String, serde_json::Value>>, // default: null
 
+    /// If specified, the system will make a best effort to sample deterministically, such that repeated requests with the same `seed` and parameters should return the same result.
+    ///
+    /// Determinism is not guaranteed, and you should refer to the `system_fingerprint` response parameter to monitor changes in the backend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+
     /// A unique identifier representing your end-user, which will help OpenAI to monitor and detect abuse. [Learn more](https://platform.openai.com/docs/usage-policies/end-user-ids).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Logprobs {
     pub tokens: Vec<String>,
     pub token_logprobs: Vec<Option<f32>>, // Option is to account for null value in the list
@@ -142,7 +200,7 @@ pub struct Logprobs {
     pub text_offset: Vec<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Choice {
     pub text: String,
     pub index: u32,
@@ -150,28 +208,31 @@ pub struct Choice {
     pub finish_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateCompletionResponse {
     pub id: String,
     pub object: String,
     pub created: u32,
     pub model: String,
+    /// Fingerprint of the backend configuration the model runs with. Together with the `seed`
+    /// request parameter, used to tell when backend changes may affect determinism.
+    pub system_fingerprint: Option<String>,
     pub choices: Vec<Choice>,
     pub usage: Option<Usage>,
 }
 
 /// Parsed server side events stream until an \[DONE\] is received from server.
 pub type CompletionResponseStream =
-    Pin<Box<dyn Stream<Item = Result<CreateCompletionResponse, OpenAIError>> + Send>>;
+    BoxStream<'static, Result<CreateCompletionResponse, OpenAIError>>;
 
-#[derive(Debug, Clone, Serialize, Default, Builder)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
 #[builder(name = "CreateEditRequestArgs")]
 #[builder(pattern = "mutable")]
 #[builder(setter(into, strip_option), default)]
@@ -205,7 +266,7 @@ pub struct CreateEditRequest {
     pub top_p: Option<f32>, // min: 0, max: 1, default: 1
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateEditResponse {
     pub id: Option<String>,
     pub object: String,
@@ -215,7 +276,7 @@ pub struct CreateEditResponse {
     pub usage: Usage,
 }
 
-#[derive(Default, Debug, Serialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub enum ImageSize {
     #[serde(rename = "256x256")]
     S256x256,
@@ -226,7 +287,7 @@ pub enum ImageSize {
     S1024x1024,
 }
 
-#[derive(Debug, Serialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum ResponseFormat {
     #[default]
@@ -235,7 +296,63 @@ pub enum ResponseFormat {
     B64Json,
 }
 
-#[derive(Debug, Clone, Serialize, Default, Builder)]
+/// Model of an image request. Other models, such as those of OpenAI compatible servers, are
+/// sent by name, e.g. `.model("flux-schnell")`, and their options are left to the server to check.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum ImageModel {
+    #[default]
+    DallE2,
+    DallE3,
+    GptImage1,
+    Other(String),
+}
+
+/// File format of images generated by `gpt-image-1`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageOutputFormat {
+    #[default]
+    Png,
+    Webp,
+    Jpeg,
+}
+
+/// Background of images generated by `gpt-image-1`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageBackground {
+    /// Let the model choose.
+    #[default]
+    Auto,
+    /// Requires the `png` or `webp` output format.
+    Transparent,
+    Opaque,
+}
+
+/// Quality of generated images: `standard` or `hd` for `dall-e-3`, `low`, `medium`, `high` or
+/// `auto` for `gpt-image-1`. `dall-e-2` only supports `standard`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageQuality {
+    Standard,
+    Hd,
+    Low,
+    Medium,
+    High,
+    Auto,
+}
+
+/// Content moderation of images generated by `gpt-image-1`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageModeration {
+    #[default]
+    Auto,
+    /// Less restrictive filtering.
+    Low,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Builder)]
 #[builder(name = "CreateImageRequestArgs")]
 #[builder(pattern = "mutable")]
 #[builder(setter(into, strip_option), default)]
@@ -245,6 +362,10 @@ pub struct CreateImageRequest {
     /// A text description of the desired image(s). The maximum length is 1000 characters.
     pub prompt: String,
 
+    /// The model to use for image generation, `dall-e-2` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<ImageModel>,
+
     /// The number of images to generate. Must be between 1 and 10.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub n: Option<u8>, // min:1 max:10 default:1
@@ -254,15 +375,47 @@ pub struct CreateImageRequest {
     pub size: Option<ImageSize>,
 
     /// The format in which the generated images are returned. Must be one of `url` or `b64_json`.
+    /// Not supported by `gpt-image-1`, which always returns `b64_json`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<ResponseFormat>,
 
+    /// The file format of the generated images, `png` by default. Only supported by `gpt-image-1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<ImageOutputFormat>,
+
+    /// The compression level from 0 to 100 of `webp` or `jpeg` images generated by `gpt-image-1`,
+    /// 100 by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_compression: Option<u8>,
+
+    /// The background of the generated images, `auto` by default. Only supported by `gpt-image-1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<ImageBackground>,
+
+    /// The quality of the generated images, see [ImageQuality] for the values of each model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<ImageQuality>,
+
+    /// The content moderation level of the generated images, `auto` by default. Only supported
+    /// by `gpt-image-1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<ImageModeration>,
+
+    /// Whether to stream the image as it is generated, see [Images::create_stream](crate::Images::create_stream).
+    /// Only supported by `gpt-image-1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+
+    /// The number of partial images from 0 to 3 to stream before the final image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_images: Option<u8>,
+
     /// A unique identifier representing your end-user, which will help OpenAI to monitor and detect abuse. [Learn more](https://platform.openai.com/docs/usage-policies/end-user-ids).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ImageData {
     Url(std::sync::Arc<String>),
@@ -270,15 +423,64 @@ pub enum ImageData {
     B64Json(std::sync::Arc<String>),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ImageResponse {
     pub created: u32,
     pub data: Vec<std::sync::Arc<ImageData>>,
 }
 
+/// Event of a streamed image generation, see [Images::create_stream](crate::Images::create_stream).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum ImageGenerationEvent {
+    #[serde(rename = "image_generation.partial_image")]
+    PartialImage(ImagePartialImageEvent),
+    #[serde(rename = "image_generation.completed")]
+    Completed(ImageCompletedEvent),
+}
+
+/// Preview of the image being generated, less refined than the final one.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImagePartialImageEvent {
+    /// The partial image, base64 encoded in `output_format`
+    pub b64_json: String,
+    /// Index of the partial image from 0, in order of generation
+    pub partial_image_index: u32,
+    pub created_at: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<ImageOutputFormat>,
+}
+
+/// The final image, which ends the stream.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImageCompletedEvent {
+    /// The image, base64 encoded in `output_format`
+    pub b64_json: String,
+    pub created_at: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<ImageOutputFormat>,
+}
+
+/// Parsed server side events stream of an image generation, ending with
+/// [ImageGenerationEvent::Completed].
+pub type ImageGenerationStream = BoxStream<'static, Result<ImageGenerationEvent, OpenAIError>>;
+
+/// Where the contents of an uploaded file come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    /// Streamed from a file on disk.
+    Path { path: PathBuf },
+    /// Uploaded from memory without copying, `filename` is sent as the part's file name.
+    Bytes { filename: String, bytes: Bytes },
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct ImageInput {
-    pub path: PathBuf,
+    pub source: InputSource,
 }
 
 #[derive(Debug, Clone, Default, Builder)]
@@ -289,11 +491,17 @@ pub struct ImageInput {
 #[builder(build_fn(error = "OpenAIError"))]
 pub struct CreateImageEditRequest {
     /// The image to edit. Must be a valid PNG file, less than 4MB, and square.
-    pub image: ImageInput,
+    ///
+    /// `gpt-image-1` takes up to 16 reference images, each a PNG, WEBP or JPEG file less than 25MB; set them with [CreateImageEditRequestArgs::images].
+    #[builder(setter(custom))]
+    pub image: Vec<ImageInput>,
 
-    /// An additional image whose fully transparent areas (e.g. where alpha is zero) indicate where `image` should be edited. Must be a valid PNG file, less than 4MB, and have the same dimensions as `image`.
+    /// An additional image whose fully transparent areas (e.g. where alpha is zero) indicate where `image` should be edited. Must be a valid PNG file, less than 4MB, and have the same dimensions as `image`. With several images the mask applies to the first.
     pub mask: ImageInput,
 
+    /// The model to use for image editing. Defaults to `dall-e-2`.
+    pub model: Option<ImageModel>,
+
     /// A text description of the desired image(s). The maximum length is 1000 characters.
     pub prompt: String,
 
@@ -310,6 +518,23 @@ pub struct CreateImageEditRequest {
     pub user: Option<String>,
 }
 
+impl CreateImageEditRequestArgs {
+    /// The image to edit.
+    pub fn image<I: Into<ImageInput>>(&mut self, image: I) -> &mut Self {
+        self.image = Some(vec![image.into()]);
+        self
+    }
+
+    /// The reference images to edit together, supported by `gpt-image-1`.
+    pub fn images<I: IntoIterator<Item = T>, T: Into<ImageInput>>(
+        &mut self,
+        images: I,
+    ) -> &mut Self {
+        self.image = Some(images.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
 #[derive(Debug, Default, Clone, Builder)]
 #[builder(name = "CreateImageVariationRequestArgs")]
 #[builder(pattern = "mutable")]
@@ -333,14 +558,14 @@ pub struct CreateImageVariationRequest {
     pub user: Option<String>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum ModerationInput {
     String(String),
     StringArray(Vec<String>),
 }
 
-#[derive(Debug, Serialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub enum TextModerationModel {
     #[default]
     #[serde(rename = "text-moderation-latest")]
@@ -349,7 +574,7 @@ pub enum TextModerationModel {
     Stable,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Builder)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Builder)]
 #[builder(name = "CreateModerationRequestArgs")]
 #[builder(pattern = "mutable")]
 #[builder(setter(into, strip_option), default)]
@@ -366,7 +591,7 @@ pub struct CreateModerationRequest {
     pub model: Option<TextModerationModel>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Category {
     pub hate: bool,
     #[serde(rename = "hate/threatening")]
@@ -379,9 +604,28 @@ pub struct Category {
     pub violence: bool,
     #[serde(rename = "violence/graphic")]
     pub violence_graphic: bool,
+    // Categories which only newer models return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub harassment: Option<bool>,
+    #[serde(
+        rename = "harassment/threatening",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub harassment_threatening: Option<bool>,
+    #[serde(rename = "self-harm/intent", skip_serializing_if = "Option::is_none")]
+    pub self_harm_intent: Option<bool>,
+    #[serde(
+        rename = "self-harm/instructions",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub self_harm_instructions: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illicit: Option<bool>,
+    #[serde(rename = "illicit/violent", skip_serializing_if = "Option::is_none")]
+    pub illicit_violent: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CategoryScore {
     pub hate: f32,
     #[serde(rename = "hate/threatening")]
@@ -394,25 +638,104 @@ pub struct CategoryScore {
     pub violence: f32,
     #[serde(rename = "violence/graphic")]
     pub violence_graphic: f32,
+    // Categories which only newer models return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub harassment: Option<f32>,
+    #[serde(
+        rename = "harassment/threatening",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub harassment_threatening: Option<f32>,
+    #[serde(rename = "self-harm/intent", skip_serializing_if = "Option::is_none")]
+    pub self_harm_intent: Option<f32>,
+    #[serde(
+        rename = "self-harm/instructions",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub self_harm_instructions: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illicit: Option<f32>,
+    #[serde(rename = "illicit/violent", skip_serializing_if = "Option::is_none")]
+    pub illicit_violent: Option<f32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ContentModerationResult {
     pub flagged: bool,
     pub categories: Category,
     pub category_scores: CategoryScore,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateModerationResponse {
     pub id: String,
     pub model: String,
     pub results: Vec<ContentModerationResult>,
 }
 
+/// Content policy category of a [ContentModerationResult]. Harassment, the self-harm
+/// subcategories and illicit are only returned by newer moderation models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ModerationCategory {
+    #[serde(rename = "hate")]
+    Hate,
+    #[serde(rename = "hate/threatening")]
+    HateThreatening,
+    #[serde(rename = "self-harm")]
+    SelfHarm,
+    #[serde(rename = "sexual")]
+    Sexual,
+    #[serde(rename = "sexual/minors")]
+    SexualMinors,
+    #[serde(rename = "violence")]
+    Violence,
+    #[serde(rename = "violence/graphic")]
+    ViolenceGraphic,
+    #[serde(rename = "harassment")]
+    Harassment,
+    #[serde(rename = "harassment/threatening")]
+    HarassmentThreatening,
+    #[serde(rename = "self-harm/intent")]
+    SelfHarmIntent,
+    #[serde(rename = "self-harm/instructions")]
+    SelfHarmInstructions,
+    #[serde(rename = "illicit")]
+    Illicit,
+    #[serde(rename = "illicit/violent")]
+    IllicitViolent,
+}
+
+/// Score from which a category counts as flagged, per category, for pipelines stricter or
+/// more lenient than the `flagged` booleans of the API. Categories without a threshold are
+/// flagged as the API flagged them.
+///
+/// ```
+/// use async_openai::types::{ModerationCategory, ModerationThresholds};
+///
+/// let thresholds = ModerationThresholds::new()
+///     .with_threshold(ModerationCategory::Violence, 0.2)
+///     .with_threshold(ModerationCategory::Sexual, 0.9);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ModerationThresholds {
+    pub(crate) thresholds: HashMap<ModerationCategory, f32>,
+}
+
+impl ModerationThresholds {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Flag `category` when its score is at least `threshold`, from 0 to 1.
+    pub fn with_threshold(mut self, category: ModerationCategory, threshold: f32) -> Self {
+        self.thresholds.insert(category, threshold);
+        self
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct FileInput {
-    pub path: PathBuf,
+    pub source: InputSource,
 }
 
 #[derive(Debug, Default, Clone, Builder)]
@@ -433,20 +756,20 @@ pub struct CreateFileRequest {
     pub purpose: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ListFilesResponse {
-    pub object: String,
-    pub data: Vec<OpenAIFile>,
-}
+/// Files of [Files::list_stream](crate::Files::list_stream) as they are received, or of
+/// [Files::list_all](crate::Files::list_all).
+pub type OpenAIFileStream = BoxStream<'static, Result<OpenAIFile, OpenAIError>>;
 
-#[derive(Debug, Deserialize)]
+pub type ListFilesResponse = ListObject<OpenAIFile>;
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DeleteFileResponse {
     pub id: String,
     pub object: String,
     pub deleted: bool,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub struct OpenAIFile {
     pub id: String,
     pub object: String,
@@ -458,7 +781,48 @@ pub struct OpenAIFile {
     pub status_details: Option<serde_json::Value>, // nullable: true
 }
 
-#[derive(Debug, Serialize, Clone, Default, Builder)]
+/// One line of a fine-tuning training file, see [TrainingFileBuilder](crate::TrainingFileBuilder).
+/// Legacy fine-tunes train completion models on prompt/completion pairs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrainingExample {
+    pub prompt: String,
+    pub completion: String,
+}
+
+/// Fine-tuning hyperparameter which is either chosen by the API from the training data,
+/// serialized as `"auto"`, or set to a value. Builders take plain values, e.g. `.n_epochs(4)`.
+///
+/// `Auto` is only accepted by fine-tuning jobs, the legacy `/fine-tunes` endpoint takes
+/// values alone, see [CreateFineTuneRequest::validate].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Hyperparameter<T> {
+    #[default]
+    Auto,
+    Value(T),
+}
+
+/// Hyperparameters a fine-tune was trained with.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FineTuneHyperparams {
+    #[serde(default)]
+    pub n_epochs: Hyperparameter<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<Hyperparameter<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub learning_rate_multiplier: Option<Hyperparameter<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_loss_weight: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_classification_metrics: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification_n_classes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification_positive_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification_betas: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Builder)]
 #[builder(name = "CreateFineTuneRequestArgs")]
 #[builder(pattern = "mutable")]
 #[builder(setter(into, strip_option), default)]
@@ -500,7 +864,7 @@ pub struct CreateFineTuneRequest {
     /// The number of epochs to train the model for. An epoch refers to one
     /// full cycle through the training dataset.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub n_epochs: Option<u32>, // default: 4
+    pub n_epochs: Option<Hyperparameter<u32>>, // default: 4
 
     /// The batch size to use for training. The batch size is the number of
     /// training examples used to train a single forward and backward pass.
@@ -510,7 +874,7 @@ pub struct CreateFineTuneRequest {
     /// in general, we've found that larger batch sizes tend to work better
     /// for larger datasets.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub batch_size: Option<u32>, // default: null
+    pub batch_size: Option<Hyperparameter<u32>>, // default: null
 
     /// The learning rate multiplier to use for training.
     /// The fine-tuning learning rate is the original learning rate used for
@@ -522,7 +886,7 @@ pub struct CreateFineTuneRequest {
     /// with values in the range 0.02 to 0.2 to see what produces the best
     /// results.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub learning_rate_multiplier: Option<f32>, // default: null
+    pub learning_rate_multiplier: Option<Hyperparameter<f32>>, // default: null
 
     /// The weight to use for loss on the prompt tokens. This controls how
     /// much the model tries to learn to generate the prompt (as compared
@@ -577,13 +941,9 @@ pub struct CreateFineTuneRequest {
     pub suffix: Option<String>, // default: null, minLength:1, maxLength:40
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ListFineTuneResponse {
-    pub object: String,
-    pub data: Vec<FineTune>,
-}
+pub type ListFineTuneResponse = LegacyListObject<FineTune>;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FineTune {
     pub id: String,
     pub object: String,
@@ -593,14 +953,30 @@ pub struct FineTune {
     pub fine_tuned_model: Option<String>, // nullable: true
     pub organization_id: String,
     pub status: String,
-    pub hyperparams: serde_json::Value,
+    pub hyperparams: FineTuneHyperparams,
     pub training_files: Vec<OpenAIFile>,
     pub validation_files: Vec<OpenAIFile>,
     pub result_files: Vec<OpenAIFile>,
     pub events: Option<Vec<FineTuneEvent>>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Row of the metrics in the result file of a fine-tune, see
+/// [FineTunes::results](crate::FineTunes::results). Validation metrics are only reported on
+/// some steps, and accuracies only by some models.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct FineTuneMetrics {
+    pub step: u32,
+    pub train_loss: Option<f32>,
+    /// Fraction of training completions predicted exactly.
+    pub train_accuracy: Option<f32>,
+    /// Fraction of training completion tokens predicted correctly.
+    pub train_token_accuracy: Option<f32>,
+    pub valid_loss: Option<f32>,
+    pub valid_accuracy: Option<f32>,
+    pub valid_token_accuracy: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FineTuneEvent {
     pub object: String,
     pub created_at: u32,
@@ -608,24 +984,20 @@ pub struct FineTuneEvent {
     pub message: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ListFineTuneEventsResponse {
-    pub object: String,
-    pub data: Vec<FineTuneEvent>,
-}
+pub type ListFineTuneEventsResponse = LegacyListObject<FineTuneEvent>;
 
 /// Parsed server side events stream until an \[DONE\] is received from server.
 pub type FineTuneEventsResponseStream =
-    Pin<Box<dyn Stream<Item = Result<ListFineTuneEventsResponse, OpenAIError>> + Send>>;
+    BoxStream<'static, Result<ListFineTuneEventsResponse, OpenAIError>>;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DeleteModelResponse {
     pub id: String,
     pub object: String,
     pub deleted: bool,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum EmbeddingInput {
     String(String),
@@ -635,7 +1007,7 @@ pub enum EmbeddingInput {
     ArrayOfIntegerArray(Vec<Vec<u32>>),
 }
 
-#[derive(Debug, Serialize, Default, Clone, Builder)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Builder)]
 #[builder(name = "CreateEmbeddingRequestArgs")]
 #[builder(pattern = "mutable")]
 #[builder(setter(into, strip_option), default)]
@@ -659,25 +1031,113 @@ pub struct CreateEmbeddingRequest {
     ///  to monitor and detect abuse. [Learn more](https://platform.openai.com/docs/usage-policies/end-user-ids).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+
+    /// The format to return the embeddings in, `float` by default. Base64 responses are
+    /// about a quarter of the size, and are decoded into [Embedding::embedding] as they are
+    /// received.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+
+    /// The number of dimensions the embeddings should have, for models which support
+    /// shortening them. Embeddings of another length are an error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingFormat {
+    Float,
+    Base64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Embedding {
     pub index: u32,
     pub object: String,
+    #[serde(deserialize_with = "super::impls::deserialize_embedding")]
     pub embedding: Vec<f32>,
 }
 
-#[derive(Debug, Deserialize)]
+impl AsRef<[f32]> for Embedding {
+    fn as_ref(&self) -> &[f32] {
+        &self.embedding
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EmbeddingUsage {
     pub prompt_tokens: u32,
     pub total_tokens: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CreateEmbeddingResponse {
     pub object: String,
     pub model: String,
     pub data: Vec<Embedding>,
     pub usage: EmbeddingUsage,
 }
+
+/// Options for [Embeddings::create_batched](crate::Embeddings::create_batched).
+#[derive(Debug, Clone)]
+pub struct EmbeddingBatchOptions {
+    pub(crate) max_inputs_per_request: usize,
+    pub(crate) max_tokens_per_request: usize,
+    pub(crate) max_concurrency: usize,
+    pub(crate) token_estimator: TokenEstimator,
+}
+
+impl Default for EmbeddingBatchOptions {
+    fn default() -> Self {
+        Self {
+            max_inputs_per_request: 2048,
+            max_tokens_per_request: 300_000,
+            max_concurrency: 4,
+            token_estimator: TokenEstimator::default(),
+        }
+    }
+}
+
+impl EmbeddingBatchOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Maximum number of inputs in a single request. Default is 2048.
+    pub fn with_max_inputs_per_request(mut self, max: usize) -> Self {
+        self.max_inputs_per_request = max.max(1);
+        self
+    }
+
+    /// Maximum number of tokens across all inputs of a single request. Default is 300,000.
+    pub fn with_max_tokens_per_request(mut self, max: usize) -> Self {
+        self.max_tokens_per_request = max;
+        self
+    }
+
+    /// Maximum number of requests in flight at once. Default is 4.
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = max.max(1);
+        self
+    }
+
+    /// Function counting the tokens of a text input. The default estimates four characters per token;
+    /// use a real tokenizer when inputs are close to the limits. Token array inputs are counted exactly.
+    pub fn with_token_estimator<F>(mut self, estimator: F) -> Self
+    where
+        F: Fn(&str) -> usize + Send + Sync + 'static,
+    {
+        self.token_estimator = TokenEstimator::new(estimator);
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateEmbeddingBatchResponse {
+    pub model: String,
+    /// The embedding for each input in input order, or the error of the request which contained the input.
+    pub data: Vec<Result<Embedding, std::sync::Arc<OpenAIError>>>,
+    /// Sum of the usage of all successful requests
+    pub usage: EmbeddingUsage,
+}
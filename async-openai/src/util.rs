@@ -1,40 +1,306 @@
-use std::path::Path;
-
+use base64::{engine::general_purpose, Engine as _};
+#[cfg(feature = "_post")]
+use bytes::Bytes;
+#[cfg(feature = "files")]
+use futures::StreamExt;
+#[cfg(feature = "multipart")]
 use reqwest::Body;
-use tokio_util::codec::{BytesCodec, FramedRead};
+#[cfg(any(feature = "azure-ad", feature = "_api"))]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "_post")]
+use serde::Serialize;
 
+#[cfg(any(feature = "azure-ad", feature = "_api"))]
 use crate::error::OpenAIError;
+#[cfg(all(feature = "multipart", not(target_arch = "wasm32")))]
+use crate::runtime::fs;
+#[cfg(feature = "files")]
+use crate::runtime::BoxStream;
+#[cfg(feature = "multipart")]
+use crate::types::InputSource;
+
+/// Serializes a request once; the returned buffer is shared, not copied, by retries.
+#[cfg(feature = "_post")]
+pub(crate) fn json_body<I: Serialize>(request: &I) -> Result<Bytes, OpenAIError> {
+    serde_json::to_vec(request)
+        .map(Bytes::from)
+        .map_err(OpenAIError::JSONSerialize)
+}
+
+/// Deserialize a response body read with `bytes()`.
+#[cfg(any(feature = "azure-ad", feature = "_api"))]
+pub(crate) fn deserialize<O: DeserializeOwned>(bytes: &[u8]) -> Result<O, OpenAIError> {
+    serde_json::from_slice(bytes).map_err(OpenAIError::JSONDeserialize)
+}
+
+/// Append the little-endian `f32`s of `encoded`, an embedding requested with `encoding_format`
+/// base64, to `out`. `bytes` is a buffer which can be reused for the next embedding.
+pub(crate) fn decode_f32_le(
+    encoded: &str,
+    bytes: &mut Vec<u8>,
+    out: &mut Vec<f32>,
+) -> Result<(), String> {
+    bytes.clear();
+    general_purpose::STANDARD
+        .decode_vec(encoded, bytes)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() % 4 != 0 {
+        return Err(format!(
+            "base64 embedding of {} bytes is not a whole number of f32",
+            bytes.len()
+        ));
+    }
+    out.extend(
+        bytes
+            .chunks_exact(4)
+            .map(|le| f32::from_le_bytes([le[0], le[1], le[2], le[3]])),
+    );
+    Ok(())
+}
+
+/// Incrementally splits the elements of the array in one field of a JSON object,
+/// such as `data` in list responses, out of a body received in chunks.
+///
+/// Only the element being received is buffered, so memory use does not grow with the
+/// length of the array.
+#[cfg(feature = "files")]
+pub(crate) struct JsonArrayItems {
+    field: &'static [u8],
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Contents of the last string at the top level of the object, a candidate field name
+    string: Vec<u8>,
+    /// Set when the last top level string was followed by `:`
+    field_matched: bool,
+    in_array: bool,
+    array_done: bool,
+    item: Vec<u8>,
+}
+
+#[cfg(feature = "files")]
+impl JsonArrayItems {
+    pub(crate) fn new(field: &'static str) -> Self {
+        Self {
+            field: field.as_bytes(),
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            string: Vec::new(),
+            field_matched: false,
+            in_array: false,
+            array_done: false,
+            item: Vec::new(),
+        }
+    }
+
+    /// Whether the whole array has been received.
+    pub(crate) fn is_done(&self) -> bool {
+        self.array_done
+    }
+
+    /// Feed the next chunk of the body, passing each element completed by it to `item`.
+    pub(crate) fn push(&mut self, chunk: &[u8], mut item: impl FnMut(&[u8])) {
+        for &b in chunk {
+            if self.in_string {
+                if self.in_array {
+                    self.item.push(b);
+                } else if self.depth == 1 {
+                    self.string.push(b);
+                }
+
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                    if !self.in_array && self.depth == 1 {
+                        self.string.pop();
+                    }
+                }
+                continue;
+            }
 
-pub(crate) async fn file_stream_body<P: AsRef<Path>>(path: P) -> Result<Body, OpenAIError> {
-    let file = tokio::fs::File::open(path.as_ref())
-        .await
-        .map_err(|e| OpenAIError::FileReadError(e.to_string()))?;
-    let stream = FramedRead::new(file, BytesCodec::new());
-    let body = Body::wrap_stream(stream);
-    Ok(body)
+            if self.in_array {
+                let in_item = !self.item.is_empty();
+                match b {
+                    // Scalar elements end at the separator or at the end of the array
+                    b',' | b']' if self.depth == 2 => {
+                        if in_item {
+                            item(&self.item);
+                            self.item.clear();
+                        }
+                        if b == b']' {
+                            self.depth = 1;
+                            self.in_array = false;
+                            self.array_done = true;
+                        }
+                    }
+                    b' ' | b'\t' | b'\n' | b'\r' if !in_item => {}
+                    _ => {
+                        self.item.push(b);
+                        match b {
+                            b'"' => self.in_string = true,
+                            b'{' | b'[' => self.depth += 1,
+                            b'}' | b']' => {
+                                self.depth -= 1;
+                                if self.depth == 2 {
+                                    item(&self.item);
+                                    self.item.clear();
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                continue;
+            }
+
+            match b {
+                b'"' => {
+                    self.in_string = true;
+                    self.field_matched = false;
+                    self.string.clear();
+                }
+                b':' if self.depth == 1 => self.field_matched = self.string == self.field,
+                b'[' if self.depth == 1 && self.field_matched && !self.array_done => {
+                    self.depth += 1;
+                    self.in_array = true;
+                }
+                b'{' | b'[' => {
+                    self.depth += 1;
+                    self.field_matched = false;
+                }
+                b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+                b' ' | b'\t' | b'\n' | b'\r' => {}
+                _ => self.field_matched = false,
+            }
+        }
+    }
+}
+
+/// Gathers the items of a pagination stream, failing with [OpenAIError::TooManyItems] as soon
+/// as there are more than `max_items`, before fetching further pages.
+#[cfg(feature = "files")]
+pub(crate) async fn collect_all<T>(
+    mut items: BoxStream<'static, Result<T, OpenAIError>>,
+    max_items: usize,
+) -> Result<Vec<T>, OpenAIError> {
+    let mut collected = vec![];
+    while let Some(item) = items.next().await {
+        let item = item?;
+        if collected.len() == max_items {
+            return Err(OpenAIError::TooManyItems(max_items));
+        }
+        collected.push(item);
+    }
+    Ok(collected)
 }
 
-/// Creates the part for the given image file for multipart upload.
-pub(crate) async fn create_file_part<P: AsRef<Path>>(
-    path: P,
+/// Creates the part for the given file or in-memory bytes for multipart upload.
+#[cfg(feature = "multipart")]
+pub(crate) async fn create_file_part(
+    source: &InputSource,
 ) -> Result<reqwest::multipart::Part, OpenAIError> {
-    let file_name = path
-        .as_ref()
-        .file_name()
-        .ok_or_else(|| {
-            OpenAIError::FileReadError(format!(
-                "cannot extract file name from {}",
-                path.as_ref().display()
-            ))
-        })?
-        .to_str()
-        .unwrap()
-        .to_string();
-
-    let file_part = reqwest::multipart::Part::stream(file_stream_body(path.as_ref()).await?)
+    let (file_name, body) = match source {
+        #[cfg(not(target_arch = "wasm32"))]
+        InputSource::Path { path } => {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| {
+                    OpenAIError::FileReadError(format!(
+                        "cannot extract file name from {}",
+                        path.display()
+                    ))
+                })?
+                .to_str()
+                .unwrap()
+                .to_string();
+
+            let body = fs::body(path)
+                .await
+                .map_err(|e| OpenAIError::FileReadError(e.to_string()))?;
+            (file_name, body)
+        }
+        #[cfg(target_arch = "wasm32")]
+        InputSource::Path { path } => {
+            return Err(OpenAIError::FileReadError(format!(
+                "cannot read {} on wasm32, upload the file from memory instead",
+                path.display()
+            )))
+        }
+        InputSource::Bytes { filename, bytes } => (filename.clone(), Body::from(bytes.clone())),
+    };
+
+    let file_part = reqwest::multipart::Part::stream(body)
         .file_name(file_name)
         .mime_str("application/octet-stream")
         .unwrap();
 
     Ok(file_part)
 }
+
+#[cfg(test)]
+#[cfg(feature = "files")]
+mod tests {
+    use super::collect_all;
+    #[cfg(feature = "files")]
+    use super::JsonArrayItems;
+    use crate::error::OpenAIError;
+
+    #[tokio::test]
+    async fn test_collect_all() {
+        let items = || Box::pin(futures::stream::iter([1, 2, 3].map(Ok))) as _;
+
+        assert_eq!(collect_all(items(), 3).await.unwrap(), [1, 2, 3]);
+        assert!(matches!(
+            collect_all(items(), 2).await,
+            Err(OpenAIError::TooManyItems(2))
+        ));
+
+        // An error after the last item is returned instead of TooManyItems
+        let failing = Box::pin(futures::stream::iter([
+            Ok(1),
+            Ok(2),
+            Err(OpenAIError::InvalidArgument("page".into())),
+        ])) as _;
+        assert!(matches!(
+            collect_all(failing, 2).await,
+            Err(OpenAIError::InvalidArgument(_))
+        ));
+    }
+
+    #[cfg(feature = "files")]
+    #[test]
+    fn test_json_array_items() {
+        let json = r#"{"object": "list", "first_id": "a", "data": [
+            {"id": "a", "text": "brackets ] } in \"strings\" [", "nested": {"list": [1, 2]}},
+            {"id": "b", "data": []},
+            [1, "x"], 3, "four", null
+        ], "has_more": false}"#;
+
+        for chunk_size in [1, 2, 5, 13, json.len()] {
+            let mut items = JsonArrayItems::new("data");
+            let mut parsed = Vec::new();
+            for chunk in json.as_bytes().chunks(chunk_size) {
+                items.push(chunk, |item| {
+                    parsed.push(serde_json::from_slice::<serde_json::Value>(item).unwrap())
+                });
+            }
+
+            assert!(items.is_done());
+            assert_eq!(
+                parsed,
+                vec![
+                    serde_json::json!({"id": "a", "text": "brackets ] } in \"strings\" [", "nested": {"list": [1, 2]}}),
+                    serde_json::json!({"id": "b", "data": []}),
+                    serde_json::json!([1, "x"]),
+                    serde_json::json!(3),
+                    serde_json::json!("four"),
+                    serde_json::json!(null),
+                ]
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg(feature = "completions")]
 
 use futures::StreamExt;
 use futures::future::{BoxFuture, FutureExt};
@@ -39,6 +40,6 @@ async fn boxed_future_test() {
     let mut stream = client.completions().create_stream(request).await.unwrap();
 
     let result = interpret_bool(&mut stream).await;
-    assert_eq!(result, true);
+    assert!(result);
 
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 //! This test is primarily to make sure that macros_rules for From traits are correct.
+#![cfg(feature = "completions")]
 use async_openai::types::Prompt;
 
 fn prompt_input<T>(input: T) -> Prompt
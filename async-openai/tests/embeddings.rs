@@ -1,4 +1,5 @@
 //! This test is primarily to make sure that macros_rules for From traits are correct.
+#![cfg(feature = "embeddings")]
 use async_openai::types::EmbeddingInput;
 
 fn embedding_input<T>(input: T) -> EmbeddingInput